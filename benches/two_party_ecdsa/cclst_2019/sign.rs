@@ -57,14 +57,16 @@ mod bench {
                     &eph_ec_key_pair_party2,
                     &eph_party_one_first_message.public_share,
                     &message,
-                );
+                )
+                .expect("bad R1");
 
                 let signature = party_one::Signature::compute(
                     &party1_private,
                     partial_sig.c3,
                     &eph_ec_key_pair_party1,
                     &eph_party_two_second_message.comm_witness.public_share,
-                );
+                )
+                .expect("bad R2");
 
                 let pubkey =
                     party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
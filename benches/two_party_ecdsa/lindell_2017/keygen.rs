@@ -13,7 +13,8 @@ mod bench {
                 let (party_one_first_message, comm_witness, ec_key_pair_party1) =
                     party_one::KeyGenFirstMsg::create_commitments_with_fixed_secret_share(
                         ECScalar::from(&BigInt::sample(253)),
-                    );
+                    )
+                    .expect("secret share out of range");
                 let (party_two_first_message, _ec_key_pair_party2) =
                     party_two::KeyGenFirstMsg::create_with_fixed_secret_share(ECScalar::from(
                         &BigInt::from(10),
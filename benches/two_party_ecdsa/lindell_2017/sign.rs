@@ -45,7 +45,8 @@ mod bench {
                     &eph_ec_key_pair_party2,
                     &eph_party_one_first_message.public_share,
                     &message,
-                );
+                )
+                .expect("bad R1");
 
                 let party1_private =
                     party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
@@ -55,7 +56,8 @@ mod bench {
                     &partial_sig.c3,
                     &eph_ec_key_pair_party1,
                     &eph_party_two_second_message.comm_witness.public_share,
-                );
+                )
+                .expect("bad R2");
 
                 let pubkey = party_one::compute_pubkey(
                     &party1_private,
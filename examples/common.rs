@@ -215,3 +215,11 @@ pub fn check_sig(r: &FE, s: &FE, msg: &BigInt, pk: &GE) {
     let is_correct = verify(&msg, &secp_sig, &pk);
     assert!(is_correct);
 }
+
+/// Password of the sealed key file, read from the KEYS_PASSWORD environment variable.
+#[allow(dead_code)]
+pub fn keys_password() -> Vec<u8> {
+    env::var("KEYS_PASSWORD")
+        .expect("set KEYS_PASSWORD to the password of the key file")
+        .into_bytes()
+}
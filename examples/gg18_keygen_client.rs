@@ -10,17 +10,21 @@ use curv::{
     elliptic::curves::traits::{ECPoint, ECScalar},
     BigInt, FE, GE,
 };
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2018::key_share::LocalKeyShare;
 use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2018::party_i::{
     KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, Parameters,
 };
+use multi_party_ecdsa::utilities::key_store::SealedKeyStore;
+use multi_party_ecdsa::utilities::zk_no_small_factor::NoSmallFactorProof;
 use paillier::EncryptionKey;
 use reqwest::Client;
 use std::{env, fs, time};
+use zk_paillier::zkproofs::DLogStatement;
 
 mod common;
 use common::{
-    aes_decrypt, aes_encrypt, broadcast, poll_for_broadcasts, poll_for_p2p, postb, sendp2p, Params,
-    PartySignup, AEAD,
+    aes_decrypt, aes_encrypt, broadcast, keys_password, poll_for_broadcasts, poll_for_p2p, postb,
+    sendp2p, Params, PartySignup, AEAD,
 };
 
 fn main() {
@@ -80,12 +84,15 @@ fn main() {
 
     bc1_vec.insert(party_num_int as usize - 1, bc_i);
 
-    // send ephemeral public keys and check commitments correctness
+    // send ephemeral public keys and no small factor proofs, check commitments correctness
+    let no_small_factor_proofs = party_keys
+        .phase3_prove_no_small_factor(&bc1_vec)
+        .expect("invalid dlog statement");
     assert!(broadcast(
         &client,
         party_num_int,
         "round2",
-        serde_json::to_string(&decom_i).unwrap(),
+        serde_json::to_string(&(decom_i.clone(), no_small_factor_proofs.clone())).unwrap(),
         uuid.clone()
     )
     .is_ok());
@@ -101,15 +108,19 @@ fn main() {
     let mut j = 0;
     let mut point_vec: Vec<GE> = Vec::new();
     let mut decom_vec: Vec<KeyGenDecommitMessage1> = Vec::new();
+    let mut no_small_factor_proofs_vec: Vec<Vec<NoSmallFactorProof>> = Vec::new();
     let mut enc_keys: Vec<BigInt> = Vec::new();
     for i in 1..=PARTIES {
         if i == party_num_int {
             point_vec.push(decom_i.y_i);
             decom_vec.push(decom_i.clone());
+            no_small_factor_proofs_vec.push(no_small_factor_proofs.clone());
         } else {
-            let decom_j: KeyGenDecommitMessage1 = serde_json::from_str(&round2_ans_vec[j]).unwrap();
+            let (decom_j, proofs_j): (KeyGenDecommitMessage1, Vec<NoSmallFactorProof>) =
+                serde_json::from_str(&round2_ans_vec[j]).unwrap();
             point_vec.push(decom_j.y_i);
             decom_vec.push(decom_j.clone());
+            no_small_factor_proofs_vec.push(proofs_j);
             enc_keys.push((decom_j.y_i.clone() * party_keys.u_i).x_coor().unwrap());
             j = j + 1;
         }
@@ -123,6 +134,14 @@ fn main() {
             &params, &decom_vec, &bc1_vec,
         )
         .expect("invalid key");
+    party_keys
+        .phase3_verify_no_small_factor(
+            &params,
+            &bc1_vec,
+            &no_small_factor_proofs_vec,
+            party_num_int as usize,
+        )
+        .expect("invalid no small factor proof");
 
     //////////////////////////////////////////////////////////////////////////////
 
@@ -248,16 +267,23 @@ fn main() {
         .map(|i| bc1_vec[i as usize].e.clone())
         .collect::<Vec<EncryptionKey>>();
 
-    let keygen_json = serde_json::to_string(&(
+    let h1_h2_N_tilde_vec = (0..PARTIES)
+        .map(|i| bc1_vec[i as usize].dlog_statement.clone())
+        .collect::<Vec<DLogStatement>>();
+
+    let key_share = LocalKeyShare::new(
         party_keys,
         shared_keys,
         party_num_int,
         vss_scheme_vec,
         paillier_key_vec,
+        h1_h2_N_tilde_vec,
         y_sum,
-    ))
-    .unwrap();
-    fs::write(env::args().nth(2).unwrap(), keygen_json).expect("Unable to save !");
+    )
+    .expect("invalid key share");
+    let key_store = SealedKeyStore::seal(&key_share, &keys_password(), "gg18 key share")
+        .expect("Unable to seal keys");
+    fs::write(env::args().nth(2).unwrap(), key_store.to_json()).expect("Unable to save !");
 }
 
 pub fn signup(client: &Client) -> Result<PartySignup, ()> {
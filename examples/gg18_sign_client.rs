@@ -3,24 +3,27 @@
 use curv::{
     cryptographic_primitives::{
         proofs::sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof,
-        proofs::sigma_dlog::DLogProof, secret_sharing::feldman_vss::VerifiableSS,
+        proofs::sigma_dlog::DLogProof,
     },
     elliptic::curves::traits::ECScalar,
-    BigInt, FE, GE,
+    BigInt, FE,
 };
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2018::key_share::LocalKeyShare;
 use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2018::party_i::{
     Keys, LocalSignature, PartyPrivate, Phase5ADecom1, Phase5Com1, Phase5Com2, Phase5DDecom2,
-    SharedKeys, SignBroadcastPhase1, SignDecommitPhase1, SignKeys,
+    SignBroadcastPhase1, SignDecommitPhase1, SignKeys,
 };
+use multi_party_ecdsa::utilities::key_store::SealedKeyStore;
 use multi_party_ecdsa::utilities::mta::*;
 
-use paillier::EncryptionKey;
 use reqwest::Client;
 use std::{env, fs, time};
+use zeroize::Zeroizing;
 
 mod common;
 use common::{
-    broadcast, check_sig, poll_for_broadcasts, poll_for_p2p, postb, sendp2p, Params, PartySignup,
+    broadcast, check_sig, keys_password, poll_for_broadcasts, poll_for_p2p, postb, sendp2p, Params,
+    PartySignup,
 };
 
 #[allow(clippy::cognitive_complexity)]
@@ -41,16 +44,38 @@ fn main() {
     // delay:
     let delay = time::Duration::from_millis(25);
     // read key file
-    let data = fs::read_to_string(env::args().nth(2).unwrap())
-        .expect("Unable to load keys, did you run keygen first? ");
-    let (party_keys, shared_keys, party_id, vss_scheme_vec, paillier_key_vector, y_sum): (
-        Keys,
-        SharedKeys,
-        u16,
-        Vec<VerifiableSS>,
-        Vec<EncryptionKey>,
-        GE,
-    ) = serde_json::from_str(&data).unwrap();
+    let data = Zeroizing::new(
+        fs::read_to_string(env::args().nth(2).unwrap())
+            .expect("Unable to load keys, did you run keygen first? "),
+    );
+    // the share goes through LocalKeyShare::from_json, which checks its version and contents
+    let key_share = match SealedKeyStore::from_json(&data) {
+        Ok(key_store) => {
+            let plaintext = key_store
+                .open_bytes(&keys_password())
+                .expect("Unable to open keys, wrong password?");
+            let json = std::str::from_utf8(&plaintext).expect("Unable to decode keys");
+            LocalKeyShare::from_json(json)
+        }
+        // key files written before the key store was added are not sealed
+        Err(_) => LocalKeyShare::from_json(&data),
+    }
+    .expect("Unable to decode keys");
+    // the range proofs of the MtA need the ring-Pedersen parameters of every party
+    assert!(
+        !key_share.needs_upgrade(),
+        "key share has no ring-Pedersen parameters, upgrade it with LocalKeyShare::upgrade"
+    );
+    let LocalKeyShare {
+        party_keys,
+        shared_keys,
+        party_index: party_id,
+        vss_scheme_vec,
+        paillier_key_vec: paillier_key_vector,
+        h1_h2_N_tilde_vec,
+        y: y_sum,
+        ..
+    } = key_share;
 
     //read parameters:
     let data = fs::read_to_string("params.json")
@@ -106,12 +131,19 @@ fn main() {
     let xi_com_vec = Keys::get_commitments_to_xi(&vss_scheme_vec);
     //////////////////////////////////////////////////////////////////////////////
     let (com, decommit) = sign_keys.phase1_broadcast();
-    let (m_a_k, _) = MessageA::a(&sign_keys.k_i, &party_keys.ek);
+    // one message a for every other signer, with a range proof against its parameters
+    let m_a_k_vec = (1..THRESHOLD + 2)
+        .filter(|&i| i != party_num_int)
+        .map(|i| {
+            let statement = &h1_h2_N_tilde_vec[signers_vec[(i - 1) as usize]];
+            MessageAWithRangeProof::a(&sign_keys.k_i, &party_keys.ek, statement).0
+        })
+        .collect::<Vec<MessageAWithRangeProof>>();
     assert!(broadcast(
         &client,
         party_num_int,
         "round1",
-        serde_json::to_string(&(com.clone(), m_a_k.clone())).unwrap(),
+        serde_json::to_string(&(com.clone(), m_a_k_vec.clone())).unwrap(),
         uuid.clone()
     )
     .is_ok());
@@ -126,7 +158,7 @@ fn main() {
 
     let mut j = 0;
     let mut bc1_vec: Vec<SignBroadcastPhase1> = Vec::new();
-    let mut m_a_vec: Vec<MessageA> = Vec::new();
+    let mut m_a_vec: Vec<MessageAWithRangeProof> = Vec::new();
 
     for i in 1..THRESHOLD + 2 {
         if i == party_num_int {
@@ -134,10 +166,17 @@ fn main() {
         //   m_a_vec.push(m_a_k.clone());
         } else {
             //     if signers_vec.contains(&(i as usize)) {
-            let (bc1_j, m_a_party_j): (SignBroadcastPhase1, MessageA) =
+            let (bc1_j, mut m_a_party_j): (SignBroadcastPhase1, Vec<MessageAWithRangeProof>) =
                 serde_json::from_str(&round1_ans_vec[j]).unwrap();
+            // party i skips itself in the vector of messages a
+            let pos = if party_num_int < i {
+                party_num_int - 1
+            } else {
+                party_num_int - 2
+            };
+            assert_eq!(m_a_party_j.len(), THRESHOLD as usize);
             bc1_vec.push(bc1_j);
-            m_a_vec.push(m_a_party_j);
+            m_a_vec.push(m_a_party_j.swap_remove(pos as usize));
 
             j += 1;
             //       }
@@ -146,23 +185,31 @@ fn main() {
     assert_eq!(signers_vec.len(), bc1_vec.len());
 
     //////////////////////////////////////////////////////////////////////////////
-    let mut m_b_gamma_send_vec: Vec<MessageB> = Vec::new();
+    let statement_i = &h1_h2_N_tilde_vec[signers_vec[(party_num_int - 1) as usize]];
+    let mut m_b_gamma_send_vec: Vec<MessageBWithRangeProof> = Vec::new();
     let mut beta_vec: Vec<FE> = Vec::new();
-    let mut m_b_w_send_vec: Vec<MessageB> = Vec::new();
+    let mut m_b_w_send_vec: Vec<MessageBWithRangeProof> = Vec::new();
     let mut ni_vec: Vec<FE> = Vec::new();
     let mut j = 0;
     for i in 1..THRESHOLD + 2 {
         if i != party_num_int {
-            let (m_b_gamma, beta_gamma, _) = MessageB::b(
+            let statement_j = &h1_h2_N_tilde_vec[signers_vec[(i - 1) as usize]];
+            let (m_b_gamma, beta_gamma, _) = MessageBWithRangeProof::b_with_check(
                 &sign_keys.gamma_i,
                 &paillier_key_vector[signers_vec[(i - 1) as usize]],
-                m_a_vec[j].clone(),
-            );
-            let (m_b_w, beta_wi, _) = MessageB::b(
+                &m_a_vec[j],
+                statement_j,
+                statement_i,
+            )
+            .expect("bad range proof of k_j");
+            let (m_b_w, beta_wi, _) = MessageBWithRangeProof::b_with_check(
                 &sign_keys.w_i,
                 &paillier_key_vector[signers_vec[(i - 1) as usize]],
-                m_a_vec[j].clone(),
-            );
+                &m_a_vec[j],
+                statement_j,
+                statement_i,
+            )
+            .expect("bad range proof of k_j");
             m_b_gamma_send_vec.push(m_b_gamma);
             m_b_w_send_vec.push(m_b_w);
             beta_vec.push(beta_gamma);
@@ -197,12 +244,12 @@ fn main() {
         uuid.clone(),
     );
 
-    let mut m_b_gamma_rec_vec: Vec<MessageB> = Vec::new();
-    let mut m_b_w_rec_vec: Vec<MessageB> = Vec::new();
+    let mut m_b_gamma_rec_vec: Vec<MessageBWithRangeProof> = Vec::new();
+    let mut m_b_w_rec_vec: Vec<MessageBWithRangeProof> = Vec::new();
 
     for i in 0..THRESHOLD {
         //  if signers_vec.contains(&(i as usize)) {
-        let (m_b_gamma_i, m_b_w_i): (MessageB, MessageB) =
+        let (m_b_gamma_i, m_b_w_i): (MessageBWithRangeProof, MessageBWithRangeProof) =
             serde_json::from_str(&round2_ans_vec[i as usize]).unwrap();
         m_b_gamma_rec_vec.push(m_b_gamma_i);
         m_b_w_rec_vec.push(m_b_w_i);
@@ -215,30 +262,44 @@ fn main() {
     let mut j = 0;
     for i in 1..THRESHOLD + 2 {
         if i != party_num_int {
-            let m_b = m_b_gamma_rec_vec[j].clone();
-
-            let alpha_ij_gamma = m_b
-                .verify_proofs_get_alpha(&party_keys.dk, &sign_keys.k_i)
-                .expect("wrong dlog or m_b");
-            let m_b = m_b_w_rec_vec[j].clone();
-            let alpha_ij_wi = m_b
-                .verify_proofs_get_alpha(&party_keys.dk, &sign_keys.k_i)
-                .expect("wrong dlog or m_b");
-            alpha_vec.push(alpha_ij_gamma);
-            miu_vec.push(alpha_ij_wi);
+            let c_a = &m_a_k_vec[j].c;
+            let alpha_ij_gamma = m_b_gamma_rec_vec[j]
+                .verify_proofs_get_alpha_with_check(
+                    &party_keys.dk,
+                    &party_keys.ek,
+                    c_a,
+                    statement_i,
+                    &m_b_gamma_rec_vec[j].b_proof.pk,
+                )
+                .expect("wrong range proof or m_b");
+            // w_j used in the MtAwc must match the public commitment to x_j
             let g_w_i = Keys::update_commitments_to_xi(
                 &xi_com_vec[signers_vec[(i - 1) as usize]],
                 &vss_scheme_vec[signers_vec[(i - 1) as usize]],
                 signers_vec[(i - 1) as usize],
                 &signers_vec,
             );
-            assert_eq!(m_b.b_proof.pk, g_w_i);
+            let alpha_ij_wi = m_b_w_rec_vec[j]
+                .verify_proofs_get_alpha_with_check(
+                    &party_keys.dk,
+                    &party_keys.ek,
+                    c_a,
+                    statement_i,
+                    &g_w_i,
+                )
+                .expect("wrong range proof or m_b");
+            alpha_vec.push(alpha_ij_gamma);
+            miu_vec.push(alpha_ij_wi);
             j += 1;
         }
     }
     //////////////////////////////////////////////////////////////////////////////
-    let delta_i = sign_keys.phase2_delta_i(&alpha_vec, &beta_vec);
-    let sigma = sign_keys.phase2_sigma_i(&miu_vec, &ni_vec);
+    let delta_i = sign_keys
+        .phase2_delta_i(&alpha_vec, &beta_vec)
+        .expect("bad delta_i");
+    let sigma = sign_keys
+        .phase2_sigma_i(&miu_vec, &ni_vec)
+        .expect("bad sigma_i");
 
     assert!(broadcast(
         &client,
@@ -263,7 +324,7 @@ fn main() {
         delta_i,
         &mut delta_vec,
     );
-    let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec);
+    let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec).expect("bad delta");
 
     //////////////////////////////////////////////////////////////////////////////
     // decommit to gamma_i
@@ -305,7 +366,8 @@ fn main() {
     // we assume the message is already hashed (by the signer).
     let message_bn = BigInt::from(message);
     let local_sig =
-        LocalSignature::phase5_local_sig(&sign_keys.k_i, &message_bn, &R, &sigma, &y_sum);
+        LocalSignature::phase5_local_sig(&sign_keys.k_i, &message_bn, &R, &sigma, &y_sum)
+            .expect("bad R");
 
     let (phase5_com, phase_5a_decom, helgamal_proof, dlog_proof_rho) =
         local_sig.phase5a_broadcast_5b_zkproof();
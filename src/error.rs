@@ -0,0 +1,200 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+use std::fmt;
+
+/// Protocol (or sub protocol) in which a check failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Gg18Keygen,
+    Gg18Sign,
+    Gg18Refresh,
+    Gg18Reshare,
+    Gg18Recover,
+    Gg18Import,
+    Gg18Reconstruct,
+    Gg18Convert,
+    Gg20Sign,
+    Lindell17Keygen,
+    Lindell17Sign,
+    Cclst19Keygen,
+    Cclst19Sign,
+    MtA,
+    Pdl,
+    PdlWithSlack,
+    PaillierBlumModulus,
+    NoSmallFactor,
+    Bip32,
+    Backup,
+    KeyShare,
+    KeyStore,
+    SignatureEncoding,
+}
+
+/// The check that failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidCommitment,
+    InvalidCorrectKeyProof,
+    InvalidPaillierBlumModulusProof,
+    InvalidNoSmallFactorProof,
+    /// paillier modulus below the minimum bit length
+    PaillierModulusTooSmall,
+    /// ring-Pedersen parameters (N_tilde, h1, h2) that are not proven well formed
+    InvalidCompositeDLogProof,
+    InvalidDLogProof,
+    InvalidHomoElGamalProof,
+    InvalidRangeProof,
+    InvalidPdlProof,
+    InvalidClDlProof,
+    /// centipede proof that the backup does not encrypt the secret of the public share
+    InvalidBackupProof,
+    InvalidMtaProof,
+    InvalidSecretShare,
+    /// fewer distinct shares than the threshold plus one
+    TooFewShares,
+    /// threshold t that is not below n, or zero where the protocol needs t > 0
+    InvalidThreshold,
+    BadSum,
+    /// delta_i that does not match the MtA values revealed after a failed check
+    InvalidDelta,
+    InvalidSignature,
+    /// input vectors of unexpected length
+    LengthMismatch,
+    PointAtInfinity,
+    /// a sum of shares that is zero where it must be inverted
+    ZeroSum,
+    /// hardened child index, which needs the private key
+    HardenedDerivation,
+    /// child key outside the curve order or at infinity, BIP32 skips to the next index
+    InvalidChildKey,
+    /// stored data that does not decode, or decodes to an inconsistent key share
+    InvalidEncoding,
+    /// format version written by a newer release of the crate
+    UnsupportedVersion,
+    /// ciphertext that does not authenticate, a wrong password or tampered data
+    AuthenticationFailed,
+    /// presignature whose id is already in the set of used ids
+    ReusedPresignature,
+}
+
+/// Error returned by every protocol of the crate.
+///
+/// `round` names the protocol step as it appears in the function names, e.g. `"phase5c"`.
+/// `parties` lists the parties responsible for the failure, when the check can tell. For the
+/// multi party protocols these are positions in the input vectors of the failing function, for
+/// the two party protocols they are the party numbers (1 or 2).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub protocol: Protocol,
+    pub round: &'static str,
+    pub kind: ErrorKind,
+    pub parties: Vec<usize>,
+}
+
+impl Error {
+    pub fn new(protocol: Protocol, round: &'static str, kind: ErrorKind) -> Self {
+        Error {
+            protocol,
+            round,
+            kind,
+            parties: Vec::new(),
+        }
+    }
+
+    pub fn with_parties(self, parties: Vec<usize>) -> Self {
+        Error { parties, ..self }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Protocol::Gg18Keygen => "gg18 keygen",
+            Protocol::Gg18Sign => "gg18 signing",
+            Protocol::Gg18Refresh => "gg18 refresh",
+            Protocol::Gg18Reshare => "gg18 resharing",
+            Protocol::Gg18Recover => "gg18 share recovery",
+            Protocol::Gg18Import => "gg18 key import",
+            Protocol::Gg18Reconstruct => "gg18 key reconstruction",
+            Protocol::Gg18Convert => "gg18 key conversion",
+            Protocol::Gg20Sign => "gg20 signing",
+            Protocol::Lindell17Keygen => "lindell17 keygen",
+            Protocol::Lindell17Sign => "lindell17 signing",
+            Protocol::Cclst19Keygen => "cclst19 keygen",
+            Protocol::Cclst19Sign => "cclst19 signing",
+            Protocol::MtA => "MtA",
+            Protocol::Pdl => "PDL",
+            Protocol::PdlWithSlack => "PDL with slack",
+            Protocol::PaillierBlumModulus => "Paillier-Blum modulus",
+            Protocol::NoSmallFactor => "no small factor",
+            Protocol::Bip32 => "BIP32 derivation",
+            Protocol::Backup => "verifiable backup",
+            Protocol::KeyShare => "key share encoding",
+            Protocol::KeyStore => "sealed key store",
+            Protocol::SignatureEncoding => "signature encoding",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ErrorKind::InvalidCommitment => "invalid decommitment",
+            ErrorKind::InvalidCorrectKeyProof => "invalid paillier correct key proof",
+            ErrorKind::InvalidPaillierBlumModulusProof => "invalid Paillier-Blum modulus proof",
+            ErrorKind::InvalidNoSmallFactorProof => "invalid no small factor proof",
+            ErrorKind::PaillierModulusTooSmall => "paillier modulus too small",
+            ErrorKind::InvalidCompositeDLogProof => "invalid composite dlog proof",
+            ErrorKind::InvalidDLogProof => "invalid dlog proof",
+            ErrorKind::InvalidHomoElGamalProof => "invalid homomorphic elgamal proof",
+            ErrorKind::InvalidRangeProof => "invalid range proof",
+            ErrorKind::InvalidPdlProof => "invalid PDL proof",
+            ErrorKind::InvalidClDlProof => "invalid CL-DL proof",
+            ErrorKind::InvalidBackupProof => "invalid backup proof",
+            ErrorKind::InvalidMtaProof => "invalid MtA proof",
+            ErrorKind::InvalidSecretShare => "invalid secret share",
+            ErrorKind::TooFewShares => "too few shares",
+            ErrorKind::InvalidThreshold => "invalid threshold",
+            ErrorKind::BadSum => "sum check failed",
+            ErrorKind::InvalidDelta => "invalid delta_i",
+            ErrorKind::InvalidSignature => "invalid signature",
+            ErrorKind::LengthMismatch => "mismatched input lengths",
+            ErrorKind::PointAtInfinity => "point at infinity",
+            ErrorKind::ZeroSum => "sum of shares is zero",
+            ErrorKind::HardenedDerivation => "hardened derivation needs the private key",
+            ErrorKind::InvalidChildKey => "invalid child key",
+            ErrorKind::InvalidEncoding => "invalid encoding",
+            ErrorKind::UnsupportedVersion => "unsupported version",
+            ErrorKind::AuthenticationFailed => "authentication failed",
+            ErrorKind::ReusedPresignature => "presignature already used",
+        };
+        f.write_str(description)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: {}", self.protocol, self.round, self.kind)?;
+        if !self.parties.is_empty() {
+            write!(f, " (parties {:?})", self.parties)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
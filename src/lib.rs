@@ -16,11 +16,6 @@
 
 pub mod protocols;
 pub mod utilities;
-#[derive(Copy, PartialEq, Eq, Clone, Debug)]
-pub enum Error {
-    InvalidKey,
-    InvalidSS,
-    InvalidCom,
-    InvalidSig,
-    Phase5BadSum,
-}
+
+mod error;
+pub use error::{Error, ErrorKind, Protocol};
@@ -0,0 +1,302 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Self-describing file format of a gg18 key share.
+//!
+//! Every encoding carries a format version. Decoding accepts all versions written by earlier
+//! releases, including the positional JSON tuple of the keygen example (version 0), and
+//! rejects versions it does not know instead of guessing. Version 0 shares lack the
+//! ring-Pedersen parameters that signing needs, LocalKeyShare::upgrade adds them.
+
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::{BigInt, FE, GE};
+use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::DLogStatement;
+
+use super::party_i::{Keys, SharedKeys};
+use super::state_machine::keygen::LocalKey;
+use crate::utilities::secret::zeroize_decryption_key;
+use crate::Error;
+use crate::ErrorKind::{InvalidEncoding, LengthMismatch, UnsupportedVersion};
+use crate::Protocol::KeyShare;
+
+pub const KEY_SHARE_VERSION: u16 = 1;
+pub const CURVE: &str = "secp256k1";
+pub const PROTOCOL: &str = "gg18";
+/// prefix of the binary encoding, followed by the version as a big endian u16
+pub const MAGIC: &[u8; 4] = b"MPKS";
+
+/// Version 0: (party_keys, shared_keys, party_index, vss_scheme_vec, paillier_key_vec, y)
+type LegacyKeyShare = (
+    LegacyKeys,
+    SharedKeys,
+    u16,
+    Vec<VerifiableSS>,
+    Vec<EncryptionKey>,
+    GE,
+);
+
+/// Keys as written by version 0, before the ring-Pedersen parameters were added.
+#[derive(Deserialize)]
+struct LegacyKeys {
+    u_i: FE,
+    y_i: GE,
+    dk: DecryptionKey,
+    ek: EncryptionKey,
+    party_index: usize,
+}
+
+impl Zeroize for LegacyKeys {
+    fn zeroize(&mut self) {
+        self.u_i.zeroize();
+        zeroize_decryption_key(&mut self.dk);
+    }
+}
+
+impl Drop for LegacyKeys {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl LegacyKeys {
+    /// The ring-Pedersen parameters are left zero until LocalKeyShare::upgrade.
+    fn upgrade(&self) -> Keys {
+        Keys {
+            u_i: self.u_i,
+            y_i: self.y_i,
+            dk: self.dk.clone(),
+            ek: self.ek.clone(),
+            party_index: self.party_index,
+            N_tilde: BigInt::zero(),
+            h1: BigInt::zero(),
+            h2: BigInt::zero(),
+            xhi: BigInt::zero(),
+            xhi_inv: BigInt::zero(),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalKeyShare {
+    pub version: u16,
+    pub curve: String,
+    pub protocol: String,
+    pub threshold: u16,
+    pub share_count: u16,
+    /// 1-based index of the party
+    pub party_index: u16,
+    pub y: GE,
+    pub vss_scheme_vec: Vec<VerifiableSS>,
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    /// ring-Pedersen parameters of every party, empty for shares stored in version 0, which
+    /// need LocalKeyShare::upgrade before they can sign
+    pub h1_h2_N_tilde_vec: Vec<DLogStatement>,
+    pub party_keys: Keys,
+    pub shared_keys: SharedKeys,
+}
+
+impl LocalKeyShare {
+    /// The threshold parameters are taken from the vss schemes, which must not be empty.
+    #[allow(non_snake_case)]
+    pub fn new(
+        party_keys: Keys,
+        shared_keys: SharedKeys,
+        party_index: u16,
+        vss_scheme_vec: Vec<VerifiableSS>,
+        paillier_key_vec: Vec<EncryptionKey>,
+        h1_h2_N_tilde_vec: Vec<DLogStatement>,
+        y: GE,
+    ) -> Result<Self, Error> {
+        let parameters = match vss_scheme_vec.first() {
+            Some(vss_scheme) => &vss_scheme.parameters,
+            None => return Err(Error::new(KeyShare, "encode", LengthMismatch)),
+        };
+        Ok(LocalKeyShare {
+            version: KEY_SHARE_VERSION,
+            curve: CURVE.to_string(),
+            protocol: PROTOCOL.to_string(),
+            threshold: parameters.threshold as u16,
+            share_count: parameters.share_count as u16,
+            party_index,
+            y,
+            vss_scheme_vec,
+            paillier_key_vec,
+            h1_h2_N_tilde_vec,
+            party_keys,
+            shared_keys,
+        })
+    }
+
+    /// Whether the share was stored in version 0 and needs upgrade before it can sign.
+    pub fn needs_upgrade(&self) -> bool {
+        self.h1_h2_N_tilde_vec.is_empty()
+    }
+
+    /// Adds the ring-Pedersen parameters to a share stored in version 0. All parties of the key
+    /// run it together: each one creates party_keys with Keys::create_from on its u_i and
+    /// party index, and the parties exchange and check them with Keys::verify_new_party_keys,
+    /// whose output is passed here. The paillier keys are replaced as well, the shares of the
+    /// key are unchanged.
+    pub fn upgrade(
+        &self,
+        party_keys: Keys,
+        paillier_key_vec: Vec<EncryptionKey>,
+        h1_h2_N_tilde_vec: Vec<DLogStatement>,
+    ) -> Result<Self, Error> {
+        let i = usize::from(self.party_index).saturating_sub(1);
+        let consistent = self.needs_upgrade()
+            && party_keys.y_i == self.party_keys.y_i
+            && paillier_key_vec.get(i).map(|ek| &ek.n) == Some(&party_keys.ek.n)
+            && h1_h2_N_tilde_vec.get(i).map(|st| &st.N) == Some(&party_keys.N_tilde);
+        if !consistent {
+            return Err(Error::new(KeyShare, "upgrade", InvalidEncoding));
+        }
+        let key_share = LocalKeyShare::new(
+            party_keys,
+            self.shared_keys.clone(),
+            self.party_index,
+            self.vss_scheme_vec.clone(),
+            paillier_key_vec,
+            h1_h2_N_tilde_vec,
+            self.y,
+        )?;
+        key_share.check()?;
+        Ok(key_share)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("key share serializes to JSON")
+    }
+
+    /// Decodes the JSON of any known version, upgrading it to the current one.
+    pub fn from_json(data: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(data).map_err(|_| invalid_encoding())?;
+        let key_share = match value {
+            Value::Array(_) => LocalKeyShare::from_legacy(value)?,
+            Value::Object(ref fields) => {
+                match fields.get("version").and_then(Value::as_u64) {
+                    Some(version) if version == u64::from(KEY_SHARE_VERSION) => (),
+                    Some(_) => return Err(Error::new(KeyShare, "decode", UnsupportedVersion)),
+                    None => return Err(invalid_encoding()),
+                }
+                serde_json::from_value(value).map_err(|_| invalid_encoding())?
+            }
+            _ => return Err(invalid_encoding()),
+        };
+        key_share.check()?;
+        Ok(key_share)
+    }
+
+    fn from_legacy(value: Value) -> Result<Self, Error> {
+        let legacy: LegacyKeyShare =
+            serde_json::from_value(value).map_err(|_| invalid_encoding())?;
+        let (party_keys, shared_keys, party_index, vss_scheme_vec, paillier_key_vec, y) = legacy;
+        if vss_scheme_vec.is_empty() {
+            return Err(invalid_encoding());
+        }
+        LocalKeyShare::new(
+            party_keys.upgrade(),
+            shared_keys,
+            party_index,
+            vss_scheme_vec,
+            paillier_key_vec,
+            Vec::new(),
+            y,
+        )
+    }
+
+    /// MAGIC, the version as a big endian u16 and the JSON encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_be_bytes());
+        bytes.extend_from_slice(self.to_json().as_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < MAGIC.len() + 2 || bytes[..MAGIC.len()] != MAGIC[..] {
+            return Err(invalid_encoding());
+        }
+        let version = u16::from_be_bytes([bytes[MAGIC.len()], bytes[MAGIC.len() + 1]]);
+        if version != KEY_SHARE_VERSION {
+            return Err(Error::new(KeyShare, "decode", UnsupportedVersion));
+        }
+        let json =
+            std::str::from_utf8(&bytes[MAGIC.len() + 2..]).map_err(|_| invalid_encoding())?;
+        LocalKeyShare::from_json(json)
+    }
+
+    /// Checks that the decoded share is for this curve and protocol and is consistent.
+    fn check(&self) -> Result<(), Error> {
+        let n = self.share_count as usize;
+        let consistent = self.curve == CURVE
+            && self.protocol == PROTOCOL
+            && self.threshold < self.share_count
+            && self.party_index >= 1
+            && self.party_index <= self.share_count
+            && self.vss_scheme_vec.len() == n
+            && self.paillier_key_vec.len() == n
+            && (self.h1_h2_N_tilde_vec.is_empty() || self.h1_h2_N_tilde_vec.len() == n)
+            && self.shared_keys.y == self.y;
+        if consistent {
+            Ok(())
+        } else {
+            Err(invalid_encoding())
+        }
+    }
+}
+
+fn invalid_encoding() -> Error {
+    Error::new(KeyShare, "decode", InvalidEncoding)
+}
+
+impl TryFrom<LocalKey> for LocalKeyShare {
+    type Error = Error;
+
+    fn try_from(local_key: LocalKey) -> Result<Self, Error> {
+        LocalKeyShare::new(
+            local_key.party_keys,
+            local_key.shared_keys,
+            local_key.i,
+            local_key.vss_scheme_vec,
+            local_key.paillier_key_vec,
+            local_key.h1_h2_N_tilde_vec,
+            local_key.y_sum,
+        )
+    }
+}
+
+impl From<LocalKeyShare> for LocalKey {
+    fn from(key_share: LocalKeyShare) -> Self {
+        LocalKey {
+            party_keys: key_share.party_keys,
+            shared_keys: key_share.shared_keys,
+            i: key_share.party_index,
+            t: key_share.threshold,
+            n: key_share.share_count,
+            vss_scheme_vec: key_share.vss_scheme_vec,
+            paillier_key_vec: key_share.paillier_key_vec,
+            h1_h2_N_tilde_vec: key_share.h1_h2_N_tilde_vec,
+            y_sum: key_share.y,
+        }
+    }
+}
@@ -1,8 +1,11 @@
+#![allow(non_snake_case)]
+
 use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
-    verify, Keys, LocalSignature, Parameters,
-    PartyPrivate, Phase5ADecom1, Phase5Com1, SharedKeys, SignKeys,
+    reconstruct_private_key, verify, KeyGenBroadcastMessage1, Keys, LocalSignature, Parameters,
+    PartyPrivate, Phase5ADecom1, Phase5Com1, PreSignature, SharedKeys, SignKeys, SignatureRecid,
 };
-use crate::utilities::mta::{MessageA, MessageB};
+use crate::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+use crate::utilities::mta::{MessageAWithRangeProof, MessageBWithRangeProof};
 
 use curv::arithmetic::traits::Converter;
 use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
@@ -12,8 +15,14 @@ use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
 use curv::elliptic::curves::traits::*;
 use curv::{FE, GE};
 use paillier::*;
-
-pub fn keygen_t_n_parties(t: u16, n: u16) -> (Vec<Keys>, Vec<SharedKeys>, Vec<GE>, GE, VerifiableSS) {
+use std::collections::HashSet;
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::DLogStatement;
+
+pub fn keygen_t_n_parties(
+    t: u16,
+    n: u16,
+) -> (Vec<Keys>, Vec<SharedKeys>, Vec<GE>, GE, Vec<VerifiableSS>) {
     let parames = Parameters {
         threshold: t,
         share_count: n,
@@ -46,6 +55,15 @@ pub fn keygen_t_n_parties(t: u16, n: u16) -> (Vec<Keys>, Vec<SharedKeys>, Vec<GE
         })
         .collect();
 
+    let no_small_factor_proofs_vec = party_keys_vec
+        .iter()
+        .map(|k| k.phase3_prove_no_small_factor(&bc1_vec).expect("invalid dlog statement"))
+        .collect::<Vec<_>>();
+    for (i, k) in party_keys_vec.iter().enumerate() {
+        k.phase3_verify_no_small_factor(&parames, &bc1_vec, &no_small_factor_proofs_vec, i + 1)
+            .expect("invalid no small factor proof");
+    }
+
     for (vss_scheme, secret_shares, index) in vss_result {
         vss_scheme_vec.push(vss_scheme);
         secret_shares_vec.push(secret_shares); // cannot unzip
@@ -87,57 +105,375 @@ pub fn keygen_t_n_parties(t: u16, n: u16) -> (Vec<Keys>, Vec<SharedKeys>, Vec<GE
     Keys::verify_dlog_proofs(&parames, &dlog_proof_vec, &y_vec).expect("bad dlog proof");
 
     //test
-    let xi_vec = (0..=t).map(|i| shared_keys_vec[i].x_i).collect::<Vec<FE>>();
-    let x = vss_scheme_for_test[0]
-        .clone()
-        .reconstruct(&index_vec[0..=t], &xi_vec);
+    let x = reconstruct_private_key(
+        &y_sum,
+        &vss_scheme_for_test,
+        &index_vec[0..=t],
+        &shared_keys_vec[0..=t],
+    )
+    .expect("invalid shares");
     let sum_u_i = party_keys_vec.iter().fold(FE::zero(), |acc, x| acc + x.u_i);
-    assert_eq!(x, sum_u_i);
+    assert_eq!(*x, sum_u_i);
 
     (
         party_keys_vec,
         shared_keys_vec,
         pk_vec,
         y_sum,
-        vss_scheme_for_test[0].clone(),
+        vss_scheme_for_test,
     )
 }
 
-pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
+/// Runs a proactive refresh on the output of keygen, rotating the paillier keys if
+/// rotate_paillier is set. Returns the new keys, shared keys and vss schemes.
+pub fn refresh_t_n_parties(
+    t: u16,
+    n: u16,
+    party_keys_vec: &[Keys],
+    shared_keys_vec: &[SharedKeys],
+    vss_scheme_vec: &[VerifiableSS],
+    rotate_paillier: bool,
+) -> (Vec<Keys>, Vec<SharedKeys>, Vec<VerifiableSS>) {
+    let params = Parameters {
+        threshold: t,
+        share_count: n,
+    };
+    let n = n as usize;
+    // the keygen broadcasts carry the proofs of the ring-Pedersen parameters
+    let bc1_vec = if rotate_paillier {
+        party_keys_vec
+            .iter()
+            .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key().0)
+            .collect::<Vec<KeyGenBroadcastMessage1>>()
+    } else {
+        Vec::new()
+    };
+    let ek_vec = party_keys_vec
+        .iter()
+        .map(|k| k.ek.clone())
+        .collect::<Vec<EncryptionKey>>();
+
+    let mut bc_vec = Vec::new();
+    let mut secret_shares_vec = Vec::new();
+    let mut new_keys_vec = Vec::new();
+    for k in party_keys_vec {
+        let bc1_vec = if rotate_paillier {
+            Some(&bc1_vec[..])
+        } else {
+            None
+        };
+        let (bc, secret_shares, new_keys) = k
+            .refresh_phase1_distribute(&params, bc1_vec)
+            .expect("invalid refresh");
+        bc_vec.push(bc);
+        secret_shares_vec.push(secret_shares);
+        new_keys_vec.push(new_keys);
+    }
+
+    let mut new_shared_keys_vec = Vec::new();
+    let mut new_vss_scheme_vec = Vec::new();
+    for i in 0..n {
+        let party_shares = (0..n).map(|j| secret_shares_vec[j][i]).collect::<Vec<FE>>();
+        let (shared_keys, updated_vss_scheme_vec, new_ek_vec) = new_keys_vec[i]
+            .refresh_phase2_verify_update(
+                &params,
+                &bc_vec,
+                &party_shares,
+                &shared_keys_vec[i],
+                vss_scheme_vec,
+                &ek_vec,
+                i + 1,
+            )
+            .expect("invalid refresh");
+        assert_eq!(shared_keys.y, shared_keys_vec[i].y);
+        assert_eq!(
+            Keys::get_commitments_to_xi(&updated_vss_scheme_vec)[i],
+            GE::generator() * shared_keys.x_i
+        );
+        for (ek, k) in new_ek_vec.iter().zip(&new_keys_vec) {
+            assert_eq!(ek.n, k.ek.n);
+        }
+        new_shared_keys_vec.push(shared_keys);
+        new_vss_scheme_vec = updated_vss_scheme_vec;
+    }
+
+    (new_keys_vec, new_shared_keys_vec, new_vss_scheme_vec)
+}
+
+/// Reshares the keygen output of the parties in s to a new group of new_n parties with
+/// threshold new_t. Returns the output of the new parties in the shape of keygen_t_n_parties.
+pub fn reshare_t_n_parties(
+    shared_keys_vec: &[SharedKeys],
+    vss_scheme_vec: &[VerifiableSS],
+    s: &[usize],
+    new_t: u16,
+    new_n: u16,
+) -> (Vec<Keys>, Vec<SharedKeys>, Vec<GE>, GE, Vec<VerifiableSS>) {
+    let new_params = Parameters {
+        threshold: new_t,
+        share_count: new_n,
+    };
+    let party_keys_vec = create_new_party_keys(&new_params);
+    let (bc_vec, secret_shares_vec): (Vec<_>, Vec<_>) = s
+        .iter()
+        .map(|&i| {
+            shared_keys_vec[i]
+                .reshare_distribute(&vss_scheme_vec[0], i, s, &new_params)
+                .expect("invalid signers")
+        })
+        .unzip();
+
+    let y = shared_keys_vec[s[0]].y;
+    let mut new_shared_keys_vec = Vec::new();
+    let mut new_vss_scheme_vec = Vec::new();
+    for j in 0..new_n as usize {
+        let party_shares = secret_shares_vec
+            .iter()
+            .map(|secret_shares| secret_shares[j])
+            .collect::<Vec<FE>>();
+        let (shared_keys, vss_scheme_vec) = SharedKeys::reshare_verify_construct(
+            &y,
+            vss_scheme_vec,
+            s,
+            &bc_vec,
+            &party_shares,
+            &new_params,
+            j + 1,
+        )
+        .expect("invalid resharing");
+        new_shared_keys_vec.push(shared_keys);
+        new_vss_scheme_vec = vss_scheme_vec;
+    }
+    let pk_vec = Keys::get_commitments_to_xi(&new_vss_scheme_vec);
+    for (pk, shared_keys) in pk_vec.iter().zip(&new_shared_keys_vec) {
+        assert_eq!(*pk, GE::generator() * shared_keys.x_i);
+    }
+
+    (
+        party_keys_vec,
+        new_shared_keys_vec,
+        pk_vec,
+        y,
+        new_vss_scheme_vec,
+    )
+}
+
+// the parties that join in resharing or conversion create paillier keys and ring-Pedersen
+// parameters and check those of each other as in keygen
+fn create_new_party_keys(params: &Parameters) -> Vec<Keys> {
+    let party_keys_vec = (0..params.share_count as usize)
+        .map(Keys::create)
+        .collect::<Vec<Keys>>();
+    let (bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key())
+        .unzip();
+    let no_small_factor_proofs_vec = party_keys_vec
+        .iter()
+        .map(|k| {
+            k.phase3_prove_no_small_factor(&bc1_vec)
+                .expect("invalid dlog statement")
+        })
+        .collect::<Vec<_>>();
+    for (i, k) in party_keys_vec.iter().enumerate() {
+        let (ek_vec, statement_vec) = k
+            .verify_new_party_keys(
+                params,
+                &decom_vec,
+                &bc1_vec,
+                &no_small_factor_proofs_vec,
+                i + 1,
+            )
+            .expect("invalid keys of a new party");
+        assert_eq!(ek_vec[i].n, k.ek.n);
+        assert_eq!(statement_vec[i].N, k.N_tilde);
+    }
+    party_keys_vec
+}
+
+/// The helpers in s rebuild the share of the party with index recover_index.
+pub fn recover_share(
+    shared_keys_vec: &[SharedKeys],
+    vss_scheme_vec: &[VerifiableSS],
+    s: &[usize],
+    recover_index: usize,
+) -> SharedKeys {
+    let (bc_vec, pieces_vec): (Vec<_>, Vec<_>) = s
+        .iter()
+        .map(|&i| {
+            shared_keys_vec[i]
+                .recovery_phase1_split(&vss_scheme_vec[0], i, s, recover_index)
+                .expect("invalid helpers")
+        })
+        .unzip();
+
+    let sum_vec = (0..s.len())
+        .map(|p| {
+            let received = pieces_vec
+                .iter()
+                .map(|pieces| pieces[p])
+                .collect::<Vec<FE>>();
+            SharedKeys::recovery_phase2_combine(
+                vss_scheme_vec,
+                s,
+                recover_index,
+                &bc_vec,
+                &received,
+                p,
+            )
+            .expect("invalid pieces")
+        })
+        .collect::<Vec<FE>>();
+
+    let y = shared_keys_vec[s[0]].y;
+    SharedKeys::recovery_phase3_construct(&y, vss_scheme_vec, s, recover_index, &bc_vec, &sum_vec)
+        .expect("invalid recovery")
+}
+
+/// Runs lindell 2017 keygen and converts the key to a (t, n) key. Returns the output of the
+/// new parties in the shape of keygen_t_n_parties, y is the public key of the lindell key.
+pub fn convert_lindell_t_n_parties(
+    t: u16,
+    n: u16,
+) -> (Vec<Keys>, Vec<SharedKeys>, Vec<GE>, GE, Vec<VerifiableSS>) {
+    let (_party_one_first_message, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (party_two_first_message, ec_key_pair_party2) = party_two::KeyGenFirstMsg::create();
+    let paillier_key_pair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party_one_private =
+        party_one::Party1Private::set_private_key(&ec_key_pair_party1, &paillier_key_pair);
+    let party_two_private = party_two::Party2Private::set_private_key(&ec_key_pair_party2);
+    let y = party_one::compute_pubkey(&party_one_private, &party_two_first_message.public_share);
+
+    // MtA on x1 * x2, party two answers the encryption of x1 from keygen
+    let (message_b, beta) = party_two_private
+        .to_mta_message_b(&paillier_key_pair.ek, &paillier_key_pair.encrypted_share);
+    let alpha = party_one_private
+        .to_mta_message_b(message_b)
+        .expect("invalid MtA");
+
+    let params = Parameters {
+        threshold: t,
+        share_count: n,
+    };
+    let party_keys_vec = create_new_party_keys(&params);
+    let (bc_vec, secret_shares_vec): (Vec<_>, Vec<_>) = [alpha, beta]
+        .iter()
+        .map(|u_i| SharedKeys::convert_additive_distribute(u_i, &params))
+        .unzip();
+
+    let mut shared_keys_vec = Vec::new();
+    let mut vss_scheme_vec = Vec::new();
+    for j in 0..n as usize {
+        let party_shares = secret_shares_vec
+            .iter()
+            .map(|secret_shares| secret_shares[j])
+            .collect::<Vec<FE>>();
+        let (shared_keys, new_vss_scheme_vec) = SharedKeys::convert_additive_verify_construct(
+            &y,
+            &bc_vec,
+            &party_shares,
+            &params,
+            j + 1,
+        )
+        .expect("invalid conversion");
+        shared_keys_vec.push(shared_keys);
+        vss_scheme_vec = new_vss_scheme_vec;
+    }
+    let pk_vec = Keys::get_commitments_to_xi(&vss_scheme_vec);
+    for (pk, shared_keys) in pk_vec.iter().zip(&shared_keys_vec) {
+        assert_eq!(*pk, GE::generator() * shared_keys.x_i);
+    }
+
+    (party_keys_vec, shared_keys_vec, pk_vec, y, vss_scheme_vec)
+}
+
+/// Runs the message independent part of signing and returns the presignature of every signer
+/// together with the joint public key.
+pub fn presign(t: u16, n: u16, ttag: u16, s: &[usize]) -> (Vec<PreSignature>, GE) {
     // full key gen emulation
-    let (party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme) = keygen_t_n_parties(t, n);
+    let (party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(t, n);
 
-    let private_vec = (0..shared_keys_vec.len())
-        .map(|i| PartyPrivate::set_private(party_keys_vec[i].clone(), shared_keys_vec[i].clone()))
-        .collect::<Vec<PartyPrivate>>();
     // make sure that we have t<t'<n and the group s contains id's for t' parties
     // TODO: make sure s has unique id's and they are all in range 0..n
     // TODO: make sure this code can run when id's are not in ascending order
     assert!(ttag > t);
-    let ttag = ttag as usize;
-    assert_eq!(s.len(), ttag);
+    assert_eq!(s.len(), ttag as usize);
+    let presignature_vec =
+        presign_with_keys(&party_keys_vec, &shared_keys_vec, &vss_scheme_vec[0], s);
+    (presignature_vec, y)
+}
+
+/// The message independent part of signing by the parties in s, with keys from keygen or
+/// any of the protocols that produce keys of the same shape.
+pub fn presign_with_keys(
+    party_keys_vec: &[Keys],
+    shared_keys_vec: &[SharedKeys],
+    vss_scheme: &VerifiableSS,
+    s: &[usize],
+) -> Vec<PreSignature> {
+    let y = shared_keys_vec[s[0]].y;
+    let (sign_keys_vec, sigma_vec, R_vec) =
+        sign_phase1_to_phase4(party_keys_vec, shared_keys_vec, vss_scheme, s);
+    sign_keys_vec
+        .into_iter()
+        .enumerate()
+        .map(|(i, sign_keys)| PreSignature::create(sign_keys, sigma_vec[i], R_vec[i], y))
+        .collect::<Vec<PreSignature>>()
+}
+
+// phases 1 to 4 of signing by the parties in s, returns the signing keys, sigma_i and R of
+// every signer
+fn sign_phase1_to_phase4(
+    party_keys_vec: &[Keys],
+    shared_keys_vec: &[SharedKeys],
+    vss_scheme: &VerifiableSS,
+    s: &[usize],
+) -> (Vec<SignKeys>, Vec<FE>, Vec<GE>) {
+    let private_vec = (0..shared_keys_vec.len())
+        .map(|i| PartyPrivate::set_private(party_keys_vec[i].clone(), shared_keys_vec[i].clone()))
+        .collect::<Vec<PartyPrivate>>();
+    let ttag = s.len();
 
     // each party creates a signing key. This happens in parallel IRL. In this test we
     // create a vector of signing keys, one for each party.
     // throughout i will index parties
     let sign_keys_vec = (0..ttag)
-        .map(|i| SignKeys::create(&private_vec[s[i]], &vss_scheme, s[i], &s))
+        .map(|i| SignKeys::create(&private_vec[s[i]], vss_scheme, s[i], s))
         .collect::<Vec<SignKeys>>();
 
     // each party computes [Ci,Di] = com(g^gamma_i) and broadcast the commitments
     let (bc1_vec, decommit_vec1): (Vec<_>, Vec<_>) =
         sign_keys_vec.iter().map(|k| k.phase1_broadcast()).unzip();
 
-    // each party i sends encryption of k_i under her Paillier key
-    // m_a_vec = [ma_0;ma_1;,...]
-    let m_a_vec: Vec<_> = sign_keys_vec
+    // ring-Pedersen parameters of the signers, the range proofs are made against them
+    let statement_vec = s
         .iter()
-        .enumerate()
-        .map(|(i, k)| MessageA::a(&k.k_i, &party_keys_vec[s[i]].ek).0)
-        .collect();
+        .map(|&i| party_keys_vec[i].dlog_statement())
+        .collect::<Vec<DLogStatement>>();
+
+    // each party i sends encryption of k_i under her Paillier key to every other party j,
+    // with a range proof against the parameters of j
+    // m_a_vec_all[i][j] is the message of party i to party j, the own entry is skipped
+    let m_a_vec_all = (0..ttag)
+        .map(|i| {
+            (0..ttag)
+                .filter(|&j| j != i)
+                .map(|j| {
+                    MessageAWithRangeProof::a(
+                        &sign_keys_vec[i].k_i,
+                        &party_keys_vec[s[i]].ek,
+                        &statement_vec[j],
+                    )
+                    .0
+                })
+                .collect::<Vec<MessageAWithRangeProof>>()
+        })
+        .collect::<Vec<_>>();
 
     // each party i sends responses to m_a_vec she received (one response with input gamma_i and one with w_i)
-    // m_b_gamma_vec_all is a matrix where column i is a vector of message_b's that party i answers to all ma_{j!=i} using paillier key of party j to answer to ma_j
+    // m_b_gamma_vec_all is a matrix where row i is a vector of message_b's that party i answers to all ma_{j!=i} using paillier key of party j to answer to ma_j
 
     // aggregation of the n messages of all parties
     let mut m_b_gamma_vec_all = Vec::new();
@@ -153,31 +489,42 @@ pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
 
         for j in 0..ttag - 1 {
             let ind = if j < i { j } else { j + 1 };
+            let m_a = &m_a_vec_all[ind][skip_pos(i, ind)];
+            let alice_ek = &party_keys_vec[s[ind]].ek;
 
-            let (m_b_gamma, beta_gamma, _) = MessageB::b(
+            // the MtAwc with gamma_i, checked against the g^gamma_i that b_proof reveals
+            let (m_b_gamma, beta_gamma, _) = MessageBWithRangeProof::b_with_check(
                 &key.gamma_i,
-                &party_keys_vec[s[ind]].ek,
-                m_a_vec[ind].clone(),
-            );
-            let (m_b_w, beta_wi, _) =
-                MessageB::b(&key.w_i, &party_keys_vec[s[ind]].ek, m_a_vec[ind].clone());
+                alice_ek,
+                m_a,
+                &statement_vec[ind],
+                &statement_vec[i],
+            )
+            .expect("bad range proof of k_i");
+            // the MtAwc with w_i, checked by the receiver against the public W_i = g^w_i
+            let (m_b_w, beta_wi, _) = MessageBWithRangeProof::b_with_check(
+                &key.w_i,
+                alice_ek,
+                m_a,
+                &statement_vec[ind],
+                &statement_vec[i],
+            )
+            .expect("bad range proof of k_i");
 
             m_b_gamma_vec.push(m_b_gamma);
             beta_vec.push(beta_gamma);
             m_b_w_vec.push(m_b_w);
             ni_vec.push(beta_wi);
         }
-        m_b_gamma_vec_all.push(m_b_gamma_vec.clone());
-        beta_vec_all.push(beta_vec.clone());
-        m_b_w_vec_all.push(m_b_w_vec.clone());
-        ni_vec_all.push(ni_vec.clone());
+        m_b_gamma_vec_all.push(m_b_gamma_vec);
+        beta_vec_all.push(beta_vec);
+        m_b_w_vec_all.push(m_b_w_vec);
+        ni_vec_all.push(ni_vec);
     }
 
-    // Here we complete the MwA protocols by taking the mb matrices and starting with the first column generating the appropriate message
-    // for example for index i=0 j=0 we need party at index s[1] to answer to mb that party s[0] sent, completing a protocol between s[0] and s[1].
-    //  for index i=1 j=0 we need party at index s[0] to answer to mb that party s[1]. etc.
+    // Here we complete the MtA protocols: party i takes the answers that every other party j
+    // sent to the message a of i and decrypts them with her own Paillier key.
     // IRL each party i should get only the mb messages that other parties sent in response to the party i ma's.
-    // TODO: simulate as IRL
     let mut alpha_vec_all = Vec::new();
     let mut miu_vec_all = Vec::new();
 
@@ -185,90 +532,160 @@ pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
         let mut alpha_vec = Vec::new();
         let mut miu_vec = Vec::new();
 
-        let m_b_gamma_vec_i = &m_b_gamma_vec_all[i];
-        let m_b_w_vec_i = &m_b_w_vec_all[i];
-
         for j in 0..ttag - 1 {
             let ind = if j < i { j } else { j + 1 };
-            let m_b = m_b_gamma_vec_i[j].clone();
-
-            let alpha_ij_gamma = m_b
-                .verify_proofs_get_alpha(&party_keys_vec[s[ind]].dk, &sign_keys_vec[ind].k_i)
-                .expect("wrong dlog or m_b");
-            let m_b = m_b_w_vec_i[j].clone();
-            let alpha_ij_wi = m_b
-                .verify_proofs_get_alpha(&party_keys_vec[s[ind]].dk, &sign_keys_vec[ind].k_i)
-                .expect("wrong dlog or m_b");
-
-            // since we actually run two MtAwc each party needs to make sure that the values B are the same as the public values
-            // here for b=w_i the parties already know W_i = g^w_i  for each party so this check is done here. for b = gamma_i the check will be later when g^gamma_i will become public
-            // currently we take the W_i from the other parties signing keys
+            let pos = skip_pos(i, ind);
+            let c_a = &m_a_vec_all[i][j].c;
+            let dk = &party_keys_vec[s[i]].dk;
+            let ek = &party_keys_vec[s[i]].ek;
+
+            let m_b_gamma = &m_b_gamma_vec_all[ind][pos];
+            let alpha_ij_gamma = m_b_gamma
+                .verify_proofs_get_alpha_with_check(
+                    dk,
+                    ek,
+                    c_a,
+                    &statement_vec[i],
+                    &m_b_gamma.b_proof.pk,
+                )
+                .expect("wrong range proof or m_b");
+            // the values B of the MtAwc must be the public W_j = g^w_j
+            // currently we take the W_j from the other parties signing keys
             // TODO: use pk_vec (first change from x_i to w_i) for this check.
-            assert_eq!(m_b.b_proof.pk, sign_keys_vec[i].g_w_i);
+            let alpha_ij_wi = m_b_w_vec_all[ind][pos]
+                .verify_proofs_get_alpha_with_check(
+                    dk,
+                    ek,
+                    c_a,
+                    &statement_vec[i],
+                    &sign_keys_vec[ind].g_w_i,
+                )
+                .expect("wrong range proof or m_b");
 
             alpha_vec.push(alpha_ij_gamma);
             miu_vec.push(alpha_ij_wi);
         }
-        alpha_vec_all.push(alpha_vec.clone());
-        miu_vec_all.push(miu_vec.clone());
+        alpha_vec_all.push(alpha_vec);
+        miu_vec_all.push(miu_vec);
     }
 
     let mut delta_vec = Vec::new();
     let mut sigma_vec = Vec::new();
 
     for i in 0..ttag {
-        let delta = sign_keys_vec[i].phase2_delta_i(&alpha_vec_all[i], &beta_vec_all[i]);
-        let sigma = sign_keys_vec[i].phase2_sigma_i(&miu_vec_all[i], &ni_vec_all[i]);
+        let delta = sign_keys_vec[i]
+            .phase2_delta_i(&alpha_vec_all[i], &beta_vec_all[i])
+            .expect("bad delta");
+        let sigma = sign_keys_vec[i]
+            .phase2_sigma_i(&miu_vec_all[i], &ni_vec_all[i])
+            .expect("bad sigma");
         delta_vec.push(delta);
         sigma_vec.push(sigma);
     }
+    beta_vec_all.zeroize();
+    ni_vec_all.zeroize();
+    miu_vec_all.zeroize();
 
     // all parties broadcast delta_i and compute delta_i ^(-1)
-    let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec);
+    let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec).expect("bad delta");
 
-    // de-commit to g^gamma_i from phase1, test comm correctness, and that it is the same value used in MtA.
+    // de-commit to g^gamma_i from phase1 and test comm correctness.
     // Return R
 
-    let _g_gamma_i_vec = (0..ttag)
-        .map(|i| sign_keys_vec[i].g_gamma_i)
-        .collect::<Vec<GE>>();
-
+    let b_proof_vec = (0..ttag)
+        .map(|i| &m_b_gamma_vec_all[i][0].b_proof)
+        .collect::<Vec<&DLogProof>>();
     let R_vec = (0..ttag)
         .map(|_| {
-            // each party i tests all B = g^b = g ^ gamma_i she received.
-            let b_proof_vec = (0..ttag)
-                .map(|j| {
-                    let b_gamma_vec = &m_b_gamma_vec_all[j];
-                    &b_gamma_vec[0].b_proof
-                })
-                .collect::<Vec<&DLogProof>>();
             SignKeys::phase4(&delta_inv, &b_proof_vec, decommit_vec1.clone(), &bc1_vec)
                 .expect("bad gamma_i decommit")
         })
         .collect::<Vec<GE>>();
 
+    (sign_keys_vec, sigma_vec, R_vec)
+}
+
+// position of signer j in a vector over all signers that skips signer i
+fn skip_pos(j: usize, i: usize) -> usize {
+    if j < i {
+        j
+    } else {
+        j - 1
+    }
+}
+
+pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
+    // full key gen emulation
+    let (party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(t, n);
+
+    // make sure that we have t<t'<n and the group s contains id's for t' parties
+    // TODO: make sure s has unique id's and they are all in range 0..n
+    // TODO: make sure this code can run when id's are not in ascending order
+    assert!(ttag > t);
+    let ttag = ttag as usize;
+    assert_eq!(s.len(), ttag);
+
+    let (sign_keys_vec, sigma_vec, R_vec) =
+        sign_phase1_to_phase4(&party_keys_vec, &shared_keys_vec, &vss_scheme_vec[0], &s);
+
     let message: [u8; 4] = [79, 77, 69, 82];
     let message_bn = HSha256::create_hash(&[&BigInt::from(&message[..])]);
-    let mut local_sig_vec = Vec::new();
 
     // each party computes s_i but don't send it yet. we start with phase5
-    for i in 0..ttag {
-        let local_sig = LocalSignature::phase5_local_sig(
-            &sign_keys_vec[i].k_i,
-            &message_bn,
-            &R_vec[i],
-            &sigma_vec[i],
-            &y,
-        );
-        local_sig_vec.push(local_sig);
-    }
+    let local_sig_vec = (0..ttag)
+        .map(|i| {
+            LocalSignature::phase5_local_sig(
+                &sign_keys_vec[i].k_i,
+                &message_bn,
+                &R_vec[i],
+                &sigma_vec[i],
+                &y,
+            )
+            .expect("bad R")
+        })
+        .collect::<Vec<LocalSignature>>();
 
+    let sig = phase5_output_signature(&local_sig_vec, &R_vec[0]);
+    assert_eq!(local_sig_vec[0].y, y);
+    verify(&sig, &local_sig_vec[0].y, &local_sig_vec[0].m).unwrap();
+    assert_eq!(sig.recover_public_key(&local_sig_vec[0].m).unwrap(), y);
+    check_sig(&sig.r, &sig.s, &local_sig_vec[0].m, &y);
+}
+
+/// Signs a test message with the presignatures of all signers and verifies the signature
+/// against y.
+pub fn sign_with_presignatures(presignature_vec: Vec<PreSignature>, y: &GE) {
+    let R = presignature_vec[0].R();
+
+    let message: [u8; 4] = [79, 77, 69, 82];
+    let message_bn = HSha256::create_hash(&[&BigInt::from(&message[..])]);
+    let mut used_ids = HashSet::new();
+    let local_sig_vec = presignature_vec
+        .into_iter()
+        .map(|presignature| {
+            presignature
+                .sign(&message_bn, &mut used_ids)
+                .expect("bad R")
+        })
+        .collect::<Vec<LocalSignature>>();
+
+    let sig = phase5_output_signature(&local_sig_vec, &R);
+    assert_eq!(local_sig_vec[0].y, *y);
+    verify(&sig, &local_sig_vec[0].y, &local_sig_vec[0].m).unwrap();
+    assert_eq!(sig.recover_public_key(&local_sig_vec[0].m).unwrap(), *y);
+    check_sig(&sig.r, &sig.s, &local_sig_vec[0].m, y);
+}
+
+/// Runs phase5a to phase5d for the local signatures of all signers and outputs the signature of
+/// party i=0.
+pub fn phase5_output_signature(local_sig_vec: &[LocalSignature], R: &GE) -> SignatureRecid {
+    let ttag = local_sig_vec.len();
     let mut phase5_com_vec: Vec<Phase5Com1> = Vec::new();
     let mut phase_5a_decom_vec: Vec<Phase5ADecom1> = Vec::new();
     let mut helgamal_proof_vec = Vec::new();
     let mut dlog_proof_rho_vec = Vec::new();
     // we notice that the proof for V= R^sg^l, B = A^l is a general form of homomorphic elgamal.
-    for sig in &local_sig_vec {
+    for sig in local_sig_vec {
         let (phase5_com, phase_5a_decom, helgamal_proof, dlog_proof_rho) =
             sig.phase5a_broadcast_5b_zkproof();
         phase5_com_vec.push(phase5_com);
@@ -283,19 +700,21 @@ pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
         let mut phase_5a_decom_vec_clone = phase_5a_decom_vec.clone();
         let mut phase_5a_com_vec_clone = phase5_com_vec.clone();
         let mut phase_5b_elgamal_vec_clone = helgamal_proof_vec.clone();
+        let mut dlog_proof_rho_vec_clone = dlog_proof_rho_vec.clone();
 
         let _decom_i = phase_5a_decom_vec_clone.remove(i);
         let _com_i = phase_5a_com_vec_clone.remove(i);
         let _elgamal_i = phase_5b_elgamal_vec_clone.remove(i);
+        let _dlog_proof_rho_i = dlog_proof_rho_vec_clone.remove(i);
         //        for j in 0..s_minus_i.len() {
         let (phase5_com2, phase_5d_decom2) = local_sig_vec[i]
             .phase5c(
                 &phase_5a_decom_vec_clone,
                 &phase_5a_com_vec_clone,
                 &phase_5b_elgamal_vec_clone,
-                &dlog_proof_rho_vec,
+                &dlog_proof_rho_vec_clone,
                 &phase_5a_decom_vec[i].V_i,
-                &R_vec[0],
+                R,
             )
             .expect("error phase5");
         phase5_com2_vec.push(phase5_com2);
@@ -305,7 +724,7 @@ pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
 
     // assuming phase5 checks passes each party sends s_i and compute sum_i{s_i}
     let mut s_vec: Vec<FE> = Vec::new();
-    for sig in &local_sig_vec {
+    for sig in local_sig_vec {
         let s_i = sig
             .phase5d(&phase_5d_decom2_vec, &phase5_com2_vec, &phase_5a_decom_vec)
             .expect("bad com 5d");
@@ -314,13 +733,9 @@ pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
 
     // here we compute the signature only of party i=0 to demonstrate correctness.
     s_vec.remove(0);
-    let sig = local_sig_vec[0]
+    local_sig_vec[0]
         .output_signature(&s_vec)
-        .expect("verification failed");
-
-    assert_eq!(local_sig_vec[0].y, y);
-    verify(&sig, &local_sig_vec[0].y, &local_sig_vec[0].m).unwrap();
-    check_sig(&sig.r, &sig.s, &local_sig_vec[0].m, &y);
+        .expect("verification failed")
 }
 
 pub fn check_sig(r: &FE, s: &FE, msg: &BigInt, pk: &GE) {
@@ -14,8 +14,10 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 
+pub mod key_share;
 pub mod party_i;
 
 pub mod mock;
+pub mod state_machine;
 #[cfg(test)]
 mod test;
@@ -31,12 +31,34 @@ use curv::{BigInt, FE, GE};
 use paillier::{
     Decrypt, DecryptionKey, EncryptionKey, KeyGeneration, Paillier, RawCiphertext, RawPlaintext,
 };
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secp256k1::RecoveryId;
 use serde::{Deserialize, Serialize};
-use zk_paillier::zkproofs::NICorrectKeyProof;
-
-use crate::Error::{self, InvalidCom, InvalidKey, InvalidSS, InvalidSig};
+use std::collections::HashSet;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NICorrectKeyProof};
+
+use crate::utilities::backup::EncryptedBackup;
+use crate::utilities::bip32::{derive_path, ChildKey};
+use crate::utilities::hedged_nonce::hedged_nonce;
+use crate::utilities::ring_pedersen;
+use crate::utilities::secret::{zeroize_decryption_key, Redacted};
+use crate::utilities::signature_encoding::{
+    self, COMPACT_SIGNATURE_SIZE, RECOVERABLE_SIGNATURE_SIZE,
+};
+use crate::utilities::zk_no_small_factor::NoSmallFactorProof;
+use crate::utilities::zk_paillier_blum_modulus::{generate_blum_keypair, PaillierBlumModulusProof};
+use crate::ErrorKind::*;
+use crate::Protocol::{
+    Gg18Convert, Gg18Import, Gg18Keygen, Gg18Reconstruct, Gg18Recover, Gg18Refresh, Gg18Reshare,
+    Gg18Sign,
+};
+use crate::{Error, ErrorKind, Protocol};
 
 const SECURITY: usize = 256;
+const PAILLIER_KEY_SIZE: usize = 2048;
 
 #[derive(Debug)]
 pub struct Parameters {
@@ -44,27 +66,92 @@ pub struct Parameters {
     pub share_count: u16, //n
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Keys {
     pub u_i: FE,
     pub y_i: GE,
     pub dk: DecryptionKey,
     pub ek: EncryptionKey,
     pub party_index: usize,
+    pub N_tilde: BigInt,
+    pub h1: BigInt,
+    pub h2: BigInt,
+    pub xhi: BigInt,
+    pub xhi_inv: BigInt,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl fmt::Debug for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keys")
+            .field("u_i", &Redacted)
+            .field("y_i", &self.y_i)
+            .field("dk", &Redacted)
+            .field("ek", &self.ek)
+            .field("party_index", &self.party_index)
+            .field("N_tilde", &self.N_tilde)
+            .field("h1", &self.h1)
+            .field("h2", &self.h2)
+            .field("xhi", &Redacted)
+            .field("xhi_inv", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for Keys {
+    fn zeroize(&mut self) {
+        self.u_i.zeroize();
+        zeroize_decryption_key(&mut self.dk);
+        self.xhi.zeroize_bn();
+        self.xhi_inv.zeroize_bn();
+    }
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyPrivate {
     u_i: FE,
     x_i: FE,
     dk: DecryptionKey,
 }
 
+impl fmt::Debug for PartyPrivate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyPrivate")
+            .field("u_i", &Redacted)
+            .field("x_i", &Redacted)
+            .field("dk", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for PartyPrivate {
+    fn zeroize(&mut self) {
+        self.u_i.zeroize();
+        self.x_i.zeroize();
+        zeroize_decryption_key(&mut self.dk);
+    }
+}
+
+impl Drop for PartyPrivate {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyGenBroadcastMessage1 {
     pub e: EncryptionKey,
     pub com: BigInt,
     pub correct_key_proof: NICorrectKeyProof,
+    pub paillier_blum_modulus_proof: PaillierBlumModulusProof,
+    pub dlog_statement: DLogStatement,
+    pub composite_dlog_proof_base_h1: CompositeDLogProof,
+    pub composite_dlog_proof_base_h2: CompositeDLogProof,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -73,13 +160,76 @@ pub struct KeyGenDecommitMessage1 {
     pub y_i: GE,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SharedKeys {
     pub y: GE,
     pub x_i: FE,
 }
 
+impl fmt::Debug for SharedKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedKeys")
+            .field("y", &self.y)
+            .field("x_i", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for SharedKeys {
+    fn zeroize(&mut self) {
+        self.x_i.zeroize();
+    }
+}
+
+impl Drop for SharedKeys {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Broadcast of the proactive refresh. The dealer shares zero with f(x) = x * h(x), where h
+/// has degree t - 1 and is committed to by vss_scheme.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshBroadcastMessage {
+    pub vss_scheme: VerifiableSS,
+    pub paillier_key: Option<PaillierKeyRotation>,
+}
+
+/// A new paillier key with the keygen proofs. no_small_factor_proofs holds one proof per party,
+/// made against its ring-Pedersen parameters.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierKeyRotation {
+    pub e: EncryptionKey,
+    pub correct_key_proof: NICorrectKeyProof,
+    pub paillier_blum_modulus_proof: PaillierBlumModulusProof,
+    pub no_small_factor_proofs: Vec<NoSmallFactorProof>,
+}
+
+/// Broadcast of an old holder in resharing, commits to the sharing of its Lagrange weighted
+/// share among the new parties.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReshareBroadcastMessage {
+    pub vss_scheme: VerifiableSS,
+}
+
+/// Broadcast of a helper in share recovery: commitments to the random pieces its Lagrange
+/// contribution is split into, one piece per helper.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecoveryBroadcastMessage {
+    pub pieces_commitments: Vec<GE>,
+}
+
+/// Public output of import_secret_key, the messages of a keygen run by the dealer on behalf of
+/// all parties.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportTranscript {
+    pub bc1_vec: Vec<KeyGenBroadcastMessage1>,
+    pub decom_vec: Vec<KeyGenDecommitMessage1>,
+    pub no_small_factor_proofs_vec: Vec<Vec<NoSmallFactorProof>>,
+    pub vss_scheme_vec: Vec<VerifiableSS>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SignKeys {
     pub w_i: FE,
     pub g_w_i: GE,
@@ -88,6 +238,32 @@ pub struct SignKeys {
     pub g_gamma_i: GE,
 }
 
+impl fmt::Debug for SignKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignKeys")
+            .field("w_i", &Redacted)
+            .field("g_w_i", &self.g_w_i)
+            .field("k_i", &Redacted)
+            .field("gamma_i", &Redacted)
+            .field("g_gamma_i", &self.g_gamma_i)
+            .finish()
+    }
+}
+
+impl Zeroize for SignKeys {
+    fn zeroize(&mut self) {
+        self.w_i.zeroize();
+        self.k_i.zeroize();
+        self.gamma_i.zeroize();
+    }
+}
+
+impl Drop for SignKeys {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SignBroadcastPhase1 {
     pub com: BigInt,
@@ -99,7 +275,7 @@ pub struct SignDecommitPhase1 {
     pub g_gamma_i: GE,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LocalSignature {
     pub l_i: FE,
     pub rho_i: FE,
@@ -109,6 +285,33 @@ pub struct LocalSignature {
     pub y: GE,
 }
 
+impl fmt::Debug for LocalSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalSignature")
+            .field("l_i", &Redacted)
+            .field("rho_i", &Redacted)
+            .field("R", &self.R)
+            .field("s_i", &Redacted)
+            .field("m", &self.m)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+impl Zeroize for LocalSignature {
+    fn zeroize(&mut self) {
+        self.l_i.zeroize();
+        self.rho_i.zeroize();
+        self.s_i.zeroize();
+    }
+}
+
+impl Drop for LocalSignature {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Phase5Com1 {
     pub com: BigInt,
@@ -134,6 +337,44 @@ pub struct Phase5DDecom2 {
     pub blind_factor: BigInt,
 }
 
+/// Output of the signing rounds that do not depend on the message, up to phase4 and
+/// phase2_sigma_i. It is not `Clone`, but a serialized copy is, so single use rests on the id:
+/// `sign` records it in the caller's set of used ids and refuses ids already there. The set must
+/// be stored with the presignatures, and a stored copy deleted once it is signed with.
+#[derive(Serialize, Deserialize)]
+pub struct PreSignature {
+    id: [u8; 32],
+    k_i: FE,
+    sigma_i: FE,
+    R: GE,
+    y: GE,
+}
+
+impl fmt::Debug for PreSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PreSignature")
+            .field("id", &self.id)
+            .field("k_i", &Redacted)
+            .field("sigma_i", &Redacted)
+            .field("R", &self.R)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+impl Zeroize for PreSignature {
+    fn zeroize(&mut self) {
+        self.k_i.zeroize();
+        self.sigma_i.zeroize();
+    }
+}
+
+impl Drop for PreSignature {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SignatureRecid {
     pub r: FE,
@@ -145,7 +386,8 @@ impl Keys {
     pub fn create(index: usize) -> Self {
         let u = FE::new_random();
         let y = GE::generator() * u;
-        let (ek, dk) = Paillier::keypair().keys();
+        let (ek, dk) = generate_blum_keypair();
+        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_ring_pedersen_parameters();
 
         Self {
             u_i: u,
@@ -153,6 +395,11 @@ impl Keys {
             dk,
             ek,
             party_index: index,
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
         }
     }
 
@@ -162,6 +409,8 @@ impl Keys {
         let y = &ECPoint::generator() * &u;
 
         let (ek, dk) = Paillier::keypair_safe_primes().keys();
+        let (N_tilde, h1, h2, xhi, xhi_inv) =
+            generate_h1_h2_N_tilde(Paillier::keypair_safe_primes().keys());
 
         Keys {
             u_i: u,
@@ -169,11 +418,17 @@ impl Keys {
             dk,
             ek,
             party_index: index.clone(),
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
         }
     }
     pub fn create_from(u: FE, index: usize) -> Keys {
         let y = &ECPoint::generator() * &u;
-        let (ek, dk) = Paillier::keypair().keys();
+        let (ek, dk) = generate_blum_keypair();
+        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_ring_pedersen_parameters();
 
         Self {
             u_i: u,
@@ -181,6 +436,11 @@ impl Keys {
             dk,
             ek,
             party_index: index,
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
         }
     }
 
@@ -189,6 +449,10 @@ impl Keys {
     ) -> (KeyGenBroadcastMessage1, KeyGenDecommitMessage1) {
         let blind_factor = BigInt::sample(SECURITY);
         let correct_key_proof = NICorrectKeyProof::proof(&self.dk);
+        let paillier_blum_modulus_proof = PaillierBlumModulusProof::prove(&self.dk);
+        let dlog_statement = self.dlog_statement();
+        let (composite_dlog_proof_base_h1, composite_dlog_proof_base_h2) =
+            ring_pedersen::prove(&dlog_statement, &self.xhi, &self.xhi_inv);
         let com = HashCommitment::create_commitment_with_user_defined_randomness(
             &self.y_i.bytes_compressed_to_big_int(),
             &blind_factor,
@@ -197,6 +461,10 @@ impl Keys {
             e: self.ek.clone(),
             com,
             correct_key_proof,
+            paillier_blum_modulus_proof,
+            dlog_statement,
+            composite_dlog_proof_base_h1,
+            composite_dlog_proof_base_h2,
         };
         let decom1 = KeyGenDecommitMessage1 {
             blind_factor,
@@ -211,30 +479,44 @@ impl Keys {
         decom_vec: &[KeyGenDecommitMessage1],
         bc1_vec: &[KeyGenBroadcastMessage1],
     ) -> Result<(VerifiableSS, Vec<FE>, usize), Error> {
-        // test length:
-        assert_eq!(decom_vec.len() as u16, params.share_count);
-        assert_eq!(bc1_vec.len() as u16, params.share_count);
-        // test paillier correct key and test decommitments
-        let correct_key_correct_decom_all = (0..bc1_vec.len())
-            .map(|i| {
-                HashCommitment::create_commitment_with_user_defined_randomness(
-                    &decom_vec[i].y_i.bytes_compressed_to_big_int(),
-                    &decom_vec[i].blind_factor,
-                ) == bc1_vec[i].com
-                    && bc1_vec[i].correct_key_proof.verify(&bc1_vec[i].e).is_ok()
-            })
-            .all(|x| x);
-
+        phase1_verify_com_phase3_verify_correct_key(params, decom_vec, bc1_vec)?;
         let (vss_scheme, secret_shares) = VerifiableSS::share(
             params.threshold as usize,
             params.share_count as usize,
             &self.u_i,
         );
-        if correct_key_correct_decom_all {
-            Ok((vss_scheme, secret_shares, self.party_index))
-        } else {
-            Err(InvalidKey)
-        }
+        Ok((vss_scheme, secret_shares, self.party_index))
+    }
+
+    /// Proofs that the paillier modulus of this party has no small factors. Each proof is made
+    /// against the ring-Pedersen parameters of the party at the same position in bc1_vec,
+    /// which are verified first.
+    pub fn phase3_prove_no_small_factor(
+        &self,
+        bc1_vec: &[KeyGenBroadcastMessage1],
+    ) -> Result<Vec<NoSmallFactorProof>, Error> {
+        prove_no_small_factor(Gg18Keygen, &self.dk, bc1_vec)
+    }
+
+    /// proofs_vec holds the output of phase3_prove_no_small_factor of every party, index is
+    /// the (1-based) position of this party.
+    pub fn phase3_verify_no_small_factor(
+        &self,
+        params: &Parameters,
+        bc1_vec: &[KeyGenBroadcastMessage1],
+        proofs_vec: &[Vec<NoSmallFactorProof>],
+        index: usize,
+    ) -> Result<(), Error> {
+        let lens = [bc1_vec.len(), proofs_vec.len()];
+        check_len(Gg18Keygen, "phase3", params, &lens)?;
+        let dlog_statement = self.dlog_statement();
+        let bad_proofs = (0..proofs_vec.len())
+            .filter(|&i| match proofs_vec[i].get(index - 1) {
+                Some(proof) => proof.verify(&bc1_vec[i].e, &dlog_statement).is_err(),
+                None => true,
+            })
+            .collect::<Vec<usize>>();
+        check(Gg18Keygen, "phase3", InvalidNoSmallFactorProof, bad_proofs)
     }
 
     pub fn phase2_verify_vss_construct_keypair_phase3_pok_dlog(
@@ -245,28 +527,33 @@ impl Keys {
         vss_scheme_vec: &[VerifiableSS],
         index: usize,
     ) -> Result<(SharedKeys, DLogProof), Error> {
-        assert_eq!(y_vec.len() as u16, params.share_count);
-        assert_eq!(secret_shares_vec.len() as u16, params.share_count);
-        assert_eq!(vss_scheme_vec.len() as u16, params.share_count);
+        let lens = [y_vec.len(), secret_shares_vec.len(), vss_scheme_vec.len()];
+        check_len(Gg18Keygen, "phase2", params, &lens)?;
 
-        let correct_ss_verify = (0..y_vec.len())
-            .map(|i| {
+        let bad_shares = (0..y_vec.len())
+            .filter(|&i| {
                 vss_scheme_vec[i]
                     .validate_share(&secret_shares_vec[i], index)
-                    .is_ok()
-                    && vss_scheme_vec[i].commitments[0].get_element() == y_vec[i].get_element()
+                    .is_err()
+                    || vss_scheme_vec[i].commitments[0].get_element() != y_vec[i].get_element()
             })
-            .all(|x| x);
+            .collect::<Vec<usize>>();
+        check(Gg18Keygen, "phase2", InvalidSecretShare, bad_shares)?;
 
-        if correct_ss_verify {
-            let (head, tail) = y_vec.split_at(1);
-            let y = tail.iter().fold(head[0], |acc, x| acc + x);
+        let (head, tail) = y_vec.split_at(1);
+        let y = tail.iter().fold(head[0], |acc, x| acc + x);
 
-            let x_i = secret_shares_vec.iter().fold(FE::zero(), |acc, x| acc + x);
-            let dlog_proof = DLogProof::prove(&x_i);
-            Ok((SharedKeys { y, x_i }, dlog_proof))
-        } else {
-            Err(InvalidSS)
+        let x_i = secret_shares_vec.iter().fold(FE::zero(), |acc, x| acc + x);
+        let dlog_proof = DLogProof::prove(&x_i);
+        Ok((SharedKeys { y, x_i }, dlog_proof))
+    }
+
+    /// The ring-Pedersen parameters other parties use for range proofs towards this party.
+    pub fn dlog_statement(&self) -> DLogStatement {
+        DLogStatement {
+            N: self.N_tilde.clone(),
+            g: self.h1.clone(),
+            ni: self.h2.clone(),
         }
     }
 
@@ -297,22 +584,251 @@ impl Keys {
         comm * &li
     }
 
+    /// First round of a proactive refresh of a (t, n) key with t > 0. Returns the broadcast
+    /// and the sub-shares h(1), .., h(n), to be sent privately to each party. If the keygen
+    /// broadcasts of all parties are given the paillier key is rotated as well, with the same
+    /// proofs as in keygen, made against the ring-Pedersen parameters they carry once these
+    /// are verified. The returned keys replace self after the refresh.
+    pub fn refresh_phase1_distribute(
+        &self,
+        params: &Parameters,
+        bc1_vec: Option<&[KeyGenBroadcastMessage1]>,
+    ) -> Result<(RefreshBroadcastMessage, Vec<FE>, Keys), Error> {
+        if params.threshold == 0 || params.threshold >= params.share_count {
+            return Err(Error::new(Gg18Refresh, "phase1", InvalidThreshold));
+        }
+        let (vss_scheme, secret_shares) = VerifiableSS::share(
+            params.threshold as usize - 1,
+            params.share_count as usize,
+            &FE::new_random(),
+        );
+        let mut keys = self.clone();
+        let paillier_key = match bc1_vec {
+            Some(bc1_vec) => {
+                let (ek, dk) = generate_blum_keypair();
+                let rotation = PaillierKeyRotation {
+                    e: ek.clone(),
+                    correct_key_proof: NICorrectKeyProof::proof(&dk),
+                    paillier_blum_modulus_proof: PaillierBlumModulusProof::prove(&dk),
+                    no_small_factor_proofs: prove_no_small_factor(Gg18Refresh, &dk, bc1_vec)?,
+                };
+                keys.ek = ek;
+                keys.dk = dk;
+                Some(rotation)
+            }
+            None => None,
+        };
+        let bc = RefreshBroadcastMessage {
+            vss_scheme,
+            paillier_key,
+        };
+        Ok((bc, secret_shares, keys))
+    }
+
+    /// Second round of the proactive refresh. secret_shares_vec holds the sub-share received
+    /// from every party, vss_scheme_vec and ek_vec the keygen output of every party and index
+    /// is the (1-based) position of this party. Returns the new shared keys (y is unchanged),
+    /// the vss schemes whose get_commitments_to_xi commit to the new x_i and the paillier
+    /// keys after the rotation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn refresh_phase2_verify_update(
+        &self,
+        params: &Parameters,
+        bc_vec: &[RefreshBroadcastMessage],
+        secret_shares_vec: &[FE],
+        shared_keys: &SharedKeys,
+        vss_scheme_vec: &[VerifiableSS],
+        ek_vec: &[EncryptionKey],
+        index: usize,
+    ) -> Result<(SharedKeys, Vec<VerifiableSS>, Vec<EncryptionKey>), Error> {
+        let lens = [
+            bc_vec.len(),
+            secret_shares_vec.len(),
+            vss_scheme_vec.len(),
+            ek_vec.len(),
+        ];
+        check_len(Gg18Refresh, "phase1", params, &lens)?;
+        let t = params.threshold as usize;
+        let n = params.share_count as usize;
+
+        let rotations = (0..n)
+            .filter_map(|i| bc_vec[i].paillier_key.as_ref().map(|key| (i, key)))
+            .collect::<Vec<_>>();
+        let small_key = rotations
+            .iter()
+            .filter(|(_, key)| key.e.n.bit_length() < PAILLIER_KEY_SIZE - 1)
+            .map(|&(i, _)| i)
+            .collect::<Vec<usize>>();
+        check(Gg18Refresh, "phase1", PaillierModulusTooSmall, small_key)?;
+        let bad_key = rotations
+            .iter()
+            .filter(|(_, key)| key.correct_key_proof.verify(&key.e).is_err())
+            .map(|&(i, _)| i)
+            .collect::<Vec<usize>>();
+        check(Gg18Refresh, "phase1", InvalidCorrectKeyProof, bad_key)?;
+        let bad_modulus = rotations
+            .iter()
+            .filter(|(_, key)| key.paillier_blum_modulus_proof.verify(&key.e).is_err())
+            .map(|&(i, _)| i)
+            .collect::<Vec<usize>>();
+        check(
+            Gg18Refresh,
+            "phase1",
+            InvalidPaillierBlumModulusProof,
+            bad_modulus,
+        )?;
+        let dlog_statement = self.dlog_statement();
+        let bad_proofs = rotations
+            .iter()
+            .filter(|(_, key)| match key.no_small_factor_proofs.get(index - 1) {
+                Some(proof) => proof.verify(&key.e, &dlog_statement).is_err(),
+                None => true,
+            })
+            .map(|&(i, _)| i)
+            .collect::<Vec<usize>>();
+        check(Gg18Refresh, "phase1", InvalidNoSmallFactorProof, bad_proofs)?;
+
+        // t = 0 would make x * h(x) of degree one, more than the threshold
+        let bad_shares = (0..n)
+            .filter(|&i| {
+                let vss_scheme = &bc_vec[i].vss_scheme;
+                vss_scheme.parameters.threshold + 1 != t
+                    || vss_scheme.parameters.share_count != n
+                    || vss_scheme.commitments.len() != t
+                    || vss_scheme_vec[i].commitments.len() != t + 1
+                    || vss_scheme
+                        .validate_share(&secret_shares_vec[i], index)
+                        .is_err()
+            })
+            .collect::<Vec<usize>>();
+        check(Gg18Refresh, "phase2", InvalidSecretShare, bad_shares)?;
+
+        let index_fe: FE = ECScalar::from(&BigInt::from(index as u64));
+        let sum = secret_shares_vec.iter().fold(FE::zero(), |acc, x| acc + x);
+        let shared_keys = SharedKeys {
+            y: shared_keys.y,
+            x_i: shared_keys.x_i + sum * index_fe,
+        };
+
+        // the coefficients of x * h(x) are those of h shifted by one
+        let vss_scheme_vec = vss_scheme_vec
+            .iter()
+            .zip(bc_vec)
+            .map(|(vss_scheme, bc)| {
+                let mut vss_scheme = vss_scheme.clone();
+                for (c, d) in vss_scheme.commitments[1..]
+                    .iter_mut()
+                    .zip(&bc.vss_scheme.commitments)
+                {
+                    *c = *c + d;
+                }
+                vss_scheme
+            })
+            .collect::<Vec<VerifiableSS>>();
+
+        let ek_vec = ek_vec
+            .iter()
+            .zip(bc_vec)
+            .map(|(ek, bc)| match &bc.paillier_key {
+                Some(key) => key.e.clone(),
+                None => ek.clone(),
+            })
+            .collect::<Vec<EncryptionKey>>();
+        Ok((shared_keys, vss_scheme_vec, ek_vec))
+    }
+
+    /// Run by each party that joins in resharing or conversion, with the keys of
+    /// Keys::create. Every new party broadcasts phase1_broadcast_phase3_proof_of_correct_key
+    /// and sends phase3_prove_no_small_factor, which are checked here as in keygen, index is the
+    /// (1-based) position of this party. Returns the paillier keys and ring-Pedersen parameters
+    /// of all new parties.
+    pub fn verify_new_party_keys(
+        &self,
+        new_params: &Parameters,
+        decom_vec: &[KeyGenDecommitMessage1],
+        bc1_vec: &[KeyGenBroadcastMessage1],
+        proofs_vec: &[Vec<NoSmallFactorProof>],
+        index: usize,
+    ) -> Result<(Vec<EncryptionKey>, Vec<DLogStatement>), Error> {
+        phase1_verify_com_phase3_verify_correct_key(new_params, decom_vec, bc1_vec)?;
+        self.phase3_verify_no_small_factor(new_params, bc1_vec, proofs_vec, index)?;
+        Ok(bc1_vec
+            .iter()
+            .map(|bc1| (bc1.e.clone(), bc1.dlog_statement.clone()))
+            .unzip())
+    }
+
     pub fn verify_dlog_proofs(
         params: &Parameters,
         dlog_proofs_vec: &[DLogProof],
         y_vec: &[GE],
     ) -> Result<(), Error> {
-        assert_eq!(y_vec.len() as u16, params.share_count);
-        assert_eq!(dlog_proofs_vec.len() as u16, params.share_count);
-        let xi_dlog_verify = (0..y_vec.len())
-            .map(|i| DLogProof::verify(&dlog_proofs_vec[i]).is_ok())
-            .all(|x| x);
-
-        if xi_dlog_verify {
-            Ok(())
-        } else {
-            Err(InvalidKey)
+        let lens = [y_vec.len(), dlog_proofs_vec.len()];
+        check_len(Gg18Keygen, "phase3", params, &lens)?;
+        let bad_proofs = (0..y_vec.len())
+            .filter(|&i| DLogProof::verify(&dlog_proofs_vec[i]).is_err())
+            .collect::<Vec<usize>>();
+        check(Gg18Keygen, "phase3", InvalidDLogProof, bad_proofs)
+    }
+}
+
+impl ImportTranscript {
+    /// Run by each party on the keys it was dealt, index is its (1-based) position. Runs the
+    /// keygen checks of the paillier keys and ring-Pedersen parameters of all parties and
+    /// checks that the vss schemes commit to shares of y and that x_i is the share of this
+    /// party.
+    pub fn verify(
+        &self,
+        params: &Parameters,
+        keys: &Keys,
+        shared_keys: &SharedKeys,
+        index: usize,
+    ) -> Result<(), Error> {
+        phase1_verify_com_phase3_verify_correct_key(params, &self.decom_vec, &self.bc1_vec)?;
+        keys.phase3_verify_no_small_factor(
+            params,
+            &self.bc1_vec,
+            &self.no_small_factor_proofs_vec,
+            index,
+        )?;
+        check_len(Gg18Import, "verify", params, &[self.vss_scheme_vec.len()])?;
+
+        let t = params.threshold as usize;
+        let n = params.share_count as usize;
+        let bad_schemes = (0..n)
+            .filter(|&i| {
+                let vss_scheme = &self.vss_scheme_vec[i];
+                vss_scheme.parameters.threshold != t
+                    || vss_scheme.parameters.share_count != n
+                    || vss_scheme.commitments.len() != t + 1
+                    || vss_scheme.commitments[0] != self.decom_vec[i].y_i
+            })
+            .collect::<Vec<usize>>();
+        check(Gg18Import, "verify", InvalidSecretShare, bad_schemes)?;
+
+        let (head, tail) = self.decom_vec.split_at(1);
+        let y = tail.iter().fold(head[0].y_i, |acc, decom| acc + decom.y_i);
+        if y != shared_keys.y || keys.y_i != self.decom_vec[index - 1].y_i {
+            return Err(Error::new(Gg18Import, "verify", BadSum));
+        }
+        let xi_commitments = Keys::get_commitments_to_xi(&self.vss_scheme_vec);
+        if GE::generator() * shared_keys.x_i != xi_commitments[index - 1] {
+            return Err(
+                Error::new(Gg18Import, "verify", InvalidSecretShare).with_parties(vec![index - 1])
+            );
         }
+        Ok(())
+    }
+}
+
+impl KeyGenBroadcastMessage1 {
+    // h1 and h2 must generate the same group, which is proven in both directions
+    fn verify_dlog_statement(&self) -> bool {
+        ring_pedersen::verify(
+            &self.dlog_statement,
+            &self.composite_dlog_proof_base_h1,
+            &self.composite_dlog_proof_base_h2,
+        )
     }
 }
 
@@ -321,7 +837,7 @@ impl PartyPrivate {
         Self {
             u_i: key.u_i,
             x_i: shared_key.x_i,
-            dk: key.dk,
+            dk: key.dk.clone(),
         }
     }
 
@@ -337,7 +853,8 @@ impl PartyPrivate {
     pub fn refresh_private_key(&self, factor: &FE, index: usize) -> Keys {
         let u: FE = self.u_i + factor;
         let y = GE::generator() * u;
-        let (ek, dk) = Paillier::keypair().keys();
+        let (ek, dk) = generate_blum_keypair();
+        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_ring_pedersen_parameters();
 
         Keys {
             u_i: u,
@@ -345,6 +862,11 @@ impl PartyPrivate {
             dk,
             ek,
             party_index: index,
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
         }
     }
 
@@ -353,6 +875,8 @@ impl PartyPrivate {
         let u: FE = self.u_i + factor;
         let y = &ECPoint::generator() * &u;
         let (ek, dk) = Paillier::keypair_safe_primes().keys();
+        let (N_tilde, h1, h2, xhi, xhi_inv) =
+            generate_h1_h2_N_tilde(Paillier::keypair_safe_primes().keys());
 
         Keys {
             u_i: u,
@@ -360,6 +884,11 @@ impl PartyPrivate {
             dk,
             ek,
             party_index: index.clone(),
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
         }
     }
 
@@ -383,24 +912,274 @@ impl PartyPrivate {
     }
 }
 
+impl SharedKeys {
+    /// Resharing, run by each of the t + 1 old holders in s. index and s are party indices as
+    /// in SignKeys::create. Returns the broadcast and the sub-shares for the new parties 1..n'.
+    pub fn reshare_distribute(
+        &self,
+        vss_scheme: &VerifiableSS,
+        index: usize,
+        s: &[usize],
+        new_params: &Parameters,
+    ) -> Result<(ReshareBroadcastMessage, Vec<FE>), Error> {
+        check_party_set(Gg18Reshare, "phase1", vss_scheme, s, Some(index))?;
+        let li = vss_scheme.map_share_to_new_params(index, s);
+        let w_i = li * self.x_i;
+        let (vss_scheme, secret_shares) = VerifiableSS::share(
+            new_params.threshold as usize,
+            new_params.share_count as usize,
+            &w_i,
+        );
+        Ok((ReshareBroadcastMessage { vss_scheme }, secret_shares))
+    }
+
+    /// Run by each new party, index is its (1-based) position among the new parties. bc_vec
+    /// and secret_shares_vec hold the messages of the old holders in s, which must be exactly
+    /// t + 1 of them. Each dealt secret is checked against the commitment to the old x_i and
+    /// their sum against y. The returned vss schemes have the shape of the keygen output, n'
+    /// of them, and get_commitments_to_xi on them commits to the new shares. As after a
+    /// refresh, only their sum is meaningful: no single one is the dealing of a party. The
+    /// paillier keys and ring-Pedersen parameters of the new parties are exchanged with
+    /// Keys::verify_new_party_keys.
+    pub fn reshare_verify_construct(
+        y: &GE,
+        vss_scheme_vec: &[VerifiableSS],
+        s: &[usize],
+        bc_vec: &[ReshareBroadcastMessage],
+        secret_shares_vec: &[FE],
+        new_params: &Parameters,
+        index: usize,
+    ) -> Result<(SharedKeys, Vec<VerifiableSS>), Error> {
+        let vss_scheme = match vss_scheme_vec.first() {
+            Some(vss_scheme) if vss_scheme.parameters.share_count == vss_scheme_vec.len() => {
+                vss_scheme
+            }
+            _ => return Err(Error::new(Gg18Reshare, "phase1", LengthMismatch)),
+        };
+        check_party_set(Gg18Reshare, "phase1", vss_scheme, s, None)?;
+        if bc_vec.len() != s.len() || secret_shares_vec.len() != s.len() {
+            return Err(Error::new(Gg18Reshare, "phase1", LengthMismatch));
+        }
+        let xi_commitments = Keys::get_commitments_to_xi(vss_scheme_vec);
+        let dealt_commitments = s
+            .iter()
+            .map(|&i| Keys::update_commitments_to_xi(&xi_commitments[i], &vss_scheme_vec[0], i, s))
+            .collect::<Vec<GE>>();
+        verify_construct_dealt_shares(
+            Gg18Reshare,
+            y,
+            Some(&dealt_commitments),
+            bc_vec,
+            secret_shares_vec,
+            new_params,
+            index,
+        )
+    }
+
+    /// Conversion of a key held as x = u_1 + u_2 by two parties to a (t', n') key, for a
+    /// lindell 2017 key alpha + beta = x1 * x2 from the MtA of Party2Private::to_mta_message_b
+    /// and Party1Private::to_mta_message_b. Run by both parties with their u_i, returns the
+    /// broadcast and the sub-shares for the new parties 1..n'.
+    pub fn convert_additive_distribute(
+        u_i: &FE,
+        new_params: &Parameters,
+    ) -> (ReshareBroadcastMessage, Vec<FE>) {
+        let (vss_scheme, secret_shares) = VerifiableSS::share(
+            new_params.threshold as usize,
+            new_params.share_count as usize,
+            u_i,
+        );
+        (ReshareBroadcastMessage { vss_scheme }, secret_shares)
+    }
+
+    /// Run by each new party, index is its (1-based) position among the new parties. The dealt
+    /// secrets must add up to the discrete log of y, the public key of the converted key. The
+    /// vss schemes are returned as in reshare_verify_construct.
+    pub fn convert_additive_verify_construct(
+        y: &GE,
+        bc_vec: &[ReshareBroadcastMessage],
+        secret_shares_vec: &[FE],
+        new_params: &Parameters,
+        index: usize,
+    ) -> Result<(SharedKeys, Vec<VerifiableSS>), Error> {
+        if bc_vec.is_empty() || secret_shares_vec.len() != bc_vec.len() {
+            return Err(Error::new(Gg18Convert, "phase1", LengthMismatch));
+        }
+        verify_construct_dealt_shares(
+            Gg18Convert,
+            y,
+            None,
+            bc_vec,
+            secret_shares_vec,
+            new_params,
+            index,
+        )
+    }
+
+    /// Shares of the non-hardened BIP32 child key at path, derived from y and chain_code
+    /// without revealing any share. The Lagrange coefficients of any signing set sum to one, so
+    /// every party adds the tweak to x_i, and SignKeys::create on the result gives w_i for the
+    /// child key. The commitment to the child x_i of a party is its commitment to x_i plus
+    /// tweak * G.
+    pub fn derive_child(
+        &self,
+        chain_code: &[u8; 32],
+        path: &[u32],
+    ) -> Result<(SharedKeys, ChildKey), Error> {
+        let child = derive_path(&self.y, chain_code, path)?;
+        let shared_keys = SharedKeys {
+            y: child.public_key,
+            x_i: self.x_i + child.tweak,
+        };
+        Ok((shared_keys, child))
+    }
+
+    /// Verifiable encrypted backup of x_i to backup_public_key. Anyone can check it with
+    /// EncryptedBackup::verify against y_i = x_i * G.
+    pub fn backup(&self, backup_public_key: &GE) -> EncryptedBackup {
+        EncryptedBackup::create(&self.x_i, backup_public_key)
+    }
+
+    /// Restores the shared keys of the party with public share y_i from its backup.
+    pub fn restore(
+        backup: &EncryptedBackup,
+        y: &GE,
+        y_i: &GE,
+        backup_private_key: &FE,
+    ) -> Result<SharedKeys, Error> {
+        let x_i = backup.restore(y_i, backup_private_key)?;
+        Ok(SharedKeys { y: *y, x_i })
+    }
+
+    /// Share recovery, run by each of the t + 1 helpers in s to rebuild the share of the party
+    /// with index recover_index. index and s are party indices as in SignKeys::create. The
+    /// contribution l_i(recover_index) * x_i is split into random pieces, the i-th piece is
+    /// sent privately to the helper at position i in s.
+    pub fn recovery_phase1_split(
+        &self,
+        vss_scheme: &VerifiableSS,
+        index: usize,
+        s: &[usize],
+        recover_index: usize,
+    ) -> Result<(RecoveryBroadcastMessage, Vec<FE>), Error> {
+        check_party_set(Gg18Recover, "phase1", vss_scheme, s, Some(index))?;
+        if recover_index >= vss_scheme.parameters.share_count {
+            return Err(Error::new(Gg18Recover, "phase1", LengthMismatch));
+        }
+        let contribution = lagrange_at(recover_index, index, s) * self.x_i;
+        let mut pieces = (1..s.len()).map(|_| FE::new_random()).collect::<Vec<FE>>();
+        let sum = pieces.iter().fold(FE::zero(), |acc, x| acc + x);
+        pieces.push(contribution.sub(&sum.get_element()));
+        let pieces_commitments = pieces.iter().map(|&x| GE::generator() * x).collect();
+        Ok((RecoveryBroadcastMessage { pieces_commitments }, pieces))
+    }
+
+    /// Run by the helper at position p in s, pieces_vec holds the pieces it received from
+    /// every helper. The commitments of each helper are checked against the commitment to its
+    /// x_i. Returns the sum of the pieces, to be sent privately to the recovering party.
+    pub fn recovery_phase2_combine(
+        vss_scheme_vec: &[VerifiableSS],
+        s: &[usize],
+        recover_index: usize,
+        bc_vec: &[RecoveryBroadcastMessage],
+        pieces_vec: &[FE],
+        p: usize,
+    ) -> Result<FE, Error> {
+        check_recovery_commitments("phase2", vss_scheme_vec, s, recover_index, bc_vec)?;
+        if pieces_vec.len() != s.len() {
+            return Err(Error::new(Gg18Recover, "phase2", LengthMismatch));
+        }
+        let bad_pieces = (0..s.len())
+            .filter(|&q| GE::generator() * pieces_vec[q] != bc_vec[q].pieces_commitments[p])
+            .collect::<Vec<usize>>();
+        check(Gg18Recover, "phase2", InvalidSecretShare, bad_pieces)?;
+        Ok(pieces_vec.iter().fold(FE::zero(), |acc, x| acc + x))
+    }
+
+    /// Run by the recovering party with the sums of all helpers. None of the inputs reveals
+    /// the contribution of a single helper. The result is checked against the commitment to
+    /// x_i from get_commitments_to_xi.
+    pub fn recovery_phase3_construct(
+        y: &GE,
+        vss_scheme_vec: &[VerifiableSS],
+        s: &[usize],
+        recover_index: usize,
+        bc_vec: &[RecoveryBroadcastMessage],
+        sum_vec: &[FE],
+    ) -> Result<SharedKeys, Error> {
+        check_recovery_commitments("phase3", vss_scheme_vec, s, recover_index, bc_vec)?;
+        if sum_vec.len() != s.len() {
+            return Err(Error::new(Gg18Recover, "phase3", LengthMismatch));
+        }
+        let bad_sums = (0..s.len())
+            .filter(|&p| {
+                let (head, tail) = bc_vec.split_at(1);
+                let commitment = tail.iter().fold(head[0].pieces_commitments[p], |acc, bc| {
+                    acc + bc.pieces_commitments[p]
+                });
+                GE::generator() * sum_vec[p] != commitment
+            })
+            .collect::<Vec<usize>>();
+        check(Gg18Recover, "phase3", InvalidSecretShare, bad_sums)?;
+
+        let x_i = sum_vec.iter().fold(FE::zero(), |acc, x| acc + x);
+        if GE::generator() * x_i != Keys::get_commitments_to_xi(vss_scheme_vec)[recover_index] {
+            return Err(Error::new(Gg18Recover, "phase3", BadSum));
+        }
+        Ok(SharedKeys { y: *y, x_i })
+    }
+}
+
 impl SignKeys {
     pub fn create(
         private: &PartyPrivate,
         vss_scheme: &VerifiableSS,
         index: usize,
         s: &[usize],
+    ) -> Self {
+        Self::create_with_nonces(
+            private,
+            vss_scheme,
+            index,
+            s,
+            ECScalar::new_random(),
+            ECScalar::new_random(),
+        )
+    }
+
+    /// As create, but k_i and gamma_i are hedged nonces derived from fresh randomness, x_i,
+    /// the message digest and session_id, which must be unique per signing session.
+    pub fn create_hedged(
+        private: &PartyPrivate,
+        vss_scheme: &VerifiableSS,
+        index: usize,
+        s: &[usize],
+        message: &BigInt,
+        session_id: &[u8],
+    ) -> Self {
+        let k_i = hedged_nonce(&private.x_i, message, session_id, b"k_i");
+        let gamma_i = hedged_nonce(&private.x_i, message, session_id, b"gamma_i");
+        Self::create_with_nonces(private, vss_scheme, index, s, k_i, gamma_i)
+    }
+
+    fn create_with_nonces(
+        private: &PartyPrivate,
+        vss_scheme: &VerifiableSS,
+        index: usize,
+        s: &[usize],
+        k_i: FE,
+        gamma_i: FE,
     ) -> Self {
         let li = vss_scheme.map_share_to_new_params(index, s);
         let w_i = li * private.x_i;
         let g: GE = ECPoint::generator();
         let g_w_i = g * w_i;
-        let gamma_i: FE = ECScalar::new_random();
         let g_gamma_i = g * gamma_i;
 
         Self {
             w_i,
             g_w_i,
-            k_i: ECScalar::new_random(),
+            k_i,
             gamma_i,
             g_gamma_i,
         }
@@ -424,29 +1203,41 @@ impl SignKeys {
         )
     }
 
-    pub fn phase2_delta_i(&self, alpha_vec: &[FE], beta_vec: &[FE]) -> FE {
+    pub fn phase2_delta_i(&self, alpha_vec: &[FE], beta_vec: &[FE]) -> Result<FE, Error> {
         let vec_len = alpha_vec.len();
-        assert_eq!(alpha_vec.len(), beta_vec.len());
+        if beta_vec.len() != vec_len {
+            return Err(Error::new(Gg18Sign, "phase2", LengthMismatch));
+        }
         // assert_eq!(alpha_vec.len(), self.s.len() - 1);
         let ki_gamma_i = self.k_i.mul(&self.gamma_i.get_element());
-        (0..vec_len)
+        Ok((0..vec_len)
             .map(|i| alpha_vec[i].add(&beta_vec[i].get_element()))
-            .fold(ki_gamma_i, |acc, x| acc + x)
+            .fold(ki_gamma_i, |acc, x| acc + x))
     }
 
-    pub fn phase2_sigma_i(&self, miu_vec: &[FE], ni_vec: &[FE]) -> FE {
+    pub fn phase2_sigma_i(&self, miu_vec: &[FE], ni_vec: &[FE]) -> Result<FE, Error> {
         let vec_len = miu_vec.len();
-        assert_eq!(miu_vec.len(), ni_vec.len());
+        if ni_vec.len() != vec_len {
+            return Err(Error::new(Gg18Sign, "phase2", LengthMismatch));
+        }
         //assert_eq!(miu_vec.len(), self.s.len() - 1);
         let ki_w_i = self.k_i.mul(&self.w_i.get_element());
-        (0..vec_len)
+        Ok((0..vec_len)
             .map(|i| miu_vec[i].add(&ni_vec[i].get_element()))
-            .fold(ki_w_i, |acc, x| acc + x)
+            .fold(ki_w_i, |acc, x| acc + x))
     }
 
-    pub fn phase3_reconstruct_delta(delta_vec: &[FE]) -> FE {
-        let sum = delta_vec.iter().fold(FE::zero(), |acc, x| acc + x);
-        sum.invert()
+    pub fn phase3_reconstruct_delta(delta_vec: &[FE]) -> Result<FE, Error> {
+        // summed as integers, a zero sum cannot be inverted
+        let q = FE::q();
+        let sum = delta_vec.iter().fold(BigInt::zero(), |acc, x| {
+            BigInt::mod_add(&acc, &x.to_big_int(), &q)
+        });
+        if sum == BigInt::zero() {
+            return Err(Error::new(Gg18Sign, "phase3", ZeroSum));
+        }
+        let sum: FE = ECScalar::from(&sum);
+        Ok(sum.invert())
     }
 
     pub fn phase4(
@@ -457,49 +1248,95 @@ impl SignKeys {
     ) -> Result<GE, Error> {
         // note: b_proof_vec is populated using the results
         //from the MtAwc, which is handling the proof of knowledge verification of gamma_i such that
-        // Gamme_i = gamma_i * G in the verify_proofs_get_alpha()
-        let test_b_vec_and_com = (0..b_proof_vec.len())
-            .map(|i| {
-                b_proof_vec[i].pk.get_element() == phase1_decommit_vec[i].g_gamma_i.get_element()
-                    && HashCommitment::create_commitment_with_user_defined_randomness(
+        // Gamme_i = gamma_i * G in the verify_proofs_get_alpha_with_check()
+        if b_proof_vec.is_empty()
+            || phase1_decommit_vec.len() != b_proof_vec.len()
+            || bc1_vec.len() != b_proof_vec.len()
+        {
+            return Err(Error::new(Gg18Sign, "phase4", LengthMismatch));
+        }
+        let bad_actors = (0..b_proof_vec.len())
+            .filter(|&i| {
+                b_proof_vec[i].pk.get_element() != phase1_decommit_vec[i].g_gamma_i.get_element()
+                    || HashCommitment::create_commitment_with_user_defined_randomness(
                         &phase1_decommit_vec[i]
                             .g_gamma_i
                             .bytes_compressed_to_big_int(),
                         &phase1_decommit_vec[i].blind_factor,
-                    ) == bc1_vec[i].com
+                    ) != bc1_vec[i].com
             })
-            .all(|x| x);
-
-        let mut g_gamma_i_iter = phase1_decommit_vec.iter();
-        let head = g_gamma_i_iter.next().unwrap();
-        let tail = g_gamma_i_iter;
-        if test_b_vec_and_com {
-            Ok({
-                let gamma_sum = tail.fold(head.g_gamma_i, |acc, x| acc + x.g_gamma_i);
-                // R
-                gamma_sum * delta_inv
-            })
-        } else {
-            Err(InvalidKey)
+            .collect::<Vec<usize>>();
+
+        check(Gg18Sign, "phase4", InvalidCommitment, bad_actors)?;
+
+        let (head, tail) = phase1_decommit_vec.split_at(1);
+        let gamma_sum = tail
+            .iter()
+            .fold(head[0].g_gamma_i, |acc, x| acc + x.g_gamma_i);
+        // R
+        Ok(gamma_sum * delta_inv)
+    }
+}
+
+impl PreSignature {
+    /// R is the output of phase4, y the joint public key.
+    pub fn create(sign_keys: SignKeys, sigma_i: FE, R: GE, y: GE) -> Self {
+        let mut id = [0u8; 32];
+        OsRng.fill_bytes(&mut id);
+        PreSignature {
+            id,
+            k_i: sign_keys.k_i,
+            sigma_i,
+            R,
+            y,
         }
     }
+
+    pub fn R(&self) -> GE {
+        self.R
+    }
+
+    /// Random id, chosen when the presignature is created.
+    pub fn id(&self) -> [u8; 32] {
+        self.id
+    }
+
+    /// Starts the online part, which is phase5 as in the full protocol: the returned local
+    /// signature goes through phase5a to phase5d before s_i is published, then
+    /// `output_signature`. Fails if the id is already in `used_ids`, and adds it otherwise.
+    pub fn sign(
+        self,
+        message: &BigInt,
+        used_ids: &mut HashSet<[u8; 32]>,
+    ) -> Result<LocalSignature, Error> {
+        if !used_ids.insert(self.id) {
+            return Err(Error::new(Gg18Sign, "presign", ReusedPresignature));
+        }
+        LocalSignature::phase5_local_sig(&self.k_i, message, &self.R, &self.sigma_i, &self.y)
+    }
 }
 
 impl LocalSignature {
-    pub fn phase5_local_sig(k_i: &FE, message: &BigInt, R: &GE, sigma_i: &FE, pubkey: &GE) -> Self {
+    pub fn phase5_local_sig(
+        k_i: &FE,
+        message: &BigInt,
+        R: &GE,
+        sigma_i: &FE,
+        pubkey: &GE,
+    ) -> Result<Self, Error> {
         let m_fe: FE = ECScalar::from(message);
-        let r: FE = ECScalar::from(&R.x_coor().unwrap().mod_floor(&FE::q()));
+        let r: FE = ECScalar::from(&x_coor_mod_q(R, "phase5")?);
         let s_i = m_fe * k_i + r * sigma_i;
         let l_i: FE = ECScalar::new_random();
         let rho_i: FE = ECScalar::new_random();
-        Self {
+        Ok(Self {
             l_i,
             rho_i,
             R: *R,
             s_i,
             m: message.clone(),
             y: *pubkey,
-        }
+        })
     }
 
     pub fn phase5a_broadcast_5b_zkproof(
@@ -552,33 +1389,51 @@ impl LocalSignature {
         v_i: &GE,
         R: &GE,
     ) -> Result<(Phase5Com2, Phase5DDecom2), Error> {
-        assert_eq!(decom_vec.len(), com_vec.len());
+        let len = com_vec.len();
+        if len == 0
+            || decom_vec.len() != len
+            || elgamal_proofs.len() != len
+            || dlog_proofs_rho.len() != len
+        {
+            return Err(Error::new(Gg18Sign, "phase5c", LengthMismatch));
+        }
 
         let g: GE = ECPoint::generator();
-        let test_com_elgamal = (0..com_vec.len())
-            .map(|i| {
-                let delta = HomoElGamalStatement {
-                    G: decom_vec[i].A_i,
-                    H: *R,
-                    Y: g,
-                    D: decom_vec[i].V_i,
-                    E: decom_vec[i].B_i,
-                };
+        let bad_decom = (0..com_vec.len())
+            .filter(|&i| {
                 let input_hash = HSha256::create_hash_from_ge(&[
                     &decom_vec[i].V_i,
                     &decom_vec[i].A_i,
                     &decom_vec[i].B_i,
                 ])
                 .to_big_int();
-
                 HashCommitment::create_commitment_with_user_defined_randomness(
                     &input_hash,
                     &decom_vec[i].blind_factor,
-                ) == com_vec[i].com
-                    && elgamal_proofs[i].verify(&delta).is_ok()
-                    && DLogProof::verify(&dlog_proofs_rho[i]).is_ok()
+                ) != com_vec[i].com
             })
-            .all(|x| x);
+            .collect::<Vec<usize>>();
+        check(Gg18Sign, "phase5a", InvalidCommitment, bad_decom)?;
+        let bad_elgamal = (0..com_vec.len())
+            .filter(|&i| {
+                let delta = HomoElGamalStatement {
+                    G: decom_vec[i].A_i,
+                    H: *R,
+                    Y: g,
+                    D: decom_vec[i].V_i,
+                    E: decom_vec[i].B_i,
+                };
+                elgamal_proofs[i].verify(&delta).is_err()
+            })
+            .collect::<Vec<usize>>();
+        check(Gg18Sign, "phase5b", InvalidHomoElGamalProof, bad_elgamal)?;
+        let bad_dlog = (0..com_vec.len())
+            .filter(|&i| {
+                DLogProof::verify(&dlog_proofs_rho[i]).is_err()
+                    || dlog_proofs_rho[i].pk != decom_vec[i].A_i
+            })
+            .collect::<Vec<usize>>();
+        check(Gg18Sign, "phase5b", InvalidDLogProof, bad_dlog)?;
 
         let v_vec = (0..com_vec.len())
             .map(|i| &decom_vec[i].V_i)
@@ -589,12 +1444,12 @@ impl LocalSignature {
 
         let v = v_vec.iter().fold(v_i.clone(), |acc, x| acc + *x);
         // V = -mG -ry - sum (vi)
-        let mut a_i_iter = a_vec.iter();
-        let head = a_i_iter.next().unwrap();
-        let tail = a_i_iter;
-        let a = tail.fold((*head).clone(), |acc, x| acc.add_point(&(*x).get_element()));
+        let (head, tail) = a_vec.split_at(1);
+        let a = tail
+            .iter()
+            .fold(head[0].clone(), |acc, x| acc.add_point(&(*x).get_element()));
 
-        let r: FE = ECScalar::from(&self.R.x_coor().unwrap().mod_floor(&FE::q()));
+        let r: FE = ECScalar::from(&x_coor_mod_q(&self.R, "phase5c")?);
         let yr = self.y * r;
         let g: GE = ECPoint::generator();
         let m_fe: FE = ECScalar::from(&self.m);
@@ -609,20 +1464,14 @@ impl LocalSignature {
             &blind_factor,
         );
 
-        if test_com_elgamal {
-            Ok({
-                (
-                    Phase5Com2 { com },
-                    Phase5DDecom2 {
-                        u_i,
-                        t_i,
-                        blind_factor,
-                    },
-                )
-            })
-        } else {
-            Err(InvalidCom)
-        }
+        Ok((
+            Phase5Com2 { com },
+            Phase5DDecom2 {
+                u_i,
+                t_i,
+                blind_factor,
+            },
+        ))
     }
 
     pub fn phase5d(
@@ -631,20 +1480,21 @@ impl LocalSignature {
         com_vec2: &[Phase5Com2],
         decom_vec1: &[Phase5ADecom1],
     ) -> Result<FE, Error> {
-        assert_eq!(decom_vec2.len(), decom_vec1.len());
-        assert_eq!(decom_vec2.len(), com_vec2.len());
+        if decom_vec2.len() != decom_vec1.len() || decom_vec2.len() != com_vec2.len() {
+            return Err(Error::new(Gg18Sign, "phase5d", LengthMismatch));
+        }
 
-        let test_com = (0..com_vec2.len())
-            .map(|i| {
+        let bad_actors = (0..com_vec2.len())
+            .filter(|&i| {
                 let input_hash =
                     HSha256::create_hash_from_ge(&[&decom_vec2[i].u_i, &decom_vec2[i].t_i])
                         .to_big_int();
                 HashCommitment::create_commitment_with_user_defined_randomness(
                     &input_hash,
                     &decom_vec2[i].blind_factor,
-                ) == com_vec2[i].com
+                ) != com_vec2[i].com
             })
-            .all(|x| x);
+            .collect::<Vec<usize>>();
 
         let t_vec = (0..com_vec2.len())
             .map(|i| &decom_vec2[i].t_i)
@@ -661,22 +1511,25 @@ impl LocalSignature {
         let biased_sum_tb_minus_u = u_vec
             .iter()
             .fold(biased_sum_tb, |acc, x| acc.sub_point(&x.get_element()));
-        if test_com {
-            if g == biased_sum_tb_minus_u {
-                Ok(self.s_i)
-            } else {
-                Err(InvalidKey)
-            }
+        check(Gg18Sign, "phase5d", InvalidCommitment, bad_actors)?;
+        // the sum check does not tell which party used bad values
+        if g == biased_sum_tb_minus_u {
+            Ok(self.s_i)
         } else {
-            Err(InvalidCom)
+            Err(Error::new(Gg18Sign, "phase5d", BadSum))
         }
     }
+    /// On failure the parties can reveal l_i so that `phase5_check_s_i` finds who sent a bad s_i.
     pub fn output_signature(&self, s_vec: &[FE]) -> Result<SignatureRecid, Error> {
         let mut s = s_vec.iter().fold(self.s_i, |acc, x| acc + x);
         let s_bn = s.to_big_int();
 
-        let r: FE = ECScalar::from(&self.R.x_coor().unwrap().mod_floor(&FE::q()));
-        let ry: BigInt = self.R.y_coor().unwrap().mod_floor(&FE::q());
+        let r: FE = ECScalar::from(&x_coor_mod_q(&self.R, "phase5e")?);
+        let ry: BigInt = self
+            .R
+            .y_coor()
+            .ok_or_else(|| Error::new(Gg18Sign, "phase5e", PointAtInfinity))?
+            .mod_floor(&FE::q());
 
         /*
          Calculate recovery id - it is not possible to compute the public key out of the signature
@@ -696,7 +1549,91 @@ impl LocalSignature {
         if ver {
             Ok(sig)
         } else {
-            Err(InvalidSig)
+            Err(Error::new(Gg18Sign, "phase5e", InvalidSignature))
+        }
+    }
+
+    /// Checks every s_i against the phase 5A commitment V_i = s_i * R + l_i * G, given the l_i
+    /// revealed after `output_signature` failed. The three vectors are indexed by party.
+    pub fn phase5_check_s_i(
+        R: &GE,
+        s_vec: &[FE],
+        l_vec: &[FE],
+        decom_vec: &[Phase5ADecom1],
+    ) -> Result<(), Error> {
+        if l_vec.len() != s_vec.len() || decom_vec.len() != s_vec.len() {
+            return Err(Error::new(Gg18Sign, "phase5e", LengthMismatch));
+        }
+
+        let g: GE = ECPoint::generator();
+        let bad_actors = (0..s_vec.len())
+            .filter(|&i| *R * s_vec[i] + g * l_vec[i] != decom_vec[i].V_i)
+            .collect::<Vec<usize>>();
+        check(Gg18Sign, "phase5e", InvalidSignature, bad_actors)
+    }
+}
+
+impl SignatureRecid {
+    /// DER encoding of r and s, the recovery id is not part of it.
+    pub fn to_der(&self) -> Vec<u8> {
+        signature_encoding::to_der(&self.r.to_big_int(), &self.s.to_big_int())
+    }
+
+    pub fn from_der(bytes: &[u8], recid: u8) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_der(bytes)?;
+        Ok(SignatureRecid::from_big_int(&r, &s, recid))
+    }
+
+    /// 64 byte r || s, the recovery id is not part of it.
+    pub fn to_compact(&self) -> [u8; COMPACT_SIGNATURE_SIZE] {
+        signature_encoding::to_compact(&self.r.to_big_int(), &self.s.to_big_int())
+    }
+
+    pub fn from_compact(bytes: &[u8], recid: u8) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_compact(bytes)?;
+        Ok(SignatureRecid::from_big_int(&r, &s, recid))
+    }
+
+    /// 65 byte r || s || recid.
+    pub fn to_recoverable(&self) -> [u8; RECOVERABLE_SIGNATURE_SIZE] {
+        signature_encoding::to_recoverable(&self.r.to_big_int(), &self.s.to_big_int(), self.recid)
+    }
+
+    pub fn from_recoverable(bytes: &[u8]) -> Result<SignatureRecid, Error> {
+        let (r, s, recid) = signature_encoding::from_recoverable(bytes)?;
+        Ok(SignatureRecid::from_big_int(&r, &s, recid))
+    }
+
+    pub fn to_libsecp256k1(&self) -> Result<(secp256k1::Signature, RecoveryId), Error> {
+        Ok((
+            signature_encoding::to_libsecp256k1(&self.r.to_big_int(), &self.s.to_big_int()),
+            signature_encoding::to_recovery_id(self.recid)?,
+        ))
+    }
+
+    pub fn from_libsecp256k1(
+        signature: &secp256k1::Signature,
+        recid: &RecoveryId,
+    ) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_libsecp256k1(signature)?;
+        Ok(SignatureRecid::from_big_int(&r, &s, recid.serialize()))
+    }
+
+    /// The public key the signature recovers to, the signing key when recid is correct.
+    pub fn recover_public_key(&self, message: &BigInt) -> Result<GE, Error> {
+        signature_encoding::recover_public_key(
+            &self.r.to_big_int(),
+            &self.s.to_big_int(),
+            self.recid,
+            message,
+        )
+    }
+
+    fn from_big_int(r: &BigInt, s: &BigInt, recid: u8) -> SignatureRecid {
+        SignatureRecid {
+            r: ECScalar::from(r),
+            s: ECScalar::from(s),
+            recid,
         }
     }
 }
@@ -712,9 +1649,382 @@ pub fn verify(sig: &SignatureRecid, y: &GE, message: &BigInt) -> Result<(), Erro
     let yu2 = y * &u2;
     // can be faster using shamir trick
 
-    if sig.r == ECScalar::from(&(gu1 + yu2).x_coor().unwrap().mod_floor(&FE::q())) {
+    if sig.r == ECScalar::from(&x_coor_mod_q(&(gu1 + yu2), "verify")?) {
+        Ok(())
+    } else {
+        Err(Error::new(Gg18Sign, "verify", InvalidSignature))
+    }
+}
+
+// R is computed from the input of all parties and can be the point at infinity
+fn x_coor_mod_q(point: &GE, round: &'static str) -> Result<BigInt, Error> {
+    point
+        .x_coor()
+        .map(|x| x.mod_floor(&FE::q()))
+        .ok_or_else(|| Error::new(Gg18Sign, round, PointAtInfinity))
+}
+
+/// Trusted dealer import of an existing private key. The secret is split into random u_i, which
+/// are shared as in keygen, so the output has the shape of mock::keygen_t_n_parties: the keys
+/// and shared keys of all parties (party_index is 0-based), the commitments to the x_i, the
+/// public key and the vss schemes. The transcript is sent to every party, which checks its
+/// keys with ImportTranscript::verify. Accepts the thresholds of keygen, t < n with n >= 2.
+pub fn import_secret_key(
+    secret: &FE,
+    params: &Parameters,
+) -> Result<
+    (
+        Vec<Keys>,
+        Vec<SharedKeys>,
+        Vec<GE>,
+        GE,
+        Vec<VerifiableSS>,
+        ImportTranscript,
+    ),
+    Error,
+> {
+    let t = params.threshold as usize;
+    let n = params.share_count as usize;
+    if n < 2 || t >= n {
+        return Err(Error::new(Gg18Import, "import", InvalidThreshold));
+    }
+    let mut u_vec = (1..n).map(|_| FE::new_random()).collect::<Vec<FE>>();
+    let sum = u_vec.iter().fold(FE::zero(), |acc, x| acc + x);
+    u_vec.push(secret.sub(&sum.get_element()));
+
+    let party_keys_vec = u_vec
+        .iter()
+        .enumerate()
+        .map(|(i, &u)| Keys::create_from(u, i))
+        .collect::<Vec<Keys>>();
+    let (bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key())
+        .unzip();
+    let no_small_factor_proofs_vec = party_keys_vec
+        .iter()
+        .map(|k| k.phase3_prove_no_small_factor(&bc1_vec))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let (vss_scheme_vec, secret_shares_vec): (Vec<_>, Vec<_>) =
+        u_vec.iter().map(|u| VerifiableSS::share(t, n, u)).unzip();
+    let y = GE::generator() * *secret;
+    let shared_keys_vec = (0..n)
+        .map(|j| SharedKeys {
+            y,
+            x_i: secret_shares_vec
+                .iter()
+                .fold(FE::zero(), |acc, shares| acc + shares[j]),
+        })
+        .collect::<Vec<SharedKeys>>();
+    let pk_vec = Keys::get_commitments_to_xi(&vss_scheme_vec);
+
+    let transcript = ImportTranscript {
+        bc1_vec,
+        decom_vec,
+        no_small_factor_proofs_vec,
+        vss_scheme_vec: vss_scheme_vec.clone(),
+    };
+    Ok((
+        party_keys_vec,
+        shared_keys_vec,
+        pk_vec,
+        y,
+        vss_scheme_vec,
+        transcript,
+    ))
+}
+
+/// Emergency reconstruction of the private key from the shares of the parties in indices
+/// (0-based), at least t + 1 of them. Every share is checked against the commitments from
+/// get_commitments_to_xi and the result against the public key y.
+pub fn reconstruct_private_key(
+    y: &GE,
+    vss_scheme_vec: &[VerifiableSS],
+    indices: &[usize],
+    shared_keys_vec: &[SharedKeys],
+) -> Result<Zeroizing<FE>, Error> {
+    let n = vss_scheme_vec.len();
+    if n == 0 || indices.len() != shared_keys_vec.len() || indices.iter().any(|&i| i >= n) {
+        return Err(Error::new(Gg18Reconstruct, "reconstruct", LengthMismatch));
+    }
+    let mut distinct = indices.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() != indices.len() || indices.len() <= vss_scheme_vec[0].parameters.threshold {
+        return Err(Error::new(Gg18Reconstruct, "reconstruct", TooFewShares));
+    }
+
+    let xi_commitments = Keys::get_commitments_to_xi(vss_scheme_vec);
+    let bad_shares = (0..indices.len())
+        .filter(|&p| GE::generator() * shared_keys_vec[p].x_i != xi_commitments[indices[p]])
+        .collect::<Vec<usize>>();
+    check(
+        Gg18Reconstruct,
+        "reconstruct",
+        InvalidSecretShare,
+        bad_shares,
+    )?;
+
+    let mut x_i_vec = shared_keys_vec.iter().map(|k| k.x_i).collect::<Vec<FE>>();
+    let x = Zeroizing::new(vss_scheme_vec[0].reconstruct(indices, &x_i_vec));
+    x_i_vec.zeroize();
+    if GE::generator() * *x != *y {
+        return Err(Error::new(Gg18Reconstruct, "reconstruct", BadSum));
+    }
+    Ok(x)
+}
+
+/// Generates ring-Pedersen parameters from a paillier key pair: N_tilde is the modulus,
+/// h1 a random square and h2 = h1^xhi mod N_tilde. Returns (N_tilde, h1, h2, xhi, xhi_inv),
+/// where xhi_inv is the inverse of xhi modulo phi(N_tilde). The key pair should use safe
+/// primes in production.
+pub fn generate_h1_h2_N_tilde(
+    (ek_tilde, dk_tilde): (EncryptionKey, DecryptionKey),
+) -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
+    let one = BigInt::one();
+    let phi = (&dk_tilde.p - &one) * (&dk_tilde.q - &one);
+    let r = BigInt::sample_below(&ek_tilde.n);
+    let h1 = BigInt::mod_mul(&r, &r, &ek_tilde.n);
+    let (xhi, xhi_inv) = loop {
+        let xhi = BigInt::sample_below(&phi);
+        if let Some(xhi_inv) = xhi.invert(&phi) {
+            break (xhi, xhi_inv);
+        }
+    };
+    let h2 = BigInt::mod_pow(&h1, &xhi, &ek_tilde.n);
+    (ek_tilde.n, h1, h2, xhi, xhi_inv)
+}
+
+/// Ring-Pedersen parameters of keygen and refresh, N_tilde is the product of two safe primes.
+/// The unit tests use plain primes instead, as safe primes of this size take minutes to find.
+pub(crate) fn generate_ring_pedersen_parameters() -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
+    #[cfg(not(test))]
+    let key_pair = Paillier::keypair_safe_primes();
+    #[cfg(test)]
+    let key_pair = Paillier::keypair();
+    generate_h1_h2_N_tilde(key_pair.keys())
+}
+
+// checks the dealings of resharing and conversion against the commitments to the dealt secrets,
+// when known, and their sum against y. The sum of the dealt polynomials is split evenly into
+// one vss scheme per new party, each committing to the sum divided by n'.
+fn verify_construct_dealt_shares(
+    protocol: Protocol,
+    y: &GE,
+    dealt_commitments: Option<&[GE]>,
+    bc_vec: &[ReshareBroadcastMessage],
+    secret_shares_vec: &[FE],
+    new_params: &Parameters,
+    index: usize,
+) -> Result<(SharedKeys, Vec<VerifiableSS>), Error> {
+    let new_t = new_params.threshold as usize;
+    let new_n = new_params.share_count as usize;
+
+    let bad_shares = (0..bc_vec.len())
+        .filter(|&p| {
+            let vss_scheme = &bc_vec[p].vss_scheme;
+            vss_scheme.parameters.threshold != new_t
+                || vss_scheme.parameters.share_count != new_n
+                || vss_scheme.commitments.len() != new_t + 1
+                || dealt_commitments.map_or(false, |c| vss_scheme.commitments[0] != c[p])
+                || vss_scheme
+                    .validate_share(&secret_shares_vec[p], index)
+                    .is_err()
+        })
+        .collect::<Vec<usize>>();
+    check(protocol, "phase2", InvalidSecretShare, bad_shares)?;
+
+    let commitments = (0..=new_t)
+        .map(|k| {
+            let (head, tail) = bc_vec.split_at(1);
+            tail.iter()
+                .fold(head[0].vss_scheme.commitments[k], |acc, bc| {
+                    acc + bc.vss_scheme.commitments[k]
+                })
+        })
+        .collect::<Vec<GE>>();
+    if commitments[0] != *y {
+        return Err(Error::new(protocol, "phase2", BadSum));
+    }
+
+    let n_inv: FE = ECScalar::from(&BigInt::from(new_n as u64));
+    let n_inv = n_inv.invert();
+    let vss_scheme = VerifiableSS {
+        parameters: bc_vec[0].vss_scheme.parameters.clone(),
+        commitments: commitments.iter().map(|c| c * &n_inv).collect(),
+    };
+    let vss_scheme_vec = vec![vss_scheme; new_n];
+
+    let x_i = secret_shares_vec.iter().fold(FE::zero(), |acc, x| acc + x);
+    Ok((SharedKeys { y: *y, x_i }, vss_scheme_vec))
+}
+
+// Lagrange coefficient of party index in s for interpolating at party point, at zero this is
+// map_share_to_new_params
+fn lagrange_at(point: usize, index: usize, s: &[usize]) -> FE {
+    let to_fe = |i: usize| -> FE { ECScalar::from(&BigInt::from(i as u64 + 1)) };
+    let (x, x_i) = (to_fe(point), to_fe(index));
+    let one: FE = ECScalar::from(&BigInt::one());
+    let (num, denom) = s
+        .iter()
+        .filter(|&&j| j != index)
+        .fold((one, one), |(num, denom), &j| {
+            let x_j = to_fe(j).get_element();
+            (num * x.sub(&x_j), denom * x_i.sub(&x_j))
+        });
+    num * denom.invert()
+}
+
+// every helper in share recovery commits to pieces that add up to its contribution
+fn check_recovery_commitments(
+    round: &'static str,
+    vss_scheme_vec: &[VerifiableSS],
+    s: &[usize],
+    recover_index: usize,
+    bc_vec: &[RecoveryBroadcastMessage],
+) -> Result<(), Error> {
+    let n = vss_scheme_vec.len();
+    if recover_index >= n
+        || vss_scheme_vec[recover_index].parameters.share_count != n
+        || bc_vec.len() != s.len()
+    {
+        return Err(Error::new(Gg18Recover, round, LengthMismatch));
+    }
+    check_party_set(Gg18Recover, round, &vss_scheme_vec[recover_index], s, None)?;
+    let xi_commitments = Keys::get_commitments_to_xi(vss_scheme_vec);
+    let bad_helpers = (0..s.len())
+        .filter(|&p| {
+            let commitments = &bc_vec[p].pieces_commitments;
+            if commitments.len() != s.len() {
+                return true;
+            }
+            let (head, tail) = commitments.split_at(1);
+            let sum = tail.iter().fold(head[0], |acc, x| acc + x);
+            sum != xi_commitments[s[p]] * lagrange_at(recover_index, s[p], s)
+        })
+        .collect::<Vec<usize>>();
+    check(Gg18Recover, round, InvalidCommitment, bad_helpers)
+}
+
+// s must hold t + 1 distinct party indices of the sharing, and index if given, or the Lagrange
+// coefficients over s do not interpolate the shared secret
+fn check_party_set(
+    protocol: Protocol,
+    round: &'static str,
+    vss_scheme: &VerifiableSS,
+    s: &[usize],
+    index: Option<usize>,
+) -> Result<(), Error> {
+    let mut distinct = s.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() != s.len()
+        || s.iter().any(|&i| i >= vss_scheme.parameters.share_count)
+        || index.map_or(false, |i| !s.contains(&i))
+    {
+        return Err(Error::new(protocol, round, LengthMismatch));
+    }
+    if s.len() != vss_scheme.parameters.threshold + 1 {
+        return Err(Error::new(protocol, round, InvalidThreshold));
+    }
+    Ok(())
+}
+
+// the checks of phase1_verify_com_phase3_verify_correct_key_phase2_distribute, shared with
+// the verification of dealt keys
+fn phase1_verify_com_phase3_verify_correct_key(
+    params: &Parameters,
+    decom_vec: &[KeyGenDecommitMessage1],
+    bc1_vec: &[KeyGenBroadcastMessage1],
+) -> Result<(), Error> {
+    // test length:
+    let lens = [decom_vec.len(), bc1_vec.len()];
+    check_len(Gg18Keygen, "phase1", params, &lens)?;
+    // test paillier correct key and test decommitments
+    let bad_decom = (0..bc1_vec.len())
+        .filter(|&i| {
+            HashCommitment::create_commitment_with_user_defined_randomness(
+                &decom_vec[i].y_i.bytes_compressed_to_big_int(),
+                &decom_vec[i].blind_factor,
+            ) != bc1_vec[i].com
+        })
+        .collect::<Vec<usize>>();
+    check(Gg18Keygen, "phase1", InvalidCommitment, bad_decom)?;
+    let small_key = (0..bc1_vec.len())
+        .filter(|&i| bc1_vec[i].e.n.bit_length() < PAILLIER_KEY_SIZE - 1)
+        .collect::<Vec<usize>>();
+    check(Gg18Keygen, "phase3", PaillierModulusTooSmall, small_key)?;
+    let bad_key = (0..bc1_vec.len())
+        .filter(|&i| bc1_vec[i].correct_key_proof.verify(&bc1_vec[i].e).is_err())
+        .collect::<Vec<usize>>();
+    check(Gg18Keygen, "phase3", InvalidCorrectKeyProof, bad_key)?;
+    let bad_modulus = (0..bc1_vec.len())
+        .filter(|&i| {
+            bc1_vec[i]
+                .paillier_blum_modulus_proof
+                .verify(&bc1_vec[i].e)
+                .is_err()
+        })
+        .collect::<Vec<usize>>();
+    check(
+        Gg18Keygen,
+        "phase3",
+        InvalidPaillierBlumModulusProof,
+        bad_modulus,
+    )?;
+    let bad_statement = (0..bc1_vec.len())
+        .filter(|&i| !bc1_vec[i].verify_dlog_statement())
+        .collect::<Vec<usize>>();
+    check(
+        Gg18Keygen,
+        "phase1",
+        InvalidCompositeDLogProof,
+        bad_statement,
+    )?;
+    Ok(())
+}
+
+// the ring-Pedersen parameters of every party are checked before proving against them
+fn prove_no_small_factor(
+    protocol: Protocol,
+    dk: &DecryptionKey,
+    bc1_vec: &[KeyGenBroadcastMessage1],
+) -> Result<Vec<NoSmallFactorProof>, Error> {
+    let bad_statement = (0..bc1_vec.len())
+        .filter(|&i| !bc1_vec[i].verify_dlog_statement())
+        .collect::<Vec<usize>>();
+    check(protocol, "phase1", InvalidCompositeDLogProof, bad_statement)?;
+    Ok(bc1_vec
+        .iter()
+        .map(|bc1| NoSmallFactorProof::prove(dk, &bc1.dlog_statement))
+        .collect())
+}
+
+// keygen and refresh input holds one entry per party
+fn check_len(
+    protocol: Protocol,
+    round: &'static str,
+    params: &Parameters,
+    lens: &[usize],
+) -> Result<(), Error> {
+    if lens.iter().all(|&len| len == params.share_count as usize) {
+        Ok(())
+    } else {
+        Err(Error::new(protocol, round, LengthMismatch))
+    }
+}
+
+// fails with the parties that did not pass a check
+pub(crate) fn check(
+    protocol: Protocol,
+    round: &'static str,
+    kind: ErrorKind,
+    bad_actors: Vec<usize>,
+) -> Result<(), Error> {
+    if bad_actors.is_empty() {
         Ok(())
     } else {
-        Err(InvalidSig)
+        Err(Error::new(protocol, round, kind).with_parties(bad_actors))
     }
 }
@@ -0,0 +1,428 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! gg18 key generation. The rounds follow `examples/gg18_keygen_client.rs`:
+//! 1. broadcast commitment to y_i and the paillier key
+//! 2. broadcast decommitment of y_i, with proofs that the paillier key has no small factors
+//! 3. send the vss share of u_i to every party (point to point)
+//! 4. broadcast the vss commitments
+//! 5. broadcast proof of knowledge of x_i
+
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::traits::*;
+use curv::{FE, GE};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use zk_paillier::zkproofs::DLogStatement;
+
+use super::{blame, Error, Inbox, Msg};
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, Parameters, SharedKeys,
+};
+use crate::utilities::bip32::ChildKey;
+use crate::utilities::zk_no_small_factor::NoSmallFactorProof;
+
+const ROUNDS: u16 = 5;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ProtocolMessage {
+    Round1(KeyGenBroadcastMessage1),
+    Round2(KeyGenDecommitMessage1, Vec<NoSmallFactorProof>),
+    Round3(FE),
+    Round4(VerifiableSS),
+    Round5(DLogProof),
+}
+
+impl ProtocolMessage {
+    pub fn round(&self) -> u16 {
+        match self {
+            ProtocolMessage::Round1(_) => 1,
+            ProtocolMessage::Round2(..) => 2,
+            ProtocolMessage::Round3(_) => 3,
+            ProtocolMessage::Round4(_) => 4,
+            ProtocolMessage::Round5(_) => 5,
+        }
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        match self {
+            ProtocolMessage::Round3(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Output of the key generation: the data the keygen example stores on disk, plus the
+/// ring-Pedersen parameters of all parties.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalKey {
+    pub party_keys: Keys,
+    pub shared_keys: SharedKeys,
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+    pub vss_scheme_vec: Vec<VerifiableSS>,
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    /// ring-Pedersen parameters of every party, for range proofs during signing
+    pub h1_h2_N_tilde_vec: Vec<DLogStatement>,
+    pub y_sum: GE,
+}
+
+impl LocalKey {
+    /// Local key of the non-hardened BIP32 child at path, see SharedKeys::derive_child. The
+    /// tweak is added to the constant term of the first vss scheme, which moves every
+    /// commitment of get_commitments_to_xi by tweak * G, and y_sum becomes the child public
+    /// key, so the result can be used with the Sign state machine.
+    pub fn derive_child(
+        &self,
+        chain_code: &[u8; 32],
+        path: &[u32],
+    ) -> Result<(LocalKey, ChildKey), Error> {
+        if self.vss_scheme_vec.is_empty() {
+            return Err(Error::InvalidParameters);
+        }
+        let (shared_keys, child) = self
+            .shared_keys
+            .derive_child(chain_code, path)
+            .map_err(Error::Protocol)?;
+        let mut local_key = self.clone();
+        local_key.shared_keys = shared_keys;
+        let constant = &mut local_key.vss_scheme_vec[0].commitments[0];
+        *constant = *constant + GE::generator() * child.tweak;
+        local_key.y_sum = child.public_key;
+        Ok((local_key, child))
+    }
+}
+
+pub struct Keygen {
+    i: u16,
+    t: u16,
+    n: u16,
+    round: u16,
+    party_keys: Keys,
+    inbox: Inbox<ProtocolMessage>,
+    outgoing: Vec<Msg<ProtocolMessage>>,
+
+    decom_i: Option<KeyGenDecommitMessage1>,
+    bc1_vec: Vec<KeyGenBroadcastMessage1>,
+    decom_vec: Vec<KeyGenDecommitMessage1>,
+    no_small_factor_proofs_vec: Vec<Vec<NoSmallFactorProof>>,
+    y_vec: Vec<GE>,
+    secret_shares: Vec<FE>,
+    vss_scheme: Option<VerifiableSS>,
+    party_shares: Vec<FE>,
+    vss_scheme_vec: Vec<VerifiableSS>,
+    shared_keys: Option<SharedKeys>,
+    dlog_proof: Option<DLogProof>,
+    failed: Option<Error>,
+    output: Option<LocalKey>,
+}
+
+impl Keygen {
+    /// Starts keygen for party `i` (1-based) out of `n` with threshold `t`.
+    /// Paillier keys are generated from safe primes.
+    pub fn new(i: u16, t: u16, n: u16) -> Result<Self, Error> {
+        // checked before the expensive key generation
+        Self::validate_parameters(i, t, n)?;
+        Ok(Self::start(i, t, n, Keys::create_safe_prime(i as usize)))
+    }
+
+    /// Starts keygen using already generated party keys.
+    pub fn with_keys(i: u16, t: u16, n: u16, party_keys: Keys) -> Result<Self, Error> {
+        Self::validate_parameters(i, t, n)?;
+        Ok(Self::start(i, t, n, party_keys))
+    }
+
+    fn start(i: u16, t: u16, n: u16, party_keys: Keys) -> Self {
+        let (bc_i, decom_i) = party_keys.phase1_broadcast_phase3_proof_of_correct_key();
+        let mut keygen = Keygen {
+            i,
+            t,
+            n,
+            round: 1,
+            party_keys,
+            inbox: Inbox::new(),
+            outgoing: Vec::new(),
+            decom_i: Some(decom_i),
+            bc1_vec: vec![bc_i.clone()],
+            decom_vec: Vec::new(),
+            no_small_factor_proofs_vec: Vec::new(),
+            y_vec: Vec::new(),
+            secret_shares: Vec::new(),
+            vss_scheme: None,
+            party_shares: Vec::new(),
+            vss_scheme_vec: Vec::new(),
+            shared_keys: None,
+            dlog_proof: None,
+            failed: None,
+            output: None,
+        };
+        keygen.broadcast(ProtocolMessage::Round1(bc_i));
+        keygen
+    }
+
+    fn validate_parameters(i: u16, t: u16, n: u16) -> Result<(), Error> {
+        if n < 2 || t >= n || i == 0 || i > n {
+            Err(Error::InvalidParameters)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn party_ind(&self) -> u16 {
+        self.i
+    }
+
+    pub fn current_round(&self) -> u16 {
+        self.round
+    }
+
+    pub fn total_rounds(&self) -> u16 {
+        ROUNDS
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Messages to be delivered by the caller. The queue should be drained after every call
+    /// to `handle_incoming`.
+    pub fn message_queue(&mut self) -> &mut Vec<Msg<ProtocolMessage>> {
+        &mut self.outgoing
+    }
+
+    pub fn pick_output(&mut self) -> Option<LocalKey> {
+        self.output.take()
+    }
+
+    /// Once a round has failed the protocol can not go on, its error is returned again from
+    /// every later call.
+    pub fn handle_incoming(&mut self, msg: Msg<ProtocolMessage>) -> Result<(), Error> {
+        let (sender, round) = (msg.sender, msg.round);
+        if let Some(err) = &self.failed {
+            return Err(err.clone());
+        }
+        if self.round > ROUNDS {
+            return Err(Error::AlreadyFinished);
+        }
+        if sender == 0 || sender > self.n || sender == self.i {
+            return Err(Error::InvalidSender { sender, round });
+        }
+        if msg.body.round() != round {
+            return Err(Error::MismatchedRound { sender, round });
+        }
+        let expected_receiver = if msg.body.is_broadcast() {
+            None
+        } else {
+            Some(self.i)
+        };
+        if msg.receiver != expected_receiver {
+            return Err(Error::InvalidReceiver { sender, round });
+        }
+        if round < self.round {
+            return Err(Error::StaleMessage { sender, round });
+        }
+        self.inbox.insert(round, sender, msg.body)?;
+
+        while self.round <= ROUNDS && self.inbox.received(self.round) == (self.n - 1) as usize {
+            if let Err(err) = self.proceed() {
+                self.failed = Some(err.clone());
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn params(&self) -> Parameters {
+        Parameters {
+            threshold: self.t,
+            share_count: self.n,
+        }
+    }
+
+    // parties in the order of the collected messages
+    fn parties(&self) -> Vec<u16> {
+        (1..=self.n).collect()
+    }
+
+    fn broadcast(&mut self, body: ProtocolMessage) {
+        self.outgoing.push(Msg {
+            sender: self.i,
+            receiver: None,
+            round: body.round(),
+            body,
+        });
+    }
+
+    fn send_to(&mut self, receiver: u16, body: ProtocolMessage) {
+        self.outgoing.push(Msg {
+            sender: self.i,
+            receiver: Some(receiver),
+            round: body.round(),
+            body,
+        });
+    }
+
+    fn proceed(&mut self) -> Result<(), Error> {
+        match self.round {
+            1 => self.proceed_round1(),
+            2 => self.proceed_round2(),
+            3 => self.proceed_round3(),
+            4 => self.proceed_round4(),
+            _ => self.proceed_round5(),
+        }?;
+        self.round += 1;
+        Ok(())
+    }
+
+    fn proceed_round1(&mut self) -> Result<(), Error> {
+        let bc_i = self.bc1_vec.remove(0);
+        self.bc1_vec = self
+            .inbox
+            .collect(self.round, self.i, Some(bc_i), |m| match m {
+                ProtocolMessage::Round1(bc) => Some(bc),
+                _ => None,
+            })?;
+        let proofs = self
+            .party_keys
+            .phase3_prove_no_small_factor(&self.bc1_vec)
+            .map_err(|err| blame(err, &self.parties()))?;
+        let decom_i = self.decom_i.take().ok_or(Error::InvalidState)?;
+        self.decom_vec.push(decom_i.clone());
+        self.no_small_factor_proofs_vec.push(proofs.clone());
+        self.broadcast(ProtocolMessage::Round2(decom_i, proofs));
+        Ok(())
+    }
+
+    fn proceed_round2(&mut self) -> Result<(), Error> {
+        let decom_i = self.decom_vec.remove(0);
+        let proofs_i = self.no_small_factor_proofs_vec.remove(0);
+        let (decom_vec, proofs_vec) = self
+            .inbox
+            .collect(self.round, self.i, Some((decom_i, proofs_i)), |m| match m {
+                ProtocolMessage::Round2(decom, proofs) => Some((decom, proofs)),
+                _ => None,
+            })?
+            .into_iter()
+            .unzip();
+        self.decom_vec = decom_vec;
+        self.no_small_factor_proofs_vec = proofs_vec;
+        self.y_vec = self.decom_vec.iter().map(|decom| decom.y_i).collect();
+
+        let (vss_scheme, secret_shares, _index) = self
+            .party_keys
+            .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
+                &self.params(),
+                &self.decom_vec,
+                &self.bc1_vec,
+            )
+            .map_err(|err| blame(err, &self.parties()))?;
+        self.party_keys
+            .phase3_verify_no_small_factor(
+                &self.params(),
+                &self.bc1_vec,
+                &self.no_small_factor_proofs_vec,
+                self.i as usize,
+            )
+            .map_err(|err| blame(err, &self.parties()))?;
+        for j in 1..=self.n {
+            if j != self.i {
+                let share = secret_shares[(j - 1) as usize];
+                self.send_to(j, ProtocolMessage::Round3(share));
+            }
+        }
+        self.secret_shares = secret_shares;
+        self.vss_scheme = Some(vss_scheme);
+        Ok(())
+    }
+
+    fn proceed_round3(&mut self) -> Result<(), Error> {
+        let own_share = self.secret_shares[(self.i - 1) as usize];
+        self.party_shares =
+            self.inbox
+                .collect(self.round, self.i, Some(own_share), |m| match m {
+                    ProtocolMessage::Round3(share) => Some(share),
+                    _ => None,
+                })?;
+        let vss_scheme = self.vss_scheme.clone().ok_or(Error::InvalidState)?;
+        self.broadcast(ProtocolMessage::Round4(vss_scheme));
+        Ok(())
+    }
+
+    fn proceed_round4(&mut self) -> Result<(), Error> {
+        let vss_scheme = self.vss_scheme.take().ok_or(Error::InvalidState)?;
+        self.vss_scheme_vec =
+            self.inbox
+                .collect(self.round, self.i, Some(vss_scheme), |m| match m {
+                    ProtocolMessage::Round4(vss) => Some(vss),
+                    _ => None,
+                })?;
+        let (shared_keys, dlog_proof) = self
+            .party_keys
+            .phase2_verify_vss_construct_keypair_phase3_pok_dlog(
+                &self.params(),
+                &self.y_vec,
+                &self.party_shares,
+                &self.vss_scheme_vec,
+                self.i as usize,
+            )
+            .map_err(|err| blame(err, &self.parties()))?;
+        self.shared_keys = Some(shared_keys);
+        self.dlog_proof = Some(dlog_proof.clone());
+        self.broadcast(ProtocolMessage::Round5(dlog_proof));
+        Ok(())
+    }
+
+    fn proceed_round5(&mut self) -> Result<(), Error> {
+        let dlog_proof = self.dlog_proof.take().ok_or(Error::InvalidState)?;
+        let dlog_proof_vec =
+            self.inbox
+                .collect(self.round, self.i, Some(dlog_proof), |m| match m {
+                    ProtocolMessage::Round5(proof) => Some(proof),
+                    _ => None,
+                })?;
+        Keys::verify_dlog_proofs(&self.params(), &dlog_proof_vec, &self.y_vec)
+            .map_err(|err| blame(err, &self.parties()))?;
+
+        let (head, tail) = self.y_vec.split_at(1);
+        let y_sum = tail.iter().fold(head[0], |acc, x| acc + x);
+        let paillier_key_vec = self
+            .bc1_vec
+            .iter()
+            .map(|bc1| bc1.e.clone())
+            .collect::<Vec<EncryptionKey>>();
+        let h1_h2_N_tilde_vec = self
+            .bc1_vec
+            .iter()
+            .map(|bc1| bc1.dlog_statement.clone())
+            .collect::<Vec<DLogStatement>>();
+
+        self.output = Some(LocalKey {
+            party_keys: self.party_keys.clone(),
+            shared_keys: self.shared_keys.take().ok_or(Error::InvalidState)?,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+            vss_scheme_vec: self.vss_scheme_vec.clone(),
+            paillier_key_vec,
+            h1_h2_N_tilde_vec,
+            y_sum,
+        });
+        Ok(())
+    }
+}
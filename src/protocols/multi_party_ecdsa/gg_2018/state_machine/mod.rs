@@ -0,0 +1,173 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Sans-IO state machines driving the gg18 protocols.
+//!
+//! A state machine never touches the network: the caller feeds it incoming messages using
+//! `handle_incoming`, drains the messages it wants to send using `message_queue` and picks the
+//! result once `is_finished` returns true. Parties are identified by their 1-based index.
+//! Point-to-point messages carry secret data, the transport is responsible for delivering them
+//! over an authenticated and encrypted channel.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub mod keygen;
+pub mod sign;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Msg<B> {
+    pub sender: u16,
+    /// `None` for a broadcast message
+    pub receiver: Option<u16>,
+    pub round: u16,
+    pub body: B,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    InvalidParameters,
+    InvalidSender {
+        sender: u16,
+        round: u16,
+    },
+    InvalidReceiver {
+        sender: u16,
+        round: u16,
+    },
+    MismatchedRound {
+        sender: u16,
+        round: u16,
+    },
+    StaleMessage {
+        sender: u16,
+        round: u16,
+    },
+    DuplicateMessage {
+        sender: u16,
+        round: u16,
+    },
+    AlreadyFinished,
+    InvalidState,
+    /// A protocol check failed. The `parties` of the inner error are the 1-based indices of
+    /// the parties to blame.
+    Protocol(crate::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidParameters => write!(f, "invalid protocol parameters"),
+            Error::InvalidSender { sender, round } => {
+                write!(f, "round {}: unexpected sender {}", round, sender)
+            }
+            Error::InvalidReceiver { sender, round } => {
+                write!(
+                    f,
+                    "round {}: wrong receiver in message from {}",
+                    round, sender
+                )
+            }
+            Error::MismatchedRound { sender, round } => {
+                write!(
+                    f,
+                    "round {}: message from {} is of another round",
+                    round, sender
+                )
+            }
+            Error::StaleMessage { sender, round } => {
+                write!(f, "round {}: stale message from {}", round, sender)
+            }
+            Error::DuplicateMessage { sender, round } => {
+                write!(f, "round {}: duplicate message from {}", round, sender)
+            }
+            Error::AlreadyFinished => write!(f, "protocol already finished"),
+            Error::InvalidState => write!(f, "internal state is missing"),
+            Error::Protocol(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// replaces the positions reported by a failed check with the indices of the parties, positions
+// without a party are dropped
+fn blame(err: crate::Error, parties: &[u16]) -> Error {
+    let blamed = err
+        .parties
+        .iter()
+        .filter_map(|&pos| parties.get(pos))
+        .map(|&i| i as usize)
+        .collect();
+    Error::Protocol(err.with_parties(blamed))
+}
+
+// messages received from the other parties, indexed by round and sender
+struct Inbox<B> {
+    msgs: BTreeMap<u16, BTreeMap<u16, B>>,
+}
+
+impl<B> Inbox<B> {
+    fn new() -> Self {
+        Inbox {
+            msgs: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, round: u16, sender: u16, body: B) -> Result<(), Error> {
+        let round_msgs = self.msgs.entry(round).or_insert_with(BTreeMap::new);
+        if round_msgs.contains_key(&sender) {
+            return Err(Error::DuplicateMessage { sender, round });
+        }
+        round_msgs.insert(sender, body);
+        Ok(())
+    }
+
+    fn received(&self, round: u16) -> usize {
+        self.msgs.get(&round).map(|m| m.len()).unwrap_or(0)
+    }
+
+    // values carried by the messages of a round ordered by sender, our own value (if any) is
+    // inserted at our position
+    fn collect<T>(
+        &mut self,
+        round: u16,
+        own_index: u16,
+        own: Option<T>,
+        extract: impl Fn(B) -> Option<T>,
+    ) -> Result<Vec<T>, Error> {
+        let mut own = own;
+        let mut values = Vec::new();
+        let round_msgs = self.msgs.remove(&round).unwrap_or_else(BTreeMap::new);
+        for (sender, body) in round_msgs {
+            if sender > own_index {
+                if let Some(own) = own.take() {
+                    values.push(own);
+                }
+            }
+            values.push(extract(body).ok_or(Error::MismatchedRound { sender, round })?);
+        }
+        if let Some(own) = own.take() {
+            values.push(own);
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod test;
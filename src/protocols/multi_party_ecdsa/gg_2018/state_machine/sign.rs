@@ -0,0 +1,550 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! gg18 signing. The rounds follow `examples/gg18_sign_client.rs`:
+//! 1. broadcast commitment to g^gamma_i and MtA messages A (encryption of k_i, with a range
+//!    proof for every other signer)
+//! 2. send MtA messages B for gamma_i and w_i with range proofs (point to point)
+//! 3. broadcast delta_i
+//! 4. broadcast decommitment to g^gamma_i, compute R
+//! 5. broadcast phase 5A commitment
+//! 6. broadcast phase 5A decommitment and phase 5B proofs
+//! 7. broadcast phase 5C commitment
+//! 8. broadcast phase 5C decommitment
+//! 9. broadcast s_i and output the signature
+
+use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::{BigInt, FE, GE};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::keygen::LocalKey;
+use super::{blame, Error, Inbox, Msg};
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
+    Keys, LocalSignature, PartyPrivate, Phase5ADecom1, Phase5Com1, Phase5Com2, Phase5DDecom2,
+    SignBroadcastPhase1, SignDecommitPhase1, SignKeys, SignatureRecid,
+};
+use crate::utilities::mta::{MessageAWithRangeProof, MessageBWithRangeProof};
+use crate::ErrorKind::LengthMismatch;
+use crate::Protocol::MtA;
+
+const ROUNDS: u16 = 9;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ProtocolMessage {
+    /// one message A for every other signer, in the order of the signers
+    Round1(SignBroadcastPhase1, Vec<MessageAWithRangeProof>),
+    Round2(MessageBWithRangeProof, MessageBWithRangeProof),
+    Round3(FE),
+    Round4(SignDecommitPhase1),
+    Round5(Phase5Com1),
+    Round6(Phase5ADecom1, HomoELGamalProof, DLogProof),
+    Round7(Phase5Com2),
+    Round8(Phase5DDecom2),
+    Round9(FE),
+}
+
+impl ProtocolMessage {
+    pub fn round(&self) -> u16 {
+        match self {
+            ProtocolMessage::Round1(..) => 1,
+            ProtocolMessage::Round2(..) => 2,
+            ProtocolMessage::Round3(_) => 3,
+            ProtocolMessage::Round4(_) => 4,
+            ProtocolMessage::Round5(_) => 5,
+            ProtocolMessage::Round6(..) => 6,
+            ProtocolMessage::Round7(_) => 7,
+            ProtocolMessage::Round8(_) => 8,
+            ProtocolMessage::Round9(_) => 9,
+        }
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        match self {
+            ProtocolMessage::Round2(..) => false,
+            _ => true,
+        }
+    }
+}
+
+pub struct Sign {
+    i: u16,
+    signers: Vec<u16>,
+    s: Vec<usize>,
+    round: u16,
+    message: BigInt,
+    local_key: LocalKey,
+    sign_keys: SignKeys,
+    inbox: Inbox<ProtocolMessage>,
+    outgoing: Vec<Msg<ProtocolMessage>>,
+
+    decommit: SignDecommitPhase1,
+    m_a_vec: Vec<MessageAWithRangeProof>,
+    bc1_vec: Vec<SignBroadcastPhase1>,
+    b_proof_vec: Vec<DLogProof>,
+    beta_vec: Vec<FE>,
+    ni_vec: Vec<FE>,
+    delta_i: Option<FE>,
+    sigma_i: Option<FE>,
+    delta_inv: Option<FE>,
+    R: Option<GE>,
+    local_sig: Option<LocalSignature>,
+    phase5a_round6: Option<(Phase5ADecom1, HomoELGamalProof, DLogProof)>,
+    commit5a_vec: Vec<Phase5Com1>,
+    decommit5a_vec: Vec<Phase5ADecom1>,
+    phase5_com2: Option<Phase5Com2>,
+    phase5d_decom2: Option<Phase5DDecom2>,
+    commit5c_vec: Vec<Phase5Com2>,
+    failed: Option<Error>,
+    output: Option<SignatureRecid>,
+}
+
+impl Sign {
+    /// Starts signing of the hashed `message` by the parties `signers` (1-based keygen
+    /// indices, at least t+1 of them, including the local party).
+    pub fn new(message: BigInt, mut signers: Vec<u16>, local_key: LocalKey) -> Result<Self, Error> {
+        signers.sort();
+        signers.dedup();
+        if signers.len() <= local_key.t as usize
+            || signers.iter().any(|&j| j == 0 || j > local_key.n)
+            || !signers.contains(&local_key.i)
+            || local_key.h1_h2_N_tilde_vec.len() != local_key.n as usize
+        {
+            return Err(Error::InvalidParameters);
+        }
+        let i = local_key.i;
+        let s = signers
+            .iter()
+            .map(|&j| (j - 1) as usize)
+            .collect::<Vec<usize>>();
+
+        let private =
+            PartyPrivate::set_private(local_key.party_keys.clone(), local_key.shared_keys.clone());
+        let sign_keys = SignKeys::create(
+            &private,
+            &local_key.vss_scheme_vec[(i - 1) as usize],
+            (i - 1) as usize,
+            &s,
+        );
+        let (com, decommit) = sign_keys.phase1_broadcast();
+        // the range proof for j is made against the ring-Pedersen parameters of j
+        let m_a_vec = signers
+            .iter()
+            .filter(|&&j| j != i)
+            .map(|&j| {
+                let statement_j = &local_key.h1_h2_N_tilde_vec[(j - 1) as usize];
+                MessageAWithRangeProof::a(&sign_keys.k_i, &local_key.party_keys.ek, statement_j).0
+            })
+            .collect::<Vec<MessageAWithRangeProof>>();
+
+        let mut sign = Sign {
+            i,
+            signers,
+            s,
+            round: 1,
+            message,
+            local_key,
+            sign_keys,
+            inbox: Inbox::new(),
+            outgoing: Vec::new(),
+            decommit,
+            m_a_vec: m_a_vec.clone(),
+            bc1_vec: Vec::new(),
+            b_proof_vec: Vec::new(),
+            beta_vec: Vec::new(),
+            ni_vec: Vec::new(),
+            delta_i: None,
+            sigma_i: None,
+            delta_inv: None,
+            R: None,
+            local_sig: None,
+            phase5a_round6: None,
+            commit5a_vec: Vec::new(),
+            decommit5a_vec: Vec::new(),
+            phase5_com2: None,
+            phase5d_decom2: None,
+            commit5c_vec: Vec::new(),
+            failed: None,
+            output: None,
+        };
+        sign.broadcast(ProtocolMessage::Round1(com, m_a_vec));
+        Ok(sign)
+    }
+
+    pub fn party_ind(&self) -> u16 {
+        self.i
+    }
+
+    pub fn current_round(&self) -> u16 {
+        self.round
+    }
+
+    pub fn total_rounds(&self) -> u16 {
+        ROUNDS
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Messages to be delivered by the caller. The queue should be drained after every call
+    /// to `handle_incoming`.
+    pub fn message_queue(&mut self) -> &mut Vec<Msg<ProtocolMessage>> {
+        &mut self.outgoing
+    }
+
+    /// The signature, already verified against the joint public key.
+    pub fn pick_output(&mut self) -> Option<SignatureRecid> {
+        self.output.take()
+    }
+
+    /// Once a round has failed the protocol can not go on, its error is returned again from
+    /// every later call.
+    pub fn handle_incoming(&mut self, msg: Msg<ProtocolMessage>) -> Result<(), Error> {
+        let (sender, round) = (msg.sender, msg.round);
+        if let Some(err) = &self.failed {
+            return Err(err.clone());
+        }
+        if self.round > ROUNDS {
+            return Err(Error::AlreadyFinished);
+        }
+        if sender == self.i || !self.signers.contains(&sender) {
+            return Err(Error::InvalidSender { sender, round });
+        }
+        if msg.body.round() != round {
+            return Err(Error::MismatchedRound { sender, round });
+        }
+        let expected_receiver = if msg.body.is_broadcast() {
+            None
+        } else {
+            Some(self.i)
+        };
+        if msg.receiver != expected_receiver {
+            return Err(Error::InvalidReceiver { sender, round });
+        }
+        if round < self.round {
+            return Err(Error::StaleMessage { sender, round });
+        }
+        self.inbox.insert(round, sender, msg.body)?;
+
+        while self.round <= ROUNDS && self.inbox.received(self.round) == self.signers.len() - 1 {
+            if let Err(err) = self.proceed() {
+                self.failed = Some(err.clone());
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn broadcast(&mut self, body: ProtocolMessage) {
+        self.outgoing.push(Msg {
+            sender: self.i,
+            receiver: None,
+            round: body.round(),
+            body,
+        });
+    }
+
+    fn send_to(&mut self, receiver: u16, body: ProtocolMessage) {
+        self.outgoing.push(Msg {
+            sender: self.i,
+            receiver: Some(receiver),
+            round: body.round(),
+            body,
+        });
+    }
+
+    // the other signers, in the order their messages are collected
+    fn others(&self) -> Vec<u16> {
+        self.signers
+            .iter()
+            .cloned()
+            .filter(|&j| j != self.i)
+            .collect()
+    }
+
+    // position of the local party among the signers
+    fn position(&self) -> usize {
+        self.signers.iter().position(|&j| j == self.i).unwrap_or(0)
+    }
+
+    fn proceed(&mut self) -> Result<(), Error> {
+        match self.round {
+            1 => self.proceed_round1(),
+            2 => self.proceed_round2(),
+            3 => self.proceed_round3(),
+            4 => self.proceed_round4(),
+            5 => self.proceed_round5(),
+            6 => self.proceed_round6(),
+            7 => self.proceed_round7(),
+            8 => self.proceed_round8(),
+            _ => self.proceed_round9(),
+        }?;
+        self.round += 1;
+        Ok(())
+    }
+
+    fn proceed_round1(&mut self) -> Result<(), Error> {
+        let round1_vec = self.inbox.collect(self.round, self.i, None, |m| match m {
+            ProtocolMessage::Round1(bc1, m_a) => Some((bc1, m_a)),
+            _ => None,
+        })?;
+        let statement_i = self.local_key.h1_h2_N_tilde_vec[(self.i - 1) as usize].clone();
+        for (j, (bc1_j, m_a_vec_j)) in self.others().into_iter().zip(round1_vec) {
+            let blame_j = |err: crate::Error| blame(err.with_parties(vec![0]), &[j]);
+            // j skips itself in the vector of messages A
+            let pos = self
+                .signers
+                .iter()
+                .filter(|&&k| k != j)
+                .position(|&k| k == self.i)
+                .ok_or(Error::InvalidState)?;
+            let m_a_j = match m_a_vec_j.get(pos) {
+                Some(m_a_j) if m_a_vec_j.len() == self.signers.len() - 1 => m_a_j,
+                _ => return Err(blame_j(crate::Error::new(MtA, "message a", LengthMismatch))),
+            };
+            let ek_j = &self.local_key.paillier_key_vec[(j - 1) as usize];
+            let statement_j = &self.local_key.h1_h2_N_tilde_vec[(j - 1) as usize];
+            let (m_b_gamma, beta_gamma, _) = MessageBWithRangeProof::b_with_check(
+                &self.sign_keys.gamma_i,
+                ek_j,
+                m_a_j,
+                statement_j,
+                &statement_i,
+            )
+            .map_err(blame_j)?;
+            let (m_b_w, beta_wi, _) = MessageBWithRangeProof::b_with_check(
+                &self.sign_keys.w_i,
+                ek_j,
+                m_a_j,
+                statement_j,
+                &statement_i,
+            )
+            .map_err(blame_j)?;
+            self.bc1_vec.push(bc1_j);
+            self.beta_vec.push(beta_gamma);
+            self.ni_vec.push(beta_wi);
+            self.send_to(j, ProtocolMessage::Round2(m_b_gamma, m_b_w));
+        }
+        Ok(())
+    }
+
+    fn proceed_round2(&mut self) -> Result<(), Error> {
+        let round2_vec = self.inbox.collect(self.round, self.i, None, |m| match m {
+            ProtocolMessage::Round2(m_b_gamma, m_b_w) => Some((m_b_gamma, m_b_w)),
+            _ => None,
+        })?;
+
+        let xi_com_vec = Keys::get_commitments_to_xi(&self.local_key.vss_scheme_vec);
+        let mut alpha_vec = Vec::new();
+        let mut miu_vec = Vec::new();
+        let dk = &self.local_key.party_keys.dk;
+        let ek = &self.local_key.party_keys.ek;
+        let statement_i = &self.local_key.h1_h2_N_tilde_vec[(self.i - 1) as usize];
+        let others = self.others();
+        for ((j, (m_b_gamma, m_b_w)), m_a) in others.into_iter().zip(round2_vec).zip(&self.m_a_vec)
+        {
+            let blame_j = |err: crate::Error| blame(err.with_parties(vec![0]), &[j]);
+            // gamma_j is bound to the g^gamma_j opened in phase 4
+            let alpha_ij_gamma = m_b_gamma
+                .verify_proofs_get_alpha_with_check(
+                    dk,
+                    ek,
+                    &m_a.c,
+                    statement_i,
+                    &m_b_gamma.b_proof.pk,
+                )
+                .map_err(blame_j)?;
+
+            // w_j used in the MtAwc must match the public commitment to x_j
+            let g_w_j = Keys::update_commitments_to_xi(
+                &xi_com_vec[(j - 1) as usize],
+                &self.local_key.vss_scheme_vec[(j - 1) as usize],
+                (j - 1) as usize,
+                &self.s,
+            );
+            let alpha_ij_wi = m_b_w
+                .verify_proofs_get_alpha_with_check(dk, ek, &m_a.c, statement_i, &g_w_j)
+                .map_err(blame_j)?;
+            alpha_vec.push(alpha_ij_gamma);
+            miu_vec.push(alpha_ij_wi);
+            self.b_proof_vec.push(m_b_gamma.b_proof);
+        }
+
+        let delta_i = self
+            .sign_keys
+            .phase2_delta_i(&alpha_vec, &self.beta_vec)
+            .map_err(Error::Protocol)?;
+        let sigma_i = self
+            .sign_keys
+            .phase2_sigma_i(&miu_vec, &self.ni_vec)
+            .map_err(Error::Protocol)?;
+        // the MtA shares are only needed for delta_i and sigma_i
+        alpha_vec.zeroize();
+        miu_vec.zeroize();
+        self.beta_vec.zeroize();
+        self.ni_vec.zeroize();
+        self.delta_i = Some(delta_i);
+        self.sigma_i = Some(sigma_i);
+        self.broadcast(ProtocolMessage::Round3(delta_i));
+        Ok(())
+    }
+
+    fn proceed_round3(&mut self) -> Result<(), Error> {
+        let delta_i = self.delta_i.take().ok_or(Error::InvalidState)?;
+        let delta_vec = self
+            .inbox
+            .collect(self.round, self.i, Some(delta_i), |m| match m {
+                ProtocolMessage::Round3(delta_j) => Some(delta_j),
+                _ => None,
+            })?;
+        let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec).map_err(Error::Protocol)?;
+        self.delta_inv = Some(delta_inv);
+        self.broadcast(ProtocolMessage::Round4(self.decommit.clone()));
+        Ok(())
+    }
+
+    fn proceed_round4(&mut self) -> Result<(), Error> {
+        let decommit_vec = self.inbox.collect(self.round, self.i, None, |m| match m {
+            ProtocolMessage::Round4(decommit_j) => Some(decommit_j),
+            _ => None,
+        })?;
+        let delta_inv = self.delta_inv.ok_or(Error::InvalidState)?;
+        let b_proof_vec = self.b_proof_vec.iter().collect::<Vec<&DLogProof>>();
+        let R = SignKeys::phase4(&delta_inv, &b_proof_vec, decommit_vec, &self.bc1_vec)
+            .map_err(|err| blame(err, &self.others()))?;
+        // adding local g_gamma_i
+        let R = R + self.decommit.g_gamma_i * delta_inv;
+
+        let sigma_i = self.sigma_i.ok_or(Error::InvalidState)?;
+        let local_sig = LocalSignature::phase5_local_sig(
+            &self.sign_keys.k_i,
+            &self.message,
+            &R,
+            &sigma_i,
+            &self.local_key.y_sum,
+        )
+        .map_err(Error::Protocol)?;
+        let (phase5_com, phase5a_decom, helgamal_proof, dlog_proof_rho) =
+            local_sig.phase5a_broadcast_5b_zkproof();
+
+        self.R = Some(R);
+        self.local_sig = Some(local_sig);
+        self.phase5a_round6 = Some((phase5a_decom, helgamal_proof, dlog_proof_rho));
+        self.broadcast(ProtocolMessage::Round5(phase5_com));
+        Ok(())
+    }
+
+    fn proceed_round5(&mut self) -> Result<(), Error> {
+        self.commit5a_vec = self.inbox.collect(self.round, self.i, None, |m| match m {
+            ProtocolMessage::Round5(com) => Some(com),
+            _ => None,
+        })?;
+        let (decom, proof, dlog_proof) = self.phase5a_round6.clone().ok_or(Error::InvalidState)?;
+        self.broadcast(ProtocolMessage::Round6(decom, proof, dlog_proof));
+        Ok(())
+    }
+
+    fn proceed_round6(&mut self) -> Result<(), Error> {
+        let round6_vec = self.inbox.collect(self.round, self.i, None, |m| match m {
+            ProtocolMessage::Round6(decom, proof, dlog_proof) => Some((decom, proof, dlog_proof)),
+            _ => None,
+        })?;
+        let (decom_i, _, _) = self.phase5a_round6.take().ok_or(Error::InvalidState)?;
+        let local_sig = self.local_sig.as_ref().ok_or(Error::InvalidState)?;
+        let R = self.R.ok_or(Error::InvalidState)?;
+
+        let mut decom_vec = Vec::new();
+        let mut elgamal_proof_vec = Vec::new();
+        let mut dlog_proof_vec = Vec::new();
+        for (decom, proof, dlog_proof) in round6_vec {
+            decom_vec.push(decom);
+            elgamal_proof_vec.push(proof);
+            dlog_proof_vec.push(dlog_proof);
+        }
+        let (phase5_com2, phase5d_decom2) = local_sig
+            .phase5c(
+                &decom_vec,
+                &self.commit5a_vec,
+                &elgamal_proof_vec,
+                &dlog_proof_vec,
+                &decom_i.V_i,
+                &R,
+            )
+            .map_err(|err| blame(err, &self.others()))?;
+
+        decom_vec.insert(self.position(), decom_i);
+        self.decommit5a_vec = decom_vec;
+        self.phase5_com2 = Some(phase5_com2.clone());
+        self.phase5d_decom2 = Some(phase5d_decom2);
+        self.broadcast(ProtocolMessage::Round7(phase5_com2));
+        Ok(())
+    }
+
+    fn proceed_round7(&mut self) -> Result<(), Error> {
+        let phase5_com2 = self.phase5_com2.take().ok_or(Error::InvalidState)?;
+        self.commit5c_vec =
+            self.inbox
+                .collect(self.round, self.i, Some(phase5_com2), |m| match m {
+                    ProtocolMessage::Round7(com) => Some(com),
+                    _ => None,
+                })?;
+        let phase5d_decom2 = self.phase5d_decom2.clone().ok_or(Error::InvalidState)?;
+        self.broadcast(ProtocolMessage::Round8(phase5d_decom2));
+        Ok(())
+    }
+
+    fn proceed_round8(&mut self) -> Result<(), Error> {
+        let phase5d_decom2 = self.phase5d_decom2.take().ok_or(Error::InvalidState)?;
+        let decommit5d_vec = self
+            .inbox
+            .collect(self.round, self.i, Some(phase5d_decom2), |m| match m {
+                ProtocolMessage::Round8(decom) => Some(decom),
+                _ => None,
+            })?;
+        let local_sig = self.local_sig.as_ref().ok_or(Error::InvalidState)?;
+        let s_i = local_sig
+            .phase5d(&decommit5d_vec, &self.commit5c_vec, &self.decommit5a_vec)
+            .map_err(|err| blame(err, &self.signers))?;
+        self.broadcast(ProtocolMessage::Round9(s_i));
+        Ok(())
+    }
+
+    fn proceed_round9(&mut self) -> Result<(), Error> {
+        let s_vec = self.inbox.collect(self.round, self.i, None, |m| match m {
+            ProtocolMessage::Round9(s_j) => Some(s_j),
+            _ => None,
+        })?;
+        let local_sig = self.local_sig.take().ok_or(Error::InvalidState)?;
+        let sig = local_sig
+            .output_signature(&s_vec)
+            .map_err(|err| blame(err, &self.others()))?;
+        self.output = Some(sig);
+        Ok(())
+    }
+}
+
+impl Drop for Sign {
+    fn drop(&mut self) {
+        self.beta_vec.zeroize();
+        self.ni_vec.zeroize();
+        if let Some(sigma_i) = self.sigma_i.as_mut() {
+            sigma_i.zeroize();
+        }
+    }
+}
@@ -0,0 +1,295 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE, GE};
+
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{verify, Keys, SignatureRecid};
+use crate::protocols::multi_party_ecdsa::gg_2018::state_machine::keygen::{
+    Keygen, LocalKey, ProtocolMessage,
+};
+use crate::protocols::multi_party_ecdsa::gg_2018::state_machine::sign::{self, Sign};
+use crate::protocols::multi_party_ecdsa::gg_2018::state_machine::{Error, Msg};
+use crate::ErrorKind;
+
+// delivers every outgoing message until no party has anything left to send
+fn deliver_keygen_messages(parties: &mut Vec<Keygen>) {
+    loop {
+        let mut queue = Vec::new();
+        for party in parties.iter_mut() {
+            queue.extend(party.message_queue().drain(..));
+        }
+        if queue.is_empty() {
+            break;
+        }
+        for msg in queue {
+            for party in parties.iter_mut() {
+                let ind = party.party_ind();
+                let is_receiver = match msg.receiver {
+                    None => msg.sender != ind,
+                    Some(receiver) => receiver == ind,
+                };
+                if is_receiver {
+                    party.handle_incoming(msg.clone()).expect("keygen failed");
+                }
+            }
+        }
+    }
+}
+
+// same as above, `tamper` lets a test corrupt messages on the way
+fn deliver_sign_messages(
+    parties: &mut Vec<Sign>,
+    tamper: impl Fn(&mut Msg<sign::ProtocolMessage>),
+) -> Result<(), Error> {
+    loop {
+        let mut queue = Vec::new();
+        for party in parties.iter_mut() {
+            queue.extend(party.message_queue().drain(..));
+        }
+        if queue.is_empty() {
+            return Ok(());
+        }
+        for mut msg in queue {
+            tamper(&mut msg);
+            for party in parties.iter_mut() {
+                let ind = party.party_ind();
+                let is_receiver = match msg.receiver {
+                    None => msg.sender != ind,
+                    Some(receiver) => receiver == ind,
+                };
+                if is_receiver {
+                    party.handle_incoming(msg.clone())?;
+                }
+            }
+        }
+    }
+}
+
+pub fn keygen_t_n_parties_sm(t: u16, n: u16) -> Vec<LocalKey> {
+    let mut parties = (1..=n)
+        .map(|i| Keygen::with_keys(i, t, n, Keys::create(i as usize)).unwrap())
+        .collect::<Vec<Keygen>>();
+    deliver_keygen_messages(&mut parties);
+    parties
+        .iter_mut()
+        .map(|party| {
+            assert!(party.is_finished());
+            party.pick_output().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_keygen_sm_t1_n2() {
+    keygen_t_n_parties_sm(1, 2);
+}
+
+#[test]
+fn test_keygen_sm_t2_n3() {
+    let local_keys = keygen_t_n_parties_sm(2, 3);
+    let y = local_keys[0].y_sum;
+    assert!(local_keys.iter().all(|key| key.y_sum == y));
+
+    let indices = (0..3).collect::<Vec<usize>>();
+    let xi_vec = local_keys
+        .iter()
+        .map(|key| key.shared_keys.x_i)
+        .collect::<Vec<FE>>();
+    let x = local_keys[0].vss_scheme_vec[0].reconstruct(&indices, &xi_vec);
+    let g: GE = ECPoint::generator();
+    assert_eq!(g * x, y);
+}
+
+#[test]
+fn test_keygen_sm_rejects_bad_messages() {
+    let mut party = Keygen::with_keys(1, 1, 3, Keys::create(1)).unwrap();
+    let round1 = party.message_queue().pop().unwrap();
+
+    assert_eq!(
+        party.handle_incoming(round1.clone()),
+        Err(Error::InvalidSender {
+            sender: 1,
+            round: 1
+        })
+    );
+
+    let mut from_two = round1.clone();
+    from_two.sender = 2;
+    party.handle_incoming(from_two.clone()).unwrap();
+    assert_eq!(
+        party.handle_incoming(from_two.clone()),
+        Err(Error::DuplicateMessage {
+            sender: 2,
+            round: 1
+        })
+    );
+
+    let mut wrong_round = from_two.clone();
+    wrong_round.sender = 3;
+    wrong_round.round = 2;
+    assert_eq!(
+        party.handle_incoming(wrong_round),
+        Err(Error::MismatchedRound {
+            sender: 3,
+            round: 2
+        })
+    );
+
+    let p2p = Msg {
+        sender: 3,
+        receiver: None,
+        round: 3,
+        body: ProtocolMessage::Round3(ECScalar::new_random()),
+    };
+    assert_eq!(
+        party.handle_incoming(p2p),
+        Err(Error::InvalidReceiver {
+            sender: 3,
+            round: 3
+        })
+    );
+}
+
+// signers are 1-based keygen indices
+fn start_sign(local_keys: &[LocalKey], signers: &[u16], message: &BigInt) -> Vec<Sign> {
+    signers
+        .iter()
+        .map(|&i| {
+            let local_key = local_keys[(i - 1) as usize].clone();
+            Sign::new(message.clone(), signers.to_vec(), local_key).unwrap()
+        })
+        .collect()
+}
+
+fn sign_sm(local_keys: &[LocalKey], signers: &[u16], message: &BigInt) -> Vec<SignatureRecid> {
+    let mut parties = start_sign(local_keys, signers, message);
+    deliver_sign_messages(&mut parties, |_| ()).expect("sign failed");
+    parties
+        .iter_mut()
+        .map(|party| {
+            assert!(party.is_finished());
+            party.pick_output().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_sign_sm_t1_n2() {
+    let local_keys = keygen_t_n_parties_sm(1, 2);
+    let message = HSha256::create_hash(&[&BigInt::from(1234)]);
+    let sigs = sign_sm(&local_keys, &[1, 2], &message);
+    assert!(verify(&sigs[0], &local_keys[0].y_sum, &message).is_ok());
+    assert_eq!(
+        sigs[0].recover_public_key(&message).unwrap(),
+        local_keys[0].y_sum
+    );
+}
+
+#[test]
+fn test_sign_sm_t2_n4_subset() {
+    let local_keys = keygen_t_n_parties_sm(2, 4);
+    let message = HSha256::create_hash(&[&BigInt::from(5678)]);
+    let sigs = sign_sm(&local_keys, &[1, 3, 4], &message);
+    for sig in &sigs {
+        assert_eq!(sig.r, sigs[0].r);
+        assert_eq!(sig.s, sigs[0].s);
+        assert!(verify(sig, &local_keys[0].y_sum, &message).is_ok());
+        assert_eq!(
+            sig.recover_public_key(&message).unwrap(),
+            local_keys[0].y_sum
+        );
+    }
+}
+
+#[test]
+fn test_sign_sm_with_bip32_child_key() {
+    let local_keys = keygen_t_n_parties_sm(1, 3);
+    let chain_code = [7u8; 32];
+    let path = [0, 1, 42];
+    let (child_keys, child_vec): (Vec<_>, Vec<_>) = local_keys
+        .iter()
+        .map(|local_key| local_key.derive_child(&chain_code, &path).unwrap())
+        .unzip();
+    let child_y = child_vec[0].public_key;
+    assert_ne!(child_y, local_keys[0].y_sum);
+    for (local_key, child_key) in local_keys.iter().zip(&child_keys) {
+        assert_eq!(child_key.y_sum, child_y);
+        assert_eq!(
+            Keys::get_commitments_to_xi(&child_key.vss_scheme_vec)[(local_key.i - 1) as usize],
+            GE::generator() * child_key.shared_keys.x_i
+        );
+    }
+
+    let message = HSha256::create_hash(&[&BigInt::from(4321)]);
+    let sigs = sign_sm(&child_keys, &[1, 3], &message);
+    assert!(verify(&sigs[0], &child_y, &message).is_ok());
+    assert_eq!(sigs[0].recover_public_key(&message).unwrap(), child_y);
+}
+
+#[test]
+fn test_sign_sm_rejects_bad_signers() {
+    let local_keys = keygen_t_n_parties_sm(1, 3);
+    let message = BigInt::from(1);
+    let too_few = Sign::new(message.clone(), vec![1], local_keys[0].clone());
+    assert_eq!(too_few.err(), Some(Error::InvalidParameters));
+    let without_self = Sign::new(message.clone(), vec![2, 3], local_keys[0].clone());
+    assert_eq!(without_self.err(), Some(Error::InvalidParameters));
+
+    let mut party = Sign::new(message, vec![1, 2], local_keys[0].clone()).unwrap();
+    let mut from_three = party.message_queue().pop().unwrap();
+    from_three.sender = 3;
+    assert_eq!(
+        party.handle_incoming(from_three),
+        Err(Error::InvalidSender {
+            sender: 3,
+            round: 1
+        })
+    );
+}
+
+#[test]
+fn test_sign_sm_identifies_bad_decommit() {
+    let local_keys = keygen_t_n_parties_sm(1, 3);
+    let message = HSha256::create_hash(&[&BigInt::from(42)]);
+    let mut parties = start_sign(&local_keys, &[1, 2, 3], &message);
+    let result = deliver_sign_messages(&mut parties, |msg| {
+        if let sign::ProtocolMessage::Round4(decommit) = &mut msg.body {
+            if msg.sender == 3 {
+                decommit.blind_factor = BigInt::from(1);
+            }
+        }
+    });
+    match &result {
+        Err(Error::Protocol(err)) => {
+            assert_eq!(err.round, "phase4");
+            assert_eq!(err.kind, ErrorKind::InvalidCommitment);
+            assert_eq!(err.parties, vec![3]);
+        }
+        _ => panic!("bad decommit was not detected"),
+    }
+
+    // party 1 received the bad decommit first and stays failed
+    let later = Msg {
+        sender: 2,
+        receiver: None,
+        round: 3,
+        body: sign::ProtocolMessage::Round3(FE::new_random()),
+    };
+    assert_eq!(parties[0].handle_incoming(later), result);
+}
@@ -17,10 +17,17 @@
 */
 
 use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
-    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys
+    import_secret_key, reconstruct_private_key, verify, KeyGenBroadcastMessage1,
+    KeyGenDecommitMessage1, Keys, LocalSignature, Parameters, PartyPrivate, Phase5ADecom1,
+    PreSignature, SharedKeys, SignKeys,
 };
 
-use crate::protocols::multi_party_ecdsa::gg_2018::mock::{keygen_t_n_parties, sign};
+use crate::protocols::multi_party_ecdsa::gg_2018::mock::{
+    convert_lindell_t_n_parties, keygen_t_n_parties, phase5_output_signature, presign,
+    presign_with_keys, recover_share, refresh_t_n_parties, reshare_t_n_parties, sign,
+    sign_with_presignatures,
+};
+use crate::ErrorKind;
 
 #[test]
 fn test_keygen_t1_n2() {
@@ -52,6 +59,42 @@ fn test_sign_n20_t16_ttag17() {
     sign(16, 20, 17, vec![0, 2, 3, 4, 5, 6, 7, 8, 9, 11, 12, 13, 14, 15, 16, 18, 19])
 }
 
+#[test]
+fn test_sign_with_presignature() {
+    use curv::BigInt;
+    use std::collections::HashSet;
+
+    let (presignature_vec, y) = presign(2, 5, 3, &[0, 2, 4]);
+    let R = presignature_vec[0].R();
+    // presignatures are stored until the message is known
+    let stored_vec = presignature_vec
+        .iter()
+        .map(|presignature| serde_json::to_string(presignature).unwrap())
+        .collect::<Vec<String>>();
+    // every signer keeps the ids of its presignatures that were used
+    let mut used_ids_vec = vec![HashSet::new(); stored_vec.len()];
+
+    let message = BigInt::from(1234);
+    let local_sig_vec = stored_vec
+        .iter()
+        .zip(used_ids_vec.iter_mut())
+        .map(|(stored, used_ids)| {
+            let presignature = serde_json::from_str::<PreSignature>(stored).unwrap();
+            presignature.sign(&message, used_ids).unwrap()
+        })
+        .collect::<Vec<LocalSignature>>();
+    let sig = phase5_output_signature(&local_sig_vec, &R);
+    verify(&sig, &y, &message).unwrap();
+    assert_eq!(sig.recover_public_key(&message).unwrap(), y);
+
+    // a second copy of a stored presignature cannot sign another message
+    let copy = serde_json::from_str::<PreSignature>(&stored_vec[0]).unwrap();
+    let err = copy
+        .sign(&BigInt::from(5678), &mut used_ids_vec[0])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ReusedPresignature);
+}
+
 #[test]
 fn test_serialize_deserialize() {
     use serde_json;
@@ -67,3 +110,762 @@ fn test_serialize_deserialize() {
     let decoded: KeyGenDecommitMessage1 = serde_json::from_str(&encoded).unwrap();
     assert_eq!(decommit.y_i, decoded.y_i);
 }
+
+#[test]
+fn test_phase5_check_s_i_finds_bad_actor() {
+    use curv::arithmetic::traits::Samplable;
+    use curv::elliptic::curves::traits::*;
+    use curv::{BigInt, FE, GE};
+
+    let g: GE = ECPoint::generator();
+    let R = g * FE::new_random();
+    let s_vec = (0..3).map(|_| FE::new_random()).collect::<Vec<FE>>();
+    let l_vec = (0..3).map(|_| FE::new_random()).collect::<Vec<FE>>();
+    let decom_vec = (0..3)
+        .map(|i| Phase5ADecom1 {
+            V_i: R * s_vec[i] + g * l_vec[i],
+            A_i: g,
+            B_i: g,
+            blind_factor: BigInt::sample(256),
+        })
+        .collect::<Vec<Phase5ADecom1>>();
+    assert!(LocalSignature::phase5_check_s_i(&R, &s_vec, &l_vec, &decom_vec).is_ok());
+
+    let mut bad_s_vec = s_vec.clone();
+    bad_s_vec[1] = FE::new_random();
+    let err = LocalSignature::phase5_check_s_i(&R, &bad_s_vec, &l_vec, &decom_vec).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSignature);
+    assert_eq!(err.parties, vec![1]);
+    assert_eq!(err.to_string(), "gg18 signing phase5e: invalid signature (parties [1])");
+}
+
+#[test]
+fn test_malformed_input_returns_error() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let delta: FE = FE::new_random();
+    let minus_delta: FE = ECScalar::from(&(FE::q() - delta.to_big_int()));
+    let err = SignKeys::phase3_reconstruct_delta(&[delta, minus_delta]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ZeroSum);
+
+    let s_vec = vec![FE::new_random(); 2];
+    let l_vec = vec![FE::new_random(); 3];
+    let err = LocalSignature::phase5_check_s_i(
+        &(GE::generator() * delta),
+        &s_vec,
+        &l_vec,
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LengthMismatch);
+}
+
+#[test]
+fn test_keygen_rejects_bad_dlog_statement() {
+    let params = Parameters {
+        threshold: 1,
+        share_count: 2,
+    };
+    let party_keys_vec = (0..2).map(Keys::create).collect::<Vec<Keys>>();
+    let (mut bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key())
+        .unzip();
+    assert!(party_keys_vec[0]
+        .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
+            &params, &decom_vec, &bc1_vec,
+        )
+        .is_ok());
+
+    // h2 not generated by h1
+    bc1_vec[1].dlog_statement.ni = party_keys_vec[0].h2.clone();
+    let err = party_keys_vec[0]
+        .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
+            &params, &decom_vec, &bc1_vec,
+        )
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidCompositeDLogProof);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_keygen_rejects_bad_paillier_modulus_proofs() {
+    let params = Parameters {
+        threshold: 1,
+        share_count: 2,
+    };
+    let party_keys_vec = (0..2).map(Keys::create).collect::<Vec<Keys>>();
+    let (mut bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key())
+        .unzip();
+    let mut proofs_vec = party_keys_vec
+        .iter()
+        .map(|k| k.phase3_prove_no_small_factor(&bc1_vec).unwrap())
+        .collect::<Vec<_>>();
+    assert!(party_keys_vec[0]
+        .phase3_verify_no_small_factor(&params, &bc1_vec, &proofs_vec, 1)
+        .is_ok());
+
+    // no small factor proof made for the wrong verifier
+    proofs_vec[1].swap(0, 1);
+    let err = party_keys_vec[0]
+        .phase3_verify_no_small_factor(&params, &bc1_vec, &proofs_vec, 1)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidNoSmallFactorProof);
+    assert_eq!(err.parties, vec![1]);
+
+    // Paillier-Blum proof of another modulus
+    bc1_vec[1].paillier_blum_modulus_proof = bc1_vec[0].paillier_blum_modulus_proof.clone();
+    let err = party_keys_vec[0]
+        .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
+            &params, &decom_vec, &bc1_vec,
+        )
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidPaillierBlumModulusProof);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_refresh_keeps_y_and_invalidates_old_shares() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let (t, n) = (2, 4);
+    let (party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(t, n);
+    let (new_keys_vec, new_shared_keys_vec, new_vss_scheme_vec) = refresh_t_n_parties(
+        t,
+        n,
+        &party_keys_vec,
+        &shared_keys_vec,
+        &vss_scheme_vec,
+        true,
+    );
+
+    let indices = [0, 1, 2];
+    let x_i_vec = (0..3)
+        .map(|i| new_shared_keys_vec[i].x_i)
+        .collect::<Vec<FE>>();
+    let x = vss_scheme_vec[0].reconstruct(&indices, &x_i_vec);
+    assert_eq!(GE::generator() * x, y);
+    assert!(new_shared_keys_vec.iter().all(|k| k.y == y));
+    assert_ne!(new_shared_keys_vec[0].x_i, shared_keys_vec[0].x_i);
+
+    // an old share does not combine with the new ones
+    let mixed_vec = [shared_keys_vec[0].x_i, x_i_vec[1], x_i_vec[2]];
+    assert_ne!(vss_scheme_vec[0].reconstruct(&indices, &mixed_vec), x);
+
+    let commitments = Keys::get_commitments_to_xi(&new_vss_scheme_vec);
+    for (commitment, k) in commitments.iter().zip(&new_shared_keys_vec) {
+        assert_eq!(*commitment, GE::generator() * k.x_i);
+    }
+    for (old, new) in party_keys_vec.iter().zip(&new_keys_vec) {
+        assert_ne!(old.ek.n, new.ek.n);
+    }
+}
+
+#[test]
+fn test_refresh_rejects_bad_sub_share() {
+    use curv::elliptic::curves::traits::*;
+    use curv::FE;
+
+    let params = Parameters {
+        threshold: 1,
+        share_count: 3,
+    };
+    let (party_keys_vec, shared_keys_vec, _pk_vec, _y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let ek_vec = party_keys_vec
+        .iter()
+        .map(|k| k.ek.clone())
+        .collect::<Vec<_>>();
+    let (bc_vec, mut secret_shares_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| {
+            let (bc, secret_shares, _) = k.refresh_phase1_distribute(&params, None).unwrap();
+            (bc, secret_shares[0])
+        })
+        .unzip();
+    secret_shares_vec[2] = secret_shares_vec[2] + FE::new_random();
+
+    let err = party_keys_vec[0]
+        .refresh_phase2_verify_update(
+            &params,
+            &bc_vec,
+            &secret_shares_vec,
+            &shared_keys_vec[0],
+            &vss_scheme_vec,
+            &ek_vec,
+            1,
+        )
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+    assert_eq!(err.parties, vec![2]);
+}
+
+#[test]
+fn test_refresh_rejects_invalid_threshold() {
+    let party_keys = Keys::create(0);
+    for &(threshold, share_count) in &[(0, 3), (3, 3)] {
+        let params = Parameters {
+            threshold,
+            share_count,
+        };
+        let err = party_keys
+            .refresh_phase1_distribute(&params, None)
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidThreshold);
+    }
+}
+
+#[test]
+fn test_reshare_t1_n3_to_t2_n5() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let (_party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let (_new_keys_vec, new_shared_keys_vec, new_pk_vec, new_y, new_vss_scheme_vec) =
+        reshare_t_n_parties(&shared_keys_vec, &vss_scheme_vec, &[0, 2], 2, 5);
+    assert_eq!(new_y, y);
+    assert_eq!(new_shared_keys_vec.len(), 5);
+    assert_eq!(new_vss_scheme_vec.len(), 5);
+    assert!(new_shared_keys_vec.iter().all(|k| k.y == y));
+    for (pk, k) in new_pk_vec.iter().zip(&new_shared_keys_vec) {
+        assert_eq!(*pk, GE::generator() * k.x_i);
+    }
+
+    let indices = [1, 3, 4];
+    let x_i_vec = indices
+        .iter()
+        .map(|&i| new_shared_keys_vec[i].x_i)
+        .collect::<Vec<FE>>();
+    let x = new_vss_scheme_vec[0].reconstruct(&indices, &x_i_vec);
+    assert_eq!(GE::generator() * x, y);
+    // two new shares are below the new threshold
+    assert_ne!(
+        vss_scheme_vec[0].reconstruct(&indices[..2], &x_i_vec[..2]),
+        x
+    );
+    let shares = indices
+        .iter()
+        .map(|&i| new_shared_keys_vec[i].clone())
+        .collect::<Vec<SharedKeys>>();
+    reconstruct_private_key(&y, &new_vss_scheme_vec, &indices, &shares).unwrap();
+}
+
+#[test]
+fn test_reshare_then_refresh_recover_and_sign() {
+    use crate::protocols::multi_party_ecdsa::gg_2018::key_share::LocalKeyShare;
+
+    let (_party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let (new_keys_vec, new_shared_keys_vec, _new_pk_vec, _y, new_vss_scheme_vec) =
+        reshare_t_n_parties(&shared_keys_vec, &vss_scheme_vec, &[1, 2], 2, 4);
+    let (party_keys_vec, shared_keys_vec, vss_scheme_vec) = refresh_t_n_parties(
+        2,
+        4,
+        &new_keys_vec,
+        &new_shared_keys_vec,
+        &new_vss_scheme_vec,
+        true,
+    );
+
+    let recovered = recover_share(&shared_keys_vec, &vss_scheme_vec, &[0, 1, 3], 2);
+    assert_eq!(recovered.x_i, shared_keys_vec[2].x_i);
+
+    let key_share = LocalKeyShare::new(
+        party_keys_vec[3].clone(),
+        shared_keys_vec[3].clone(),
+        4,
+        vss_scheme_vec.clone(),
+        party_keys_vec.iter().map(|k| k.ek.clone()).collect(),
+        party_keys_vec.iter().map(|k| k.dlog_statement()).collect(),
+        y,
+    )
+    .unwrap();
+    LocalKeyShare::from_json(&key_share.to_json()).unwrap();
+
+    let presignature_vec = presign_with_keys(
+        &party_keys_vec,
+        &shared_keys_vec,
+        &vss_scheme_vec[0],
+        &[0, 2, 3],
+    );
+    sign_with_presignatures(presignature_vec, &y);
+}
+
+#[test]
+fn test_reshare_rejects_share_inconsistent_with_y() {
+    use curv::elliptic::curves::traits::*;
+    use curv::FE;
+
+    let (_party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let new_params = Parameters {
+        threshold: 1,
+        share_count: 2,
+    };
+    let s = [0, 1];
+    // the holder at position 1 deals a share that is not its own
+    let forged = SharedKeys {
+        y,
+        x_i: shared_keys_vec[1].x_i + FE::new_random(),
+    };
+    let (bc_vec, secret_shares_vec): (Vec<_>, Vec<_>) = [&shared_keys_vec[0], &forged]
+        .iter()
+        .zip(&s)
+        .map(|(shared_keys, &i)| {
+            let (bc, secret_shares) = shared_keys
+                .reshare_distribute(&vss_scheme_vec[0], i, &s, &new_params)
+                .unwrap();
+            (bc, secret_shares[0])
+        })
+        .unzip();
+
+    let err = SharedKeys::reshare_verify_construct(
+        &y,
+        &vss_scheme_vec,
+        &s,
+        &bc_vec,
+        &secret_shares_vec,
+        &new_params,
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_recover_share() {
+    let (_party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(2, 4);
+    let recovered = recover_share(&shared_keys_vec, &vss_scheme_vec, &[0, 1, 3], 2);
+    assert_eq!(recovered.x_i, shared_keys_vec[2].x_i);
+    assert_eq!(recovered.y, y);
+}
+
+#[test]
+fn test_recovery_rejects_bad_piece() {
+    use curv::elliptic::curves::traits::*;
+    use curv::FE;
+
+    let (_party_keys_vec, shared_keys_vec, _pk_vec, _y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let s = [0, 2];
+    let (bc_vec, mut pieces_vec): (Vec<_>, Vec<_>) = s
+        .iter()
+        .map(|&i| {
+            let (bc, pieces) = shared_keys_vec[i]
+                .recovery_phase1_split(&vss_scheme_vec[0], i, &s, 1)
+                .unwrap();
+            (bc, pieces[0])
+        })
+        .unzip();
+    pieces_vec[1] = pieces_vec[1] + FE::new_random();
+
+    let err = SharedKeys::recovery_phase2_combine(&vss_scheme_vec, &s, 1, &bc_vec, &pieces_vec, 0)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_recovery_rejects_bad_helper_set() {
+    let (_party_keys_vec, shared_keys_vec, _pk_vec, _y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let split = |s: &[usize], recover_index: usize| {
+        shared_keys_vec[0]
+            .recovery_phase1_split(&vss_scheme_vec[0], 0, s, recover_index)
+            .unwrap_err()
+            .kind
+    };
+    assert_eq!(split(&[0, 0], 1), ErrorKind::LengthMismatch);
+    assert_eq!(split(&[0, 3], 1), ErrorKind::LengthMismatch);
+    assert_eq!(split(&[1, 2], 0), ErrorKind::LengthMismatch);
+    assert_eq!(split(&[0, 2], 3), ErrorKind::LengthMismatch);
+    assert_eq!(split(&[0, 1, 2], 1), ErrorKind::InvalidThreshold);
+
+    let (bc_vec, _pieces_vec): (Vec<_>, Vec<_>) = [0, 2]
+        .iter()
+        .map(|&i| {
+            shared_keys_vec[i]
+                .recovery_phase1_split(&vss_scheme_vec[0], i, &[0, 2], 1)
+                .unwrap()
+        })
+        .unzip();
+    let err = SharedKeys::recovery_phase3_construct(
+        &shared_keys_vec[0].y,
+        &vss_scheme_vec,
+        &[2, 2],
+        1,
+        &bc_vec,
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LengthMismatch);
+}
+
+#[test]
+fn test_import_secret_key() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let params = Parameters {
+        threshold: 1,
+        share_count: 3,
+    };
+    let secret = FE::new_random();
+    let (party_keys_vec, mut shared_keys_vec, _pk_vec, y, vss_scheme_vec, transcript) =
+        import_secret_key(&secret, &params).unwrap();
+    assert_eq!(y, GE::generator() * secret);
+    for i in 0..3 {
+        transcript
+            .verify(&params, &party_keys_vec[i], &shared_keys_vec[i], i + 1)
+            .unwrap();
+    }
+
+    // the dealt keys sign like keygen output
+    let s = [0, 2];
+    let w_sum = s
+        .iter()
+        .map(|&i| {
+            let private =
+                PartyPrivate::set_private(party_keys_vec[i].clone(), shared_keys_vec[i].clone());
+            SignKeys::create(&private, &vss_scheme_vec[0], i, &s).w_i
+        })
+        .fold(FE::zero(), |acc, w_i| acc + w_i);
+    assert_eq!(w_sum, secret);
+
+    shared_keys_vec[1].x_i = shared_keys_vec[1].x_i + FE::new_random();
+    let err = transcript
+        .verify(&params, &party_keys_vec[1], &shared_keys_vec[1], 2)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_import_secret_key_rejects_invalid_threshold() {
+    use curv::elliptic::curves::traits::*;
+    use curv::FE;
+
+    let secret = FE::new_random();
+    for &(threshold, share_count) in &[(3, 3), (1, 0), (0, 1), (0, 0)] {
+        let params = Parameters {
+            threshold,
+            share_count,
+        };
+        let err = import_secret_key(&secret, &params).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidThreshold);
+    }
+
+    // t = 0 is a valid keygen threshold
+    let params = Parameters {
+        threshold: 0,
+        share_count: 2,
+    };
+    let (party_keys_vec, shared_keys_vec, _pk_vec, _y, _vss_scheme_vec, transcript) =
+        import_secret_key(&secret, &params).unwrap();
+    transcript
+        .verify(&params, &party_keys_vec[1], &shared_keys_vec[1], 2)
+        .unwrap();
+    assert_eq!(shared_keys_vec[0].x_i, secret);
+}
+
+#[test]
+fn test_reconstruct_private_key() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let (_party_keys_vec, mut shared_keys_vec, _pk_vec, y, vss_scheme_vec) =
+        keygen_t_n_parties(2, 4);
+    let x = reconstruct_private_key(
+        &y,
+        &vss_scheme_vec,
+        &[0, 2, 3],
+        &[
+            shared_keys_vec[0].clone(),
+            shared_keys_vec[2].clone(),
+            shared_keys_vec[3].clone(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(GE::generator() * *x, y);
+
+    let err =
+        reconstruct_private_key(&y, &vss_scheme_vec, &[0, 1], &shared_keys_vec[0..2]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::TooFewShares);
+    let err = reconstruct_private_key(&y, &vss_scheme_vec, &[0, 1, 1], &shared_keys_vec[0..3])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::TooFewShares);
+
+    shared_keys_vec[1].x_i = shared_keys_vec[1].x_i + FE::new_random();
+    let err = reconstruct_private_key(&y, &vss_scheme_vec, &[0, 1, 2], &shared_keys_vec[0..3])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_convert_lindell_key_and_sign() {
+    use curv::elliptic::curves::traits::*;
+    use curv::GE;
+
+    let (party_keys_vec, shared_keys_vec, pk_vec, y, vss_scheme_vec) =
+        convert_lindell_t_n_parties(1, 3);
+    assert!(shared_keys_vec.iter().all(|k| k.y == y));
+    assert_eq!(vss_scheme_vec.len(), 3);
+    for (pk, k) in pk_vec.iter().zip(&shared_keys_vec) {
+        assert_eq!(*pk, GE::generator() * k.x_i);
+    }
+    let presignature_vec = presign_with_keys(
+        &party_keys_vec,
+        &shared_keys_vec,
+        &vss_scheme_vec[0],
+        &[0, 2],
+    );
+    sign_with_presignatures(presignature_vec, &y);
+
+    // the converted key refreshes like keygen output
+    let (party_keys_vec, shared_keys_vec, vss_scheme_vec) = refresh_t_n_parties(
+        1,
+        3,
+        &party_keys_vec,
+        &shared_keys_vec,
+        &vss_scheme_vec,
+        false,
+    );
+    let presignature_vec = presign_with_keys(
+        &party_keys_vec,
+        &shared_keys_vec,
+        &vss_scheme_vec[0],
+        &[1, 2],
+    );
+    sign_with_presignatures(presignature_vec, &y);
+}
+
+#[test]
+fn test_new_party_keys_are_checked() {
+    let params = Parameters {
+        threshold: 1,
+        share_count: 2,
+    };
+    let party_keys_vec = (0..2).map(Keys::create).collect::<Vec<Keys>>();
+    let (mut bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key())
+        .unzip();
+    let proofs_vec = party_keys_vec
+        .iter()
+        .map(|k| k.phase3_prove_no_small_factor(&bc1_vec).unwrap())
+        .collect::<Vec<_>>();
+    let (ek_vec, statement_vec) = party_keys_vec[0]
+        .verify_new_party_keys(&params, &decom_vec, &bc1_vec, &proofs_vec, 1)
+        .unwrap();
+    assert_eq!(ek_vec[1].n, party_keys_vec[1].ek.n);
+    assert_eq!(statement_vec[1].N, party_keys_vec[1].N_tilde);
+
+    bc1_vec[1].dlog_statement.ni = party_keys_vec[0].h2.clone();
+    let err = party_keys_vec[0]
+        .verify_new_party_keys(&params, &decom_vec, &bc1_vec, &proofs_vec, 1)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidCompositeDLogProof);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_sign_with_bip32_child_key() {
+    let (party_keys_vec, shared_keys_vec, _pk_vec, _y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let chain_code = [7u8; 32];
+    let path = [0, 1, 42];
+    let (child_shared_keys_vec, child_vec): (Vec<_>, Vec<_>) = shared_keys_vec
+        .iter()
+        .map(|k| k.derive_child(&chain_code, &path).unwrap())
+        .unzip();
+    let child_y = child_vec[0].public_key;
+    assert!(child_vec.iter().all(|child| child.public_key == child_y));
+
+    let presignature_vec = presign_with_keys(
+        &party_keys_vec,
+        &child_shared_keys_vec,
+        &vss_scheme_vec[0],
+        &[1, 2],
+    );
+    sign_with_presignatures(presignature_vec, &child_y);
+}
+
+#[test]
+fn test_sign_with_restored_backup() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let (party_keys_vec, shared_keys_vec, pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let backup_private_key: FE = ECScalar::new_random();
+    let backup_public_key = GE::generator() * backup_private_key;
+
+    let restored_vec = shared_keys_vec
+        .iter()
+        .zip(pk_vec.iter())
+        .map(|(shared_keys, y_i)| {
+            let backup = shared_keys.backup(&backup_public_key);
+            backup.verify(y_i, &backup_public_key).unwrap();
+            SharedKeys::restore(&backup, &y, y_i, &backup_private_key).unwrap()
+        })
+        .collect::<Vec<SharedKeys>>();
+    let err = shared_keys_vec[0]
+        .backup(&backup_public_key)
+        .verify(&pk_vec[1], &backup_public_key)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidBackupProof);
+
+    let presignature_vec =
+        presign_with_keys(&party_keys_vec, &restored_vec, &vss_scheme_vec[0], &[0, 2]);
+    sign_with_presignatures(presignature_vec, &y);
+}
+
+#[test]
+fn test_local_key_share_encodings() {
+    use crate::protocols::multi_party_ecdsa::gg_2018::key_share::{
+        LocalKeyShare, KEY_SHARE_VERSION,
+    };
+    use curv::elliptic::curves::traits::*;
+    use curv::{FE, GE};
+
+    let (party_keys_vec, shared_keys_vec, _pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(1, 3);
+    let paillier_key_vec = party_keys_vec
+        .iter()
+        .map(|k| k.ek.clone())
+        .collect::<Vec<_>>();
+    let dlog_statement_vec = party_keys_vec
+        .iter()
+        .map(|k| k.dlog_statement())
+        .collect::<Vec<_>>();
+    let key_share = LocalKeyShare::new(
+        party_keys_vec[1].clone(),
+        shared_keys_vec[1].clone(),
+        2,
+        vss_scheme_vec,
+        paillier_key_vec,
+        dlog_statement_vec,
+        y,
+    )
+    .unwrap();
+    assert_eq!((key_share.threshold, key_share.share_count), (1, 3));
+
+    let decoded = LocalKeyShare::from_json(&key_share.to_json()).unwrap();
+    assert_eq!(decoded.shared_keys.x_i, key_share.shared_keys.x_i);
+    let decoded = LocalKeyShare::from_bytes(&key_share.to_bytes()).unwrap();
+    assert_eq!(decoded.y, y);
+
+    let err = LocalKeyShare::new(
+        party_keys_vec[0].clone(),
+        shared_keys_vec[0].clone(),
+        1,
+        Vec::new(),
+        key_share.paillier_key_vec.clone(),
+        key_share.h1_h2_N_tilde_vec.clone(),
+        y,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LengthMismatch);
+
+    // the positional tuple written by the keygen example before the format was versioned,
+    // with Keys that predate the ring-Pedersen parameters
+    let g = r#"{"x":"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798","y":"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"}"#;
+    let ek = r#"{"n":"143","nn":"19789"}"#;
+    let vss = format!(
+        r#"{{"parameters":{{"threshold":1,"share_count":2}},"commitments":[{},{}]}}"#,
+        g, g
+    );
+    let legacy = format!(
+        r#"[{{"u_i":"1","y_i":{g},"dk":{{"p":"11","q":"13"}},"ek":{ek},"party_index":1}},{{"y":{g},"x_i":"2"}},2,[{vss},{vss}],[{ek},{ek}],{g}]"#,
+        g = g,
+        ek = ek,
+        vss = vss
+    );
+    let decoded = LocalKeyShare::from_json(&legacy).unwrap();
+    assert_eq!((decoded.threshold, decoded.share_count), (1, 2));
+    assert_eq!(decoded.party_index, 2);
+    assert_eq!(decoded.party_keys.y_i, GE::generator());
+    assert!(decoded.needs_upgrade());
+
+    // the parties of the version 0 share exchange new keys to upgrade it
+    let params = Parameters {
+        threshold: 1,
+        share_count: 2,
+    };
+    let new_keys_vec = vec![
+        Keys::create_from(FE::new_random(), 0),
+        Keys::create_from(decoded.party_keys.u_i, 1),
+    ];
+    let (bc1_vec, decom_vec): (Vec<_>, Vec<_>) = new_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key())
+        .unzip();
+    let proofs_vec = new_keys_vec
+        .iter()
+        .map(|k| k.phase3_prove_no_small_factor(&bc1_vec).unwrap())
+        .collect::<Vec<_>>();
+    let (ek_vec, statement_vec) = new_keys_vec[1]
+        .verify_new_party_keys(&params, &decom_vec, &bc1_vec, &proofs_vec, 2)
+        .unwrap();
+    let err = decoded
+        .upgrade(
+            new_keys_vec[0].clone(),
+            ek_vec.clone(),
+            statement_vec.clone(),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+    let upgraded = decoded
+        .upgrade(new_keys_vec[1].clone(), ek_vec, statement_vec)
+        .unwrap();
+    assert!(!upgraded.needs_upgrade());
+    let upgraded = LocalKeyShare::from_json(&upgraded.to_json()).unwrap();
+    assert_eq!(upgraded.version, KEY_SHARE_VERSION);
+    assert_eq!(upgraded.shared_keys.x_i, decoded.shared_keys.x_i);
+
+    let mut future = key_share.clone();
+    future.version += 1;
+    let err = LocalKeyShare::from_json(&future.to_json()).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnsupportedVersion);
+    let mut bytes = key_share.to_bytes();
+    bytes[5] += 1;
+    let err = LocalKeyShare::from_bytes(&bytes).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnsupportedVersion);
+    let err = LocalKeyShare::from_bytes(&bytes[6..]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+}
+
+#[test]
+fn test_signature_encodings() {
+    use crate::protocols::multi_party_ecdsa::gg_2018::party_i::SignatureRecid;
+    use curv::BigInt;
+    use std::collections::HashSet;
+
+    let (presignature_vec, y) = presign(1, 3, 2, &[0, 2]);
+    let R = presignature_vec[0].R();
+    let message = BigInt::from(1234);
+    let mut used_ids = HashSet::new();
+    let local_sig_vec = presignature_vec
+        .into_iter()
+        .map(|presignature| presignature.sign(&message, &mut used_ids).unwrap())
+        .collect::<Vec<LocalSignature>>();
+    let sig = phase5_output_signature(&local_sig_vec, &R);
+
+    let (secp_sig, secp_recid) = sig.to_libsecp256k1().unwrap();
+    let decoded = vec![
+        SignatureRecid::from_der(&sig.to_der(), sig.recid).unwrap(),
+        SignatureRecid::from_compact(&sig.to_compact(), sig.recid).unwrap(),
+        SignatureRecid::from_recoverable(&sig.to_recoverable()).unwrap(),
+        SignatureRecid::from_libsecp256k1(&secp_sig, &secp_recid).unwrap(),
+    ];
+    for decoded_sig in &decoded {
+        assert_eq!(decoded_sig.r, sig.r);
+        assert_eq!(decoded_sig.s, sig.s);
+        assert_eq!(decoded_sig.recid, sig.recid);
+        verify(decoded_sig, &y, &message).unwrap();
+        assert_eq!(decoded_sig.recover_public_key(&message).unwrap(), y);
+    }
+
+    let mut recoverable = sig.to_recoverable();
+    recoverable[64] = 4;
+    let err = SignatureRecid::from_recoverable(&recoverable).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+}
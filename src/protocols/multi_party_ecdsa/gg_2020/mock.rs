@@ -0,0 +1,249 @@
+#![allow(non_snake_case)]
+
+use crate::protocols::multi_party_ecdsa::gg_2018::mock::{check_sig, keygen_t_n_parties};
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{verify, PartyPrivate, SignKeys};
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    phase2_g_w_vec, phase4_compute_R, DeltaReveal, LocalSignature, Phase1Broadcast, Phase2MtA,
+    Phase3Broadcast, Phase5Broadcast, Phase6Broadcast, PreSignature, SigmaReveal,
+};
+use crate::Error;
+use crate::ErrorKind::BadSum;
+
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE, GE};
+use paillier::EncryptionKey;
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::DLogStatement;
+
+pub fn sign(t: u16, n: u16, ttag: u16, s: Vec<usize>) {
+    sign_with_faults(t, n, ttag, &s, None, None).expect("signing failed");
+}
+
+/// Runs keygen and signing, the signer at position `bad_delta` (if any) broadcasts a wrong
+/// delta_i in phase 3.
+pub fn sign_with_bad_delta(
+    t: u16,
+    n: u16,
+    ttag: u16,
+    s: &[usize],
+    bad_delta: Option<usize>,
+) -> Result<(), Error> {
+    sign_with_faults(t, n, ttag, s, bad_delta, None)
+}
+
+/// Runs keygen and signing, the signer at position `bad_sigma` (if any) uses a wrong sigma_i
+/// from phase 3 on, with T_i and S_i consistent with it.
+pub fn sign_with_bad_sigma(
+    t: u16,
+    n: u16,
+    ttag: u16,
+    s: &[usize],
+    bad_sigma: Option<usize>,
+) -> Result<(), Error> {
+    sign_with_faults(t, n, ttag, s, None, bad_sigma)
+}
+
+fn sign_with_faults(
+    t: u16,
+    n: u16,
+    ttag: u16,
+    s: &[usize],
+    bad_delta: Option<usize>,
+    bad_sigma: Option<usize>,
+) -> Result<(), Error> {
+    // full key gen emulation
+    let (party_keys_vec, shared_keys_vec, pk_vec, y, vss_scheme_vec) = keygen_t_n_parties(t, n);
+    let vss_scheme = &vss_scheme_vec[0];
+
+    let private_vec = (0..shared_keys_vec.len())
+        .map(|i| PartyPrivate::set_private(party_keys_vec[i].clone(), shared_keys_vec[i].clone()))
+        .collect::<Vec<PartyPrivate>>();
+    assert!(ttag > t);
+    let ttag = ttag as usize;
+    assert_eq!(s.len(), ttag);
+
+    // throughout p indexes positions in s
+    let sign_keys_vec = (0..ttag)
+        .map(|p| SignKeys::create(&private_vec[s[p]], vss_scheme, s[p], s))
+        .collect::<Vec<SignKeys>>();
+    let ek_vec = s
+        .iter()
+        .map(|&i| party_keys_vec[i].ek.clone())
+        .collect::<Vec<EncryptionKey>>();
+    let dk_vec = s
+        .iter()
+        .map(|&i| party_keys_vec[i].dk.clone())
+        .collect::<Vec<_>>();
+    let dlog_statement_vec = s
+        .iter()
+        .map(|&i| party_keys_vec[i].dlog_statement())
+        .collect::<Vec<DLogStatement>>();
+    let g_w_vec = phase2_g_w_vec(&pk_vec, vss_scheme, s);
+
+    // phase 1: commitment to g^gamma_i and Enc(k_i) with range proofs
+    let mut bc1_vec = Vec::new();
+    let mut decommit_vec = Vec::new();
+    let mut k_randomness_vec = Vec::new();
+    for p in 0..ttag {
+        let (bc1, decommit, k_randomness) =
+            Phase1Broadcast::create(&sign_keys_vec[p], &ek_vec[p], &dlog_statement_vec);
+        bc1_vec.push(bc1);
+        decommit_vec.push(decommit);
+        k_randomness_vec.push(k_randomness);
+    }
+
+    // phase 2: every signer answers the MtA of every other signer
+    let mut mta_sent_vec = Vec::new();
+    let mut beta_vec_all = Vec::new();
+    let mut ni_vec_all = Vec::new();
+    for p in 0..ttag {
+        let (mta_vec, beta_vec, ni_vec) =
+            Phase2MtA::create(&sign_keys_vec[p], p, &bc1_vec, &ek_vec, &dlog_statement_vec)?;
+        mta_sent_vec.push(mta_vec);
+        beta_vec_all.push(beta_vec);
+        ni_vec_all.push(ni_vec);
+    }
+    // the answers received by p, mta_sent_vec[q] skips q
+    let mta_received_vec = (0..ttag)
+        .map(|p| {
+            (0..ttag)
+                .filter(|&q| q != p)
+                .map(|q| mta_sent_vec[q][if p < q { p } else { p - 1 }].clone())
+                .collect::<Vec<Phase2MtA>>()
+        })
+        .collect::<Vec<_>>();
+
+    // phase 3: delta_i and T_i
+    let mut bc3_vec = Vec::new();
+    let mut sigma_vec = Vec::new();
+    let mut l_vec = Vec::new();
+    for p in 0..ttag {
+        let (mut bc3, sigma_i, l_i) = Phase3Broadcast::create(
+            &sign_keys_vec[p],
+            p,
+            &bc1_vec,
+            &mta_received_vec[p],
+            &dk_vec[p],
+            &ek_vec[p],
+            &dlog_statement_vec[p],
+            &g_w_vec,
+            &beta_vec_all[p],
+            &ni_vec_all[p],
+        )?;
+        if bad_delta == Some(p) {
+            bc3.delta_i = bc3.delta_i + FE::new_random();
+        }
+        let mut sigma_i = sigma_i;
+        if bad_sigma == Some(p) {
+            let e = FE::new_random();
+            sigma_i = sigma_i + e;
+            bc3.T_i = bc3.T_i + GE::generator() * e;
+        }
+        bc3_vec.push(bc3);
+        sigma_vec.push(sigma_i);
+        l_vec.push(l_i);
+    }
+    beta_vec_all.zeroize();
+    ni_vec_all.zeroize();
+
+    // phase 4: decommit g^gamma_i, compute R
+    let R = phase4_compute_R(&bc1_vec, &decommit_vec, &bc3_vec)?;
+
+    // phase 5: R_i = R^k_i, on a bad sum everyone reveals the gamma MtA
+    let bc5_vec = (0..ttag)
+        .map(|p| {
+            Phase5Broadcast::create(
+                &sign_keys_vec[p],
+                &R,
+                &bc1_vec[p].c_k,
+                &k_randomness_vec[p],
+                &ek_vec[p],
+                &dk_vec[p],
+                &dlog_statement_vec,
+            )
+        })
+        .collect::<Vec<Phase5Broadcast>>();
+    for p in 0..ttag {
+        match Phase5Broadcast::phase5_check_R_i(
+            p,
+            &R,
+            &bc1_vec,
+            &bc5_vec,
+            &ek_vec,
+            &dlog_statement_vec[p],
+        ) {
+            Err(ref err) if err.kind == BadSum => {
+                let reveal_vec = (0..ttag)
+                    .map(|q| {
+                        DeltaReveal::create(
+                            &sign_keys_vec[q],
+                            &k_randomness_vec[q],
+                            &mta_received_vec[q],
+                            &dk_vec[q],
+                        )
+                    })
+                    .collect::<Vec<DeltaReveal>>();
+                return Err(DeltaReveal::identify_bad_delta(
+                    &reveal_vec,
+                    &bc1_vec,
+                    &decommit_vec,
+                    &bc3_vec,
+                    &ek_vec,
+                    &mta_sent_vec,
+                ));
+            }
+            result => result?,
+        }
+    }
+
+    // phase 6: S_i = R^sigma_i, on a bad sum everyone reveals the w MtA
+    let bc6_vec = (0..ttag)
+        .map(|p| Phase6Broadcast::create(&R, &bc3_vec[p].T_i, &sigma_vec[p], &l_vec[p]))
+        .collect::<Vec<Phase6Broadcast>>();
+    match Phase6Broadcast::phase6_check_S_i(&R, &y, &bc3_vec, &bc6_vec) {
+        Err(ref err) if err.kind == BadSum => {
+            let reveal_vec = (0..ttag)
+                .map(|q| {
+                    SigmaReveal::create(
+                        &sign_keys_vec[q],
+                        &k_randomness_vec[q],
+                        &l_vec[q],
+                        &mta_received_vec[q],
+                        &dk_vec[q],
+                    )
+                })
+                .collect::<Vec<SigmaReveal>>();
+            return Err(SigmaReveal::identify_bad_sigma(
+                &reveal_vec,
+                &bc1_vec,
+                &bc3_vec,
+                &ek_vec,
+                &g_w_vec,
+                &mta_sent_vec,
+            ));
+        }
+        result => result?,
+    }
+
+    let presignature_vec = (0..ttag)
+        .map(|p| PreSignature::create(&sign_keys_vec[p], &R, &sigma_vec[p], &bc5_vec, &bc6_vec, &y))
+        .collect::<Vec<PreSignature>>();
+
+    // online phase: a single round of s_i
+    let message: [u8; 4] = [79, 77, 69, 82];
+    let message_bn = HSha256::create_hash(&[&BigInt::from(&message[..])]);
+    let local_sig_vec = presignature_vec
+        .into_iter()
+        .map(|presignature| LocalSignature::phase7_local_sig(presignature, &message_bn))
+        .collect::<Result<Vec<LocalSignature>, Error>>()?;
+    let s_vec = local_sig_vec.iter().map(|sig| sig.s_i).collect::<Vec<FE>>();
+    for local_sig in &local_sig_vec {
+        let sig = local_sig.output_signature(&s_vec)?;
+        verify(&sig, &y, &message_bn).unwrap();
+        check_sig(&sig.r, &sig.s, &message_bn, &y);
+        assert_eq!(sig.recover_public_key(&message_bn)?, y);
+    }
+    Ok(())
+}
@@ -0,0 +1,917 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Signing of https://eprint.iacr.org/2020/540.pdf. Keys come from the gg18 key generation,
+//! whose `Keys` carry the paillier key and ring-Pedersen parameters every signer needs.
+//!
+//! Phases 1 to 6 do not depend on the message and output a `PreSignature`, signing is then a
+//! single round. Every phase checks the messages of all parties and names the ones that
+//! failed, parties are positions in the signer set `s`. Vectors named `*_vec` hold one entry
+//! per signer, the MtA vectors of phases 2 and 3 skip the own position, as in gg18.
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
+use curv::cryptographic_primitives::commitments::traits::Commitment;
+use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::*;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE, GE};
+use paillier::{
+    Decrypt, DecryptionKey, EncryptWithChosenRandomness, EncryptionKey, Paillier, Randomness,
+    RawCiphertext, RawPlaintext,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
+    check, verify, SignDecommitPhase1, SignKeys, SignatureRecid,
+};
+use crate::utilities::mta::range_proofs::AliceProof;
+use crate::utilities::mta::{MessageAWithRangeProof, MessageBWithRangeProof};
+use crate::utilities::secret::Redacted;
+use crate::utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness};
+use crate::Error;
+use crate::ErrorKind::*;
+use crate::Protocol::Gg20Sign;
+
+/// Commitment to g^gamma_i, Enc(k_i) and a range proof of k_i for every signer, made against
+/// the ring-Pedersen parameters of that signer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase1Broadcast {
+    pub com: BigInt,
+    pub c_k: BigInt,
+    pub range_proofs: Vec<AliceProof>,
+}
+
+/// The answers of one signer to the two MtA of another, sent point to point.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase2MtA {
+    pub m_b_gamma: MessageBWithRangeProof,
+    pub m_b_w: MessageBWithRangeProof,
+}
+
+/// delta_i and the Pedersen commitment T_i = g^sigma_i * h^l_i.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase3Broadcast {
+    pub delta_i: FE,
+    pub T_i: GE,
+}
+
+/// R_i = R^k_i with a proof, for every signer, that k_i is the plaintext of c_k.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase5Broadcast {
+    pub R_i: GE,
+    pub pdl_proofs: Vec<PDLwSlackProof>,
+}
+
+/// S_i = R^sigma_i with a proof that sigma_i is the one committed in T_i.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase6Broadcast {
+    pub S_i: GE,
+    pub proof: HomoELGamalProof,
+}
+
+/// Opening of the gamma MtA, broadcast when the check of phase 5 fails. The nonce is dropped
+/// afterwards, so revealing k_i and gamma_i is safe. c_b_vec holds the ciphertexts of the
+/// m_b_gamma received from the other signers, it is checked against the round 2 messages as
+/// sent, which must be authenticated by their sender for the blame to hold.
+#[derive(Serialize, Deserialize)]
+pub struct DeltaReveal {
+    pub k_i: FE,
+    pub k_randomness: BigInt,
+    pub gamma_i: FE,
+    pub c_b_vec: Vec<BigInt>,
+    pub alpha_vec: Vec<BigInt>,
+    pub alpha_randomness_vec: Vec<BigInt>,
+}
+
+impl fmt::Debug for DeltaReveal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeltaReveal")
+            .field("k_i", &Redacted)
+            .field("k_randomness", &Redacted)
+            .field("gamma_i", &Redacted)
+            .field("c_b_vec", &self.c_b_vec)
+            .field("alpha_vec", &Redacted)
+            .field("alpha_randomness_vec", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for DeltaReveal {
+    fn zeroize(&mut self) {
+        self.k_i.zeroize();
+        self.k_randomness.zeroize_bn();
+        self.gamma_i.zeroize();
+        for alpha in self.alpha_vec.iter_mut() {
+            alpha.zeroize_bn();
+        }
+        for randomness in self.alpha_randomness_vec.iter_mut() {
+            randomness.zeroize_bn();
+        }
+    }
+}
+
+impl Drop for DeltaReveal {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Opening of the w MtA and of T_i, broadcast when the check of phase 6 fails. As for
+/// `DeltaReveal` the nonce is dropped afterwards. The key shares stay hidden, sigma_i is
+/// checked in the exponent against g^w_i. c_b_vec holds the ciphertexts of the m_b_w
+/// received from the other signers.
+#[derive(Serialize, Deserialize)]
+pub struct SigmaReveal {
+    pub k_i: FE,
+    pub k_randomness: BigInt,
+    pub l_i: FE,
+    pub c_b_vec: Vec<BigInt>,
+    pub miu_vec: Vec<BigInt>,
+    pub miu_randomness_vec: Vec<BigInt>,
+}
+
+impl fmt::Debug for SigmaReveal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SigmaReveal")
+            .field("k_i", &Redacted)
+            .field("k_randomness", &Redacted)
+            .field("l_i", &Redacted)
+            .field("c_b_vec", &self.c_b_vec)
+            .field("miu_vec", &Redacted)
+            .field("miu_randomness_vec", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for SigmaReveal {
+    fn zeroize(&mut self) {
+        self.k_i.zeroize();
+        self.k_randomness.zeroize_bn();
+        self.l_i.zeroize();
+        for miu in self.miu_vec.iter_mut() {
+            miu.zeroize_bn();
+        }
+        for randomness in self.miu_randomness_vec.iter_mut() {
+            randomness.zeroize_bn();
+        }
+    }
+}
+
+impl Drop for SigmaReveal {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Output of the offline phases. It is consumed by `phase7_local_sig` and is not `Clone`, a
+/// serialized copy must be deleted once it is used so a nonce never signs two messages.
+#[derive(Serialize, Deserialize)]
+pub struct PreSignature {
+    pub R: GE,
+    pub k_i: FE,
+    pub sigma_i: FE,
+    pub R_i_vec: Vec<GE>,
+    pub S_i_vec: Vec<GE>,
+    pub y: GE,
+}
+
+impl fmt::Debug for PreSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PreSignature")
+            .field("R", &self.R)
+            .field("k_i", &Redacted)
+            .field("sigma_i", &Redacted)
+            .field("R_i_vec", &self.R_i_vec)
+            .field("S_i_vec", &self.S_i_vec)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+impl Zeroize for PreSignature {
+    fn zeroize(&mut self) {
+        self.k_i.zeroize();
+        self.sigma_i.zeroize();
+    }
+}
+
+impl Drop for PreSignature {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalSignature {
+    pub s_i: FE,
+    pub R: GE,
+    pub R_i_vec: Vec<GE>,
+    pub S_i_vec: Vec<GE>,
+    pub m: BigInt,
+    pub y: GE,
+}
+
+/// g^w_j of every signer, from the commitments g^x_i of the key generation.
+pub fn phase2_g_w_vec(xi_commitments: &[GE], vss_scheme: &VerifiableSS, s: &[usize]) -> Vec<GE> {
+    s.iter()
+        .map(|&j| xi_commitments[j] * vss_scheme.map_share_to_new_params(j, s))
+        .collect()
+}
+
+impl Phase1Broadcast {
+    /// Returns the broadcast, the decommitment of phase 4 and the randomness of c_k.
+    pub fn create(
+        sign_keys: &SignKeys,
+        ek: &EncryptionKey,
+        dlog_statement_vec: &[DLogStatement],
+    ) -> (Self, SignDecommitPhase1, BigInt) {
+        let (com, decommit) = sign_keys.phase1_broadcast();
+        let k_randomness = BigInt::sample_below(&ek.n);
+        let c_k = encrypt(ek, &sign_keys.k_i.to_big_int(), &k_randomness);
+        let range_proofs = dlog_statement_vec
+            .iter()
+            .map(|statement| {
+                AliceProof::generate(
+                    &sign_keys.k_i.to_big_int(),
+                    &c_k,
+                    ek,
+                    statement,
+                    &k_randomness,
+                )
+            })
+            .collect();
+        (
+            Phase1Broadcast {
+                com: com.com,
+                c_k,
+                range_proofs,
+            },
+            decommit,
+            k_randomness,
+        )
+    }
+
+    /// MtA message a for signer j.
+    pub fn message_a(&self, j: usize) -> Option<MessageAWithRangeProof> {
+        self.range_proofs
+            .get(j)
+            .map(|range_proof| MessageAWithRangeProof {
+                c: self.c_k.clone(),
+                range_proof: range_proof.clone(),
+            })
+    }
+}
+
+impl Phase2MtA {
+    /// Answers the MtA of every other signer, with gamma_i and with w_i. Returns the messages
+    /// and the beta and ni shares, all three skip the own position i.
+    pub fn create(
+        sign_keys: &SignKeys,
+        i: usize,
+        bc1_vec: &[Phase1Broadcast],
+        ek_vec: &[EncryptionKey],
+        dlog_statement_vec: &[DLogStatement],
+    ) -> Result<(Vec<Self>, Vec<FE>, Vec<FE>), Error> {
+        let len = bc1_vec.len();
+        if ek_vec.len() != len || dlog_statement_vec.len() != len || i >= len {
+            return Err(Error::new(Gg20Sign, "phase2", LengthMismatch));
+        }
+        let mut mta_vec = Vec::new();
+        let mut beta_vec = Vec::new();
+        let mut ni_vec = Vec::new();
+        let mut bad_actors = Vec::new();
+        for j in (0..len).filter(|&j| j != i) {
+            let answers = bc1_vec[j].message_a(i).ok_or(()).and_then(|m_a| {
+                let (m_b_gamma, beta, _) = MessageBWithRangeProof::b(
+                    &sign_keys.gamma_i,
+                    &ek_vec[j],
+                    &m_a,
+                    &dlog_statement_vec[j],
+                    &dlog_statement_vec[i],
+                )
+                .map_err(|_| ())?;
+                let (m_b_w, ni, _) = MessageBWithRangeProof::b_with_check(
+                    &sign_keys.w_i,
+                    &ek_vec[j],
+                    &m_a,
+                    &dlog_statement_vec[j],
+                    &dlog_statement_vec[i],
+                )
+                .map_err(|_| ())?;
+                Ok((Phase2MtA { m_b_gamma, m_b_w }, beta, ni))
+            });
+            match answers {
+                Ok((mta, beta, ni)) => {
+                    mta_vec.push(mta);
+                    beta_vec.push(beta);
+                    ni_vec.push(ni);
+                }
+                Err(()) => bad_actors.push(j),
+            }
+        }
+        check(Gg20Sign, "phase2", InvalidRangeProof, bad_actors)?;
+        Ok((mta_vec, beta_vec, ni_vec))
+    }
+}
+
+impl Phase3Broadcast {
+    /// Completes the MtA with the answers received from the other signers (skipping i) and
+    /// returns the broadcast together with sigma_i and the blinding l_i of T_i.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        sign_keys: &SignKeys,
+        i: usize,
+        bc1_vec: &[Phase1Broadcast],
+        mta_vec: &[Phase2MtA],
+        dk: &DecryptionKey,
+        ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        g_w_vec: &[GE],
+        beta_vec: &[FE],
+        ni_vec: &[FE],
+    ) -> Result<(Self, FE, FE), Error> {
+        let len = bc1_vec.len();
+        if i >= len
+            || g_w_vec.len() != len
+            || mta_vec.len() + 1 != len
+            || beta_vec.len() + 1 != len
+            || ni_vec.len() + 1 != len
+        {
+            return Err(Error::new(Gg20Sign, "phase3", LengthMismatch));
+        }
+        let c_k = &bc1_vec[i].c_k;
+        let mut alpha_vec = Vec::new();
+        let mut miu_vec = Vec::new();
+        let mut bad_actors = Vec::new();
+        for (mta, j) in mta_vec.iter().zip((0..len).filter(|&j| j != i)) {
+            let alpha = mta
+                .m_b_gamma
+                .verify_proofs_get_alpha(dk, ek, c_k, dlog_statement);
+            let miu = mta.m_b_w.verify_proofs_get_alpha_with_check(
+                dk,
+                ek,
+                c_k,
+                dlog_statement,
+                &g_w_vec[j],
+            );
+            match (alpha, miu) {
+                (Ok(alpha), Ok(miu)) => {
+                    alpha_vec.push(alpha);
+                    miu_vec.push(miu);
+                }
+                _ => bad_actors.push(j),
+            }
+        }
+        check(Gg20Sign, "phase3", InvalidMtaProof, bad_actors)?;
+
+        let delta_i = sign_keys.phase2_delta_i(&alpha_vec, beta_vec)?;
+        let sigma_i = sign_keys.phase2_sigma_i(&miu_vec, ni_vec)?;
+        let l_i: FE = ECScalar::new_random();
+        let g: GE = ECPoint::generator();
+        let h: GE = GE::base_point2();
+        let T_i = g * sigma_i + h * l_i;
+        Ok((Phase3Broadcast { delta_i, T_i }, sigma_i, l_i))
+    }
+}
+
+/// Checks the decommitments of g^gamma_i and returns R = (sum g^gamma_i)^(1 / sum delta_i).
+pub fn phase4_compute_R(
+    bc1_vec: &[Phase1Broadcast],
+    decommit_vec: &[SignDecommitPhase1],
+    bc3_vec: &[Phase3Broadcast],
+) -> Result<GE, Error> {
+    if bc1_vec.is_empty() || decommit_vec.len() != bc1_vec.len() || bc3_vec.len() != bc1_vec.len() {
+        return Err(Error::new(Gg20Sign, "phase4", LengthMismatch));
+    }
+    let bad_actors = (0..bc1_vec.len())
+        .filter(|&i| {
+            HashCommitment::create_commitment_with_user_defined_randomness(
+                &decommit_vec[i].g_gamma_i.bytes_compressed_to_big_int(),
+                &decommit_vec[i].blind_factor,
+            ) != bc1_vec[i].com
+        })
+        .collect::<Vec<usize>>();
+    check(Gg20Sign, "phase4", InvalidCommitment, bad_actors)?;
+
+    let q = FE::q();
+    let delta = bc3_vec.iter().fold(BigInt::zero(), |acc, bc3| {
+        BigInt::mod_add(&acc, &bc3.delta_i.to_big_int(), &q)
+    });
+    if delta == BigInt::zero() {
+        return Err(Error::new(Gg20Sign, "phase4", ZeroSum));
+    }
+    let delta: FE = ECScalar::from(&delta);
+    let delta_inv = delta.invert();
+    let (head, tail) = decommit_vec.split_at(1);
+    let gamma_sum = tail
+        .iter()
+        .fold(head[0].g_gamma_i, |acc, x| acc + x.g_gamma_i);
+    Ok(gamma_sum * delta_inv)
+}
+
+impl Phase5Broadcast {
+    pub fn create(
+        sign_keys: &SignKeys,
+        R: &GE,
+        c_k: &BigInt,
+        k_randomness: &BigInt,
+        ek: &EncryptionKey,
+        dk: &DecryptionKey,
+        dlog_statement_vec: &[DLogStatement],
+    ) -> Self {
+        let R_i = R * &sign_keys.k_i;
+        let witness = PDLwSlackWitness {
+            x: sign_keys.k_i,
+            r: k_randomness.clone(),
+            dk: dk.clone(),
+        };
+        let pdl_proofs = dlog_statement_vec
+            .iter()
+            .map(|statement| {
+                PDLwSlackProof::prove(&witness, &pdl_statement(c_k, ek, &R_i, R, statement))
+            })
+            .collect();
+        Phase5Broadcast { R_i, pdl_proofs }
+    }
+
+    /// Verifies the proofs made for signer i and that sum R_i = g. If only the sum fails,
+    /// some delta_i is wrong and the signers broadcast their `DeltaReveal`.
+    pub fn phase5_check_R_i(
+        i: usize,
+        R: &GE,
+        bc1_vec: &[Phase1Broadcast],
+        bc5_vec: &[Phase5Broadcast],
+        ek_vec: &[EncryptionKey],
+        dlog_statement: &DLogStatement,
+    ) -> Result<(), Error> {
+        let len = bc1_vec.len();
+        if len == 0 || bc5_vec.len() != len || ek_vec.len() != len {
+            return Err(Error::new(Gg20Sign, "phase5", LengthMismatch));
+        }
+        let bad_actors = (0..len)
+            .filter(|&j| match bc5_vec[j].pdl_proofs.get(i) {
+                Some(proof) => proof
+                    .verify(&pdl_statement(
+                        &bc1_vec[j].c_k,
+                        &ek_vec[j],
+                        &bc5_vec[j].R_i,
+                        R,
+                        dlog_statement,
+                    ))
+                    .is_err(),
+                None => true,
+            })
+            .collect::<Vec<usize>>();
+        check(Gg20Sign, "phase5", InvalidPdlProof, bad_actors)?;
+
+        let (head, tail) = bc5_vec.split_at(1);
+        let R_sum = tail.iter().fold(head[0].R_i, |acc, x| acc + x.R_i);
+        if R_sum == GE::generator() {
+            Ok(())
+        } else {
+            Err(Error::new(Gg20Sign, "phase5", BadSum))
+        }
+    }
+}
+
+impl DeltaReveal {
+    /// mta_vec are the answers received in phase 2, skipping i.
+    pub fn create(
+        sign_keys: &SignKeys,
+        k_randomness: &BigInt,
+        mta_vec: &[Phase2MtA],
+        dk: &DecryptionKey,
+    ) -> Self {
+        let c_b_vec = mta_vec
+            .iter()
+            .map(|mta| mta.m_b_gamma.c.clone())
+            .collect::<Vec<BigInt>>();
+        let (alpha_vec, alpha_randomness_vec) = open_mta(dk, &c_b_vec);
+        DeltaReveal {
+            k_i: sign_keys.k_i,
+            k_randomness: k_randomness.clone(),
+            gamma_i: sign_keys.gamma_i,
+            c_b_vec,
+            alpha_vec,
+            alpha_randomness_vec,
+        }
+    }
+
+    /// Finds the signers whose delta_i does not match the revealed values. A signer whose
+    /// reveal does not open its own ciphertexts or commitments, or reports ciphertexts other
+    /// than the ones in `mta_sent_vec`, is blamed for that instead. `mta_sent_vec` holds the
+    /// round 2 messages of every signer as sent, skipping its own position. The error lists no
+    /// party if every delta_i matches.
+    pub fn identify_bad_delta(
+        reveal_vec: &[DeltaReveal],
+        bc1_vec: &[Phase1Broadcast],
+        decommit_vec: &[SignDecommitPhase1],
+        bc3_vec: &[Phase3Broadcast],
+        ek_vec: &[EncryptionKey],
+        mta_sent_vec: &[Vec<Phase2MtA>],
+    ) -> Error {
+        let len = reveal_vec.len();
+        if bc1_vec.len() != len
+            || decommit_vec.len() != len
+            || bc3_vec.len() != len
+            || ek_vec.len() != len
+        {
+            return Error::new(Gg20Sign, "phase5 reveal", LengthMismatch);
+        }
+        if let Err(err) = check_mta_sent(mta_sent_vec, len, "phase5 reveal") {
+            return err;
+        }
+        let g: GE = ECPoint::generator();
+        let bad_reveal = (0..len)
+            .filter(|&i| {
+                let reveal = &reveal_vec[i];
+                encrypt(&ek_vec[i], &reveal.k_i.to_big_int(), &reveal.k_randomness)
+                    != bc1_vec[i].c_k
+                    || g * reveal.gamma_i != decommit_vec[i].g_gamma_i
+                    || !opens_mta(
+                        i,
+                        &ek_vec[i],
+                        &reveal.c_b_vec,
+                        &reveal.alpha_vec,
+                        &reveal.alpha_randomness_vec,
+                        mta_sent_vec,
+                        |mta| &mta.m_b_gamma.c,
+                    )
+            })
+            .collect::<Vec<usize>>();
+        if !bad_reveal.is_empty() {
+            return Error::new(Gg20Sign, "phase5 reveal", InvalidCommitment)
+                .with_parties(bad_reveal);
+        }
+
+        // alpha_vec[j] of party i is its share of k_i * gamma_j, j skipping i
+        let alpha =
+            |i: usize, j: usize| -> FE { ECScalar::from(&reveal_vec[i].alpha_vec[skip_pos(j, i)]) };
+        let bad_actors = (0..len)
+            .filter(|&i| {
+                let reveal = &reveal_vec[i];
+                let delta_i =
+                    (0..len)
+                        .filter(|&j| j != i)
+                        .fold(reveal.k_i * reveal.gamma_i, |acc, j| {
+                            let beta_ij = (reveal_vec[j].k_i * reveal.gamma_i)
+                                .sub(&alpha(j, i).get_element());
+                            acc + alpha(i, j) + beta_ij
+                        });
+                delta_i != bc3_vec[i].delta_i
+            })
+            .collect::<Vec<usize>>();
+        Error::new(Gg20Sign, "phase5 reveal", InvalidDelta).with_parties(bad_actors)
+    }
+}
+
+impl Phase6Broadcast {
+    pub fn create(R: &GE, T_i: &GE, sigma_i: &FE, l_i: &FE) -> Self {
+        let S_i = R * sigma_i;
+        let witness = HomoElGamalWitness {
+            r: *sigma_i,
+            x: *l_i,
+        };
+        let statement = phase6_statement(R, T_i, &S_i);
+        Phase6Broadcast {
+            S_i,
+            proof: HomoELGamalProof::prove(&witness, &statement),
+        }
+    }
+
+    /// Verifies every S_i against T_i and that sum S_i = y. If only the sum fails, some
+    /// sigma_i is wrong and the signers broadcast their `SigmaReveal`.
+    pub fn phase6_check_S_i(
+        R: &GE,
+        y: &GE,
+        bc3_vec: &[Phase3Broadcast],
+        bc6_vec: &[Phase6Broadcast],
+    ) -> Result<(), Error> {
+        if bc6_vec.is_empty() || bc3_vec.len() != bc6_vec.len() {
+            return Err(Error::new(Gg20Sign, "phase6", LengthMismatch));
+        }
+        let bad_actors = (0..bc6_vec.len())
+            .filter(|&i| {
+                let statement = phase6_statement(R, &bc3_vec[i].T_i, &bc6_vec[i].S_i);
+                bc6_vec[i].proof.verify(&statement).is_err()
+            })
+            .collect::<Vec<usize>>();
+        check(Gg20Sign, "phase6", InvalidHomoElGamalProof, bad_actors)?;
+
+        let (head, tail) = bc6_vec.split_at(1);
+        let S_sum = tail.iter().fold(head[0].S_i, |acc, x| acc + x.S_i);
+        if S_sum == *y {
+            Ok(())
+        } else {
+            Err(Error::new(Gg20Sign, "phase6", BadSum))
+        }
+    }
+}
+
+impl SigmaReveal {
+    /// mta_vec are the answers received in phase 2, skipping i, l_i is the blinding of T_i.
+    pub fn create(
+        sign_keys: &SignKeys,
+        k_randomness: &BigInt,
+        l_i: &FE,
+        mta_vec: &[Phase2MtA],
+        dk: &DecryptionKey,
+    ) -> Self {
+        let c_b_vec = mta_vec
+            .iter()
+            .map(|mta| mta.m_b_w.c.clone())
+            .collect::<Vec<BigInt>>();
+        let (miu_vec, miu_randomness_vec) = open_mta(dk, &c_b_vec);
+        SigmaReveal {
+            k_i: sign_keys.k_i,
+            k_randomness: k_randomness.clone(),
+            l_i: *l_i,
+            c_b_vec,
+            miu_vec,
+            miu_randomness_vec,
+        }
+    }
+
+    /// Finds the signers whose T_i does not commit to the sigma_i of the revealed values,
+    /// sigma_i = k_i * w_i + sum_j (miu_ij + k_j * w_i - miu_ji), checked against g^w_i. Bad
+    /// reveals are blamed as in `DeltaReveal::identify_bad_delta`. S_i is bound to T_i by the
+    /// proof of phase 6. The error lists no party if every T_i matches.
+    pub fn identify_bad_sigma(
+        reveal_vec: &[SigmaReveal],
+        bc1_vec: &[Phase1Broadcast],
+        bc3_vec: &[Phase3Broadcast],
+        ek_vec: &[EncryptionKey],
+        g_w_vec: &[GE],
+        mta_sent_vec: &[Vec<Phase2MtA>],
+    ) -> Error {
+        let len = reveal_vec.len();
+        if len == 0
+            || bc1_vec.len() != len
+            || bc3_vec.len() != len
+            || ek_vec.len() != len
+            || g_w_vec.len() != len
+        {
+            return Error::new(Gg20Sign, "phase6 reveal", LengthMismatch);
+        }
+        if let Err(err) = check_mta_sent(mta_sent_vec, len, "phase6 reveal") {
+            return err;
+        }
+        let bad_reveal = (0..len)
+            .filter(|&i| {
+                let reveal = &reveal_vec[i];
+                encrypt(&ek_vec[i], &reveal.k_i.to_big_int(), &reveal.k_randomness)
+                    != bc1_vec[i].c_k
+                    || !opens_mta(
+                        i,
+                        &ek_vec[i],
+                        &reveal.c_b_vec,
+                        &reveal.miu_vec,
+                        &reveal.miu_randomness_vec,
+                        mta_sent_vec,
+                        |mta| &mta.m_b_w.c,
+                    )
+            })
+            .collect::<Vec<usize>>();
+        if !bad_reveal.is_empty() {
+            return Error::new(Gg20Sign, "phase6 reveal", InvalidCommitment)
+                .with_parties(bad_reveal);
+        }
+
+        let q = FE::q();
+        let g: GE = ECPoint::generator();
+        let h: GE = GE::base_point2();
+        let k = reveal_vec[1..]
+            .iter()
+            .fold(reveal_vec[0].k_i, |acc, reveal| acc + reveal.k_i);
+        let bad_actors = (0..len)
+            .filter(|&i| {
+                // miu_ij is the share of party i of k_i * w_j, k_j * w_i - miu_ji the one of
+                // k_j * w_i
+                let miu_sum = (0..len).filter(|&j| j != i).fold(BigInt::zero(), |acc, j| {
+                    let miu_ij = &reveal_vec[i].miu_vec[skip_pos(j, i)];
+                    let miu_ji = &reveal_vec[j].miu_vec[skip_pos(i, j)];
+                    BigInt::mod_sub(&BigInt::mod_add(&acc, miu_ij, &q), miu_ji, &q)
+                });
+                let mut g_sigma_i = g_w_vec[i] * k;
+                if miu_sum != BigInt::zero() {
+                    let miu_sum: FE = ECScalar::from(&miu_sum);
+                    g_sigma_i = g_sigma_i + g * miu_sum;
+                }
+                g_sigma_i + h * reveal_vec[i].l_i != bc3_vec[i].T_i
+            })
+            .collect::<Vec<usize>>();
+        Error::new(Gg20Sign, "phase6 reveal", InvalidSecretShare).with_parties(bad_actors)
+    }
+}
+
+impl PreSignature {
+    pub fn create(
+        sign_keys: &SignKeys,
+        R: &GE,
+        sigma_i: &FE,
+        bc5_vec: &[Phase5Broadcast],
+        bc6_vec: &[Phase6Broadcast],
+        y: &GE,
+    ) -> Self {
+        PreSignature {
+            R: *R,
+            k_i: sign_keys.k_i,
+            sigma_i: *sigma_i,
+            R_i_vec: bc5_vec.iter().map(|bc5| bc5.R_i).collect(),
+            S_i_vec: bc6_vec.iter().map(|bc6| bc6.S_i).collect(),
+            y: *y,
+        }
+    }
+}
+
+impl LocalSignature {
+    /// The online round: s_i is broadcast, the presignature is consumed.
+    pub fn phase7_local_sig(presignature: PreSignature, message: &BigInt) -> Result<Self, Error> {
+        let m_fe: FE = ECScalar::from(message);
+        let r: FE = ECScalar::from(&x_coor_mod_q(&presignature.R, "phase7")?);
+        let s_i = m_fe * presignature.k_i + r * presignature.sigma_i;
+        Ok(LocalSignature {
+            s_i,
+            R: presignature.R,
+            R_i_vec: presignature.R_i_vec.clone(),
+            S_i_vec: presignature.S_i_vec.clone(),
+            m: message.clone(),
+            y: presignature.y,
+        })
+    }
+
+    /// Checks every s_i against R_i and S_i, R^s_i = R_i^m * S_i^r, and returns the
+    /// signature.
+    pub fn output_signature(&self, s_vec: &[FE]) -> Result<SignatureRecid, Error> {
+        if s_vec.is_empty()
+            || self.R_i_vec.len() != s_vec.len()
+            || self.S_i_vec.len() != s_vec.len()
+        {
+            return Err(Error::new(Gg20Sign, "phase7", LengthMismatch));
+        }
+        let m_fe: FE = ECScalar::from(&self.m);
+        let r: FE = ECScalar::from(&x_coor_mod_q(&self.R, "phase7")?);
+        let bad_actors = (0..s_vec.len())
+            .filter(|&i| self.R * s_vec[i] != self.R_i_vec[i] * m_fe + self.S_i_vec[i] * r)
+            .collect::<Vec<usize>>();
+        check(Gg20Sign, "phase7", InvalidSignature, bad_actors)?;
+
+        let (head, tail) = s_vec.split_at(1);
+        let mut s = tail.iter().fold(head[0], |acc, x| acc + x);
+        let s_bn = s.to_big_int();
+        let ry: BigInt = self
+            .R
+            .y_coor()
+            .ok_or_else(|| Error::new(Gg20Sign, "phase7", PointAtInfinity))?
+            .mod_floor(&FE::q());
+
+        // recovery id as in gg18: R.y & 1, flipped when s is replaced by q - s
+        let mut recid = if ry.tstbit(0) { 1 } else { 0 };
+        let s_tag_bn = FE::q() - &s_bn;
+        if s_bn > s_tag_bn {
+            s = ECScalar::from(&s_tag_bn);
+            recid ^= 1;
+        }
+        let sig = SignatureRecid { r, s, recid };
+        verify(&sig, &self.y, &self.m)
+            .map_err(|_| Error::new(Gg20Sign, "phase7", InvalidSignature))?;
+        Ok(sig)
+    }
+}
+
+fn encrypt(ek: &EncryptionKey, m: &BigInt, randomness: &BigInt) -> BigInt {
+    Paillier::encrypt_with_chosen_randomness(
+        ek,
+        RawPlaintext::from(m.clone()),
+        &Randomness::from(randomness.clone()),
+    )
+    .0
+    .into_owned()
+}
+
+// plaintexts and randomness of the MtA ciphertexts received by a signer
+fn open_mta(dk: &DecryptionKey, c_b_vec: &[BigInt]) -> (Vec<BigInt>, Vec<BigInt>) {
+    c_b_vec
+        .iter()
+        .map(|c_b| {
+            let m = Paillier::decrypt(dk, &RawCiphertext::from(c_b.clone()))
+                .0
+                .into_owned();
+            let randomness = extract_randomness(dk, c_b, &m);
+            (m, randomness)
+        })
+        .unzip()
+}
+
+// whether signer i opened the MtA ciphertexts it received, and reported the ones that were
+// sent to it
+fn opens_mta(
+    i: usize,
+    ek: &EncryptionKey,
+    c_b_vec: &[BigInt],
+    m_vec: &[BigInt],
+    randomness_vec: &[BigInt],
+    mta_sent_vec: &[Vec<Phase2MtA>],
+    sent_c_b: fn(&Phase2MtA) -> &BigInt,
+) -> bool {
+    let len = mta_sent_vec.len();
+    c_b_vec.len() + 1 == len
+        && m_vec.len() + 1 == len
+        && randomness_vec.len() + 1 == len
+        && (0..len).filter(|&j| j != i).all(|j| {
+            let pos = skip_pos(j, i);
+            *sent_c_b(&mta_sent_vec[j][skip_pos(i, j)]) == c_b_vec[pos]
+                && encrypt(ek, &m_vec[pos], &randomness_vec[pos]) == c_b_vec[pos]
+        })
+}
+
+// the senders whose round 2 messages do not cover every other signer
+fn check_mta_sent(
+    mta_sent_vec: &[Vec<Phase2MtA>],
+    len: usize,
+    round: &'static str,
+) -> Result<(), Error> {
+    if mta_sent_vec.len() != len {
+        return Err(Error::new(Gg20Sign, round, LengthMismatch));
+    }
+    let bad_actors = (0..len)
+        .filter(|&j| mta_sent_vec[j].len() + 1 != len)
+        .collect::<Vec<usize>>();
+    check(Gg20Sign, round, LengthMismatch, bad_actors)
+}
+
+// position of j in a vector that skips i
+fn skip_pos(j: usize, i: usize) -> usize {
+    if j < i {
+        j
+    } else {
+        j - 1
+    }
+}
+
+// r such that c = (1 + N)^m * r^N mod N^2
+fn extract_randomness(dk: &DecryptionKey, c: &BigInt, m: &BigInt) -> BigInt {
+    let n = &dk.p * &dk.q;
+    let nn = &n * &n;
+    let one = BigInt::one();
+    let phi = (&dk.p - &one) * (&dk.q - &one);
+    // (1 + N)^-m = 1 - mN mod N^2
+    let g_m_inv = BigInt::mod_sub(&one, &BigInt::mod_mul(m, &n, &nn), &nn);
+    let r_n = BigInt::mod_mul(c, &g_m_inv, &nn).mod_floor(&n);
+    let n_inv = n.invert(&phi).expect("N and phi(N) are coprime");
+    BigInt::mod_pow(&r_n, &n_inv, &n)
+}
+
+fn pdl_statement(
+    c_k: &BigInt,
+    ek: &EncryptionKey,
+    R_i: &GE,
+    R: &GE,
+    dlog_statement: &DLogStatement,
+) -> PDLwSlackStatement {
+    PDLwSlackStatement {
+        ciphertext: c_k.clone(),
+        ek: ek.clone(),
+        Q: *R_i,
+        G: *R,
+        h1: dlog_statement.g.clone(),
+        h2: dlog_statement.ni.clone(),
+        N_tilde: dlog_statement.N.clone(),
+    }
+}
+
+// T_i = l_i * h + sigma_i * g and S_i = sigma_i * R
+fn phase6_statement(R: &GE, T_i: &GE, S_i: &GE) -> HomoElGamalStatement {
+    HomoElGamalStatement {
+        G: *R,
+        H: GE::base_point2(),
+        Y: GE::generator(),
+        D: *T_i,
+        E: *S_i,
+    }
+}
+
+fn x_coor_mod_q(point: &GE, round: &'static str) -> Result<BigInt, Error> {
+    point
+        .x_coor()
+        .map(|x| x.mod_floor(&FE::q()))
+        .ok_or_else(|| Error::new(Gg20Sign, round, PointAtInfinity))
+}
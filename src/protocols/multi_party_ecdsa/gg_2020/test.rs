@@ -0,0 +1,77 @@
+#![allow(non_snake_case)]
+
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+use crate::protocols::multi_party_ecdsa::gg_2020::mock::{
+    sign, sign_with_bad_delta, sign_with_bad_sigma,
+};
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::LocalSignature;
+use crate::ErrorKind;
+
+#[test]
+fn test_sign_n3_t1_ttag2() {
+    sign(1, 3, 2, vec![0, 2])
+}
+
+#[test]
+fn test_sign_n4_t2_ttag3() {
+    sign(2, 4, 3, vec![0, 1, 3])
+}
+
+#[test]
+fn test_bad_delta_is_identified() {
+    let err = sign_with_bad_delta(1, 3, 3, &[0, 1, 2], Some(1)).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidDelta);
+    assert_eq!(err.parties, vec![1]);
+}
+
+#[test]
+fn test_bad_sigma_is_identified() {
+    let err = sign_with_bad_sigma(1, 3, 3, &[0, 1, 2], Some(2)).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+    assert_eq!(err.parties, vec![2]);
+}
+
+#[test]
+fn test_output_signature_finds_bad_s_i() {
+    use curv::elliptic::curves::traits::*;
+    use curv::{BigInt, FE, GE};
+
+    let g: GE = ECPoint::generator();
+    let R = g * FE::new_random();
+    let m = BigInt::from(1234);
+    let m_fe: FE = ECScalar::from(&m);
+    let r: FE = ECScalar::from(&R.x_coor().unwrap().mod_floor(&FE::q()));
+    let k_vec = (0..3).map(|_| FE::new_random()).collect::<Vec<FE>>();
+    let sigma_vec = (0..3).map(|_| FE::new_random()).collect::<Vec<FE>>();
+    let local_sig = LocalSignature {
+        s_i: FE::zero(),
+        R,
+        R_i_vec: k_vec.iter().map(|&k| R * k).collect(),
+        S_i_vec: sigma_vec.iter().map(|&sigma| R * sigma).collect(),
+        m,
+        y: g,
+    };
+    let mut s_vec = (0..3)
+        .map(|i| m_fe * k_vec[i] + r * sigma_vec[i])
+        .collect::<Vec<FE>>();
+    s_vec[2] = FE::new_random();
+
+    let err = local_sig.output_signature(&s_vec).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSignature);
+    assert_eq!(err.parties, vec![2]);
+}
@@ -15,3 +15,4 @@
 */
 
 pub mod gg_2018;
+pub mod gg_2020;
@@ -14,6 +14,7 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 use std::cmp;
+use std::fmt;
 
 use class_group::primitives::cl_dl_lcm::Ciphertext;
 use class_group::primitives::cl_dl_lcm::Witness;
@@ -25,26 +26,56 @@ use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::cryptographic_primitives::proofs::sigma_dlog::*;
 use curv::cryptographic_primitives::proofs::sigma_ec_ddh::*;
-use curv::cryptographic_primitives::proofs::ProofError;
 use curv::elliptic::curves::traits::*;
 use curv::BigInt;
 use curv::FE;
 use curv::GE;
+use secp256k1::RecoveryId;
 use serde::{Deserialize, Serialize};
 use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
 
 use super::party_two::EphKeyGenFirstMsg as Party2EphKeyGenFirstMessage;
 use super::party_two::EphKeyGenSecondMsg as Party2EphKeyGenSecondMessage;
 use super::SECURITY_BITS;
-use crate::Error::{self, InvalidSig};
+use crate::utilities::secret::Redacted;
+use crate::utilities::signature_encoding::{
+    self, COMPACT_SIGNATURE_SIZE, RECOVERABLE_SIGNATURE_SIZE,
+};
+use crate::Error;
+use crate::ErrorKind::{
+    InvalidCommitment, InvalidDLogProof, InvalidSecretShare, InvalidSignature, PointAtInfinity,
+};
+use crate::Protocol::{Cclst19Keygen, Cclst19Sign};
 
 //****************** Begin: Party One structs ******************//
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommWitness {
     pub pk_commitment_blind_factor: BigInt,
@@ -64,13 +95,36 @@ pub struct KeyGenSecondMsg {
     pub comm_witness: CommWitness,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct HSMCLKeyPair {
     pub keypair: HSMCL,
     pub encrypted_share: Ciphertext,
     randomness: BigInt,
 }
 
+impl fmt::Debug for HSMCLKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HSMCLKeyPair")
+            .field("keypair", &Redacted)
+            .field("encrypted_share", &self.encrypted_share)
+            .field("randomness", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for HSMCLKeyPair {
+    fn zeroize(&mut self) {
+        self.keypair.sk.zeroize_bn();
+        self.randomness.zeroize_bn();
+    }
+}
+
+impl Drop for HSMCLKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignatureRecid {
     pub s: BigInt,
@@ -91,6 +145,30 @@ pub struct Party1Private {
     c_key_randomness: BigInt,
 }
 
+impl fmt::Debug for Party1Private {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Party1Private")
+            .field("x1", &Redacted)
+            .field("keypair", &Redacted)
+            .field("c_key_randomness", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for Party1Private {
+    fn zeroize(&mut self) {
+        self.x1.zeroize();
+        self.c_key_randomness.zeroize_bn();
+        self.keypair.sk.zeroize_bn();
+    }
+}
+
+impl Drop for Party1Private {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PDLFirstMessage {
     pub c_hat: BigInt,
@@ -107,12 +185,33 @@ pub struct PDLSecondMessage {
     pub decommit: PDLdecommit,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EphEcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EphEcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EphEcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EphEcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EphEcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EphKeyGenFirstMsg {
     pub d_log_proof: ECDDHProof,
@@ -172,11 +271,17 @@ impl KeyGenFirstMsg {
 
     pub fn create_commitments_with_fixed_secret_share(
         secret_share: FE,
-    ) -> (KeyGenFirstMsg, CommWitness, EcKeyPair) {
+    ) -> Result<(KeyGenFirstMsg, CommWitness, EcKeyPair), Error> {
         //in Lindell's protocol range proof works only for x1<q/3
         let sk_bigint = secret_share.to_big_int();
         let q_third = FE::q();
-        assert!(&sk_bigint < &q_third.div_floor(&BigInt::from(3)));
+        if sk_bigint >= q_third.div_floor(&BigInt::from(3)) {
+            return Err(Error::new(
+                Cclst19Keygen,
+                "first message",
+                InvalidSecretShare,
+            ));
+        }
         let base: GE = ECPoint::generator();
         let public_share = base.scalar_mul(&secret_share.get_element());
 
@@ -200,7 +305,7 @@ impl KeyGenFirstMsg {
             public_share,
             secret_share,
         };
-        (
+        Ok((
             KeyGenFirstMsg {
                 pk_commitment,
                 zk_pok_commitment,
@@ -212,7 +317,7 @@ impl KeyGenFirstMsg {
                 d_log_proof,
             },
             ec_key_pair,
-        )
+        ))
     }
 }
 
@@ -220,8 +325,10 @@ impl KeyGenSecondMsg {
     pub fn verify_and_decommit(
         comm_witness: CommWitness,
         proof: &DLogProof,
-    ) -> Result<KeyGenSecondMsg, ProofError> {
-        DLogProof::verify(proof)?;
+    ) -> Result<KeyGenSecondMsg, Error> {
+        DLogProof::verify(proof).map_err(|_| {
+            Error::new(Cclst19Keygen, "first message", InvalidDLogProof).with_parties(vec![2])
+        })?;
         Ok(KeyGenSecondMsg { comm_witness })
     }
 }
@@ -316,7 +423,7 @@ impl EphKeyGenSecondMsg {
     pub fn verify_commitments_and_dlog_proof(
         party_two_first_message: &Party2EphKeyGenFirstMessage,
         party_two_second_message: &Party2EphKeyGenSecondMessage,
-    ) -> Result<EphKeyGenSecondMsg, ProofError> {
+    ) -> Result<EphKeyGenSecondMsg, Error> {
         let party_two_pk_commitment = &party_two_first_message.pk_commitment;
         let party_two_zk_pok_commitment = &party_two_first_message.zk_pok_commitment;
         let party_two_zk_pok_blind_factor =
@@ -347,14 +454,21 @@ impl EphKeyGenSecondMsg {
             false => flag = false,
             true => flag = flag,
         };
-        assert!(flag);
+        if !flag {
+            return Err(
+                Error::new(Cclst19Sign, "ephemeral keygen", InvalidCommitment)
+                    .with_parties(vec![2]),
+            );
+        }
         let delta = ECDDHStatement {
             g1: GE::generator(),
             h1: party_two_public_share.clone(),
             g2: GE::base_point2(),
             h2: party_two_second_message.comm_witness.c.clone(),
         };
-        party_two_d_log_proof.verify(&delta)?;
+        party_two_d_log_proof.verify(&delta).map_err(|_| {
+            Error::new(Cclst19Sign, "ephemeral keygen", InvalidDLogProof).with_parties(vec![2])
+        })?;
         Ok(EphKeyGenSecondMsg {})
     }
 }
@@ -365,12 +479,33 @@ impl Signature {
         partial_sig_c3: Ciphertext,
         ephemeral_local_share: &EphEcKeyPair,
         ephemeral_other_public_share: &GE,
-    ) -> Signature {
+    ) -> Result<Signature, Error> {
+        let SignatureRecid { s, r, .. } = Self::compute_with_recid(
+            party_one_private,
+            partial_sig_c3,
+            ephemeral_local_share,
+            ephemeral_other_public_share,
+        )?;
+        Ok(Signature { s, r })
+    }
+
+    pub fn compute_with_recid(
+        party_one_private: &Party1Private,
+        partial_sig_c3: Ciphertext,
+        ephemeral_local_share: &EphEcKeyPair,
+        ephemeral_other_public_share: &GE,
+    ) -> Result<SignatureRecid, Error> {
         //compute r = k2* R1
         let mut r = ephemeral_other_public_share.clone();
         r = r.scalar_mul(&ephemeral_local_share.secret_share.get_element());
 
-        let rx = r.x_coor().unwrap().mod_floor(&FE::q());
+        let point_at_infinity =
+            || Error::new(Cclst19Sign, "signature", PointAtInfinity).with_parties(vec![2]);
+        let rx = r
+            .x_coor()
+            .ok_or_else(point_at_infinity)?
+            .mod_floor(&FE::q());
+        let ry = r.y_coor().ok_or_else(point_at_infinity)?;
         let k1_inv = &ephemeral_local_share
             .secret_share
             .to_big_int()
@@ -379,11 +514,107 @@ impl Signature {
         let s_tag = party_one_private.keypair.decrypt(&partial_sig_c3);
         let s_tag_tag = BigInt::mod_mul(&k1_inv, &s_tag, &FE::q());
         let s = cmp::min(s_tag_tag.clone(), FE::q().clone() - s_tag_tag.clone());
-        Signature { s, r: rx }
+
+        // recid is the parity of R.y, flipped if s was negated above
+        let mut recid = if ry.tstbit(0) { 1 } else { 0 };
+        if s != s_tag_tag {
+            recid ^= 1;
+        }
+        Ok(SignatureRecid { s, r: rx, recid })
+    }
+}
+
+impl Signature {
+    /// DER encoding, as bitcoin transactions and most HSMs expect it.
+    pub fn to_der(&self) -> Vec<u8> {
+        signature_encoding::to_der(&self.r, &self.s)
+    }
+
+    pub fn from_der(bytes: &[u8]) -> Result<Signature, Error> {
+        let (r, s) = signature_encoding::from_der(bytes)?;
+        Ok(Signature { s, r })
+    }
+
+    /// 64 byte r || s.
+    pub fn to_compact(&self) -> [u8; COMPACT_SIGNATURE_SIZE] {
+        signature_encoding::to_compact(&self.r, &self.s)
+    }
+
+    pub fn from_compact(bytes: &[u8]) -> Result<Signature, Error> {
+        let (r, s) = signature_encoding::from_compact(bytes)?;
+        Ok(Signature { s, r })
+    }
+
+    pub fn to_libsecp256k1(&self) -> secp256k1::Signature {
+        signature_encoding::to_libsecp256k1(&self.r, &self.s)
+    }
+
+    pub fn from_libsecp256k1(signature: &secp256k1::Signature) -> Result<Signature, Error> {
+        let (r, s) = signature_encoding::from_libsecp256k1(signature)?;
+        Ok(Signature { s, r })
+    }
+}
+
+impl SignatureRecid {
+    /// DER encoding of r and s, the recovery id is not part of it.
+    pub fn to_der(&self) -> Vec<u8> {
+        signature_encoding::to_der(&self.r, &self.s)
+    }
+
+    pub fn from_der(bytes: &[u8], recid: u8) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_der(bytes)?;
+        Ok(SignatureRecid { s, r, recid })
+    }
+
+    /// 64 byte r || s, the recovery id is not part of it.
+    pub fn to_compact(&self) -> [u8; COMPACT_SIGNATURE_SIZE] {
+        signature_encoding::to_compact(&self.r, &self.s)
+    }
+
+    pub fn from_compact(bytes: &[u8], recid: u8) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_compact(bytes)?;
+        Ok(SignatureRecid { s, r, recid })
+    }
+
+    /// 65 byte r || s || recid.
+    pub fn to_recoverable(&self) -> [u8; RECOVERABLE_SIGNATURE_SIZE] {
+        signature_encoding::to_recoverable(&self.r, &self.s, self.recid)
+    }
+
+    pub fn from_recoverable(bytes: &[u8]) -> Result<SignatureRecid, Error> {
+        let (r, s, recid) = signature_encoding::from_recoverable(bytes)?;
+        Ok(SignatureRecid { s, r, recid })
+    }
+
+    pub fn to_libsecp256k1(&self) -> Result<(secp256k1::Signature, RecoveryId), Error> {
+        Ok((
+            signature_encoding::to_libsecp256k1(&self.r, &self.s),
+            signature_encoding::to_recovery_id(self.recid)?,
+        ))
+    }
+
+    pub fn from_libsecp256k1(
+        signature: &secp256k1::Signature,
+        recid: &RecoveryId,
+    ) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_libsecp256k1(signature)?;
+        Ok(SignatureRecid {
+            s,
+            r,
+            recid: recid.serialize(),
+        })
+    }
+
+    /// The public key the signature recovers to, the signing key when recid is correct.
+    pub fn recover_public_key(&self, message: &BigInt) -> Result<GE, Error> {
+        signature_encoding::recover_public_key(&self.r, &self.s, self.recid, message)
     }
 }
 
 pub fn verify(signature: &Signature, pubkey: &GE, message: &BigInt) -> Result<(), Error> {
+    if signature.s == BigInt::zero() || signature.r == BigInt::zero() {
+        return Err(Error::new(Cclst19Sign, "verify", InvalidSignature));
+    }
     let s_fe: FE = ECScalar::from(&signature.s);
     let rx_fe: FE = ECScalar::from(&signature.r);
 
@@ -394,13 +625,16 @@ pub fn verify(signature: &Signature, pubkey: &GE, message: &BigInt) -> Result<()
 
     // second condition is against malleability
     let rx_bytes = &BigInt::to_vec(&signature.r)[..];
-    let u1_plus_u2_bytes = &BigInt::to_vec(&(u1 + u2).x_coor().unwrap())[..];
+    let u1_plus_u2_x = (u1 + u2)
+        .x_coor()
+        .ok_or_else(|| Error::new(Cclst19Sign, "verify", InvalidSignature))?;
+    let u1_plus_u2_bytes = &BigInt::to_vec(&u1_plus_u2_x)[..];
 
     if rx_bytes.ct_eq(&u1_plus_u2_bytes).unwrap_u8() == 1
         && signature.s < FE::q() - signature.s.clone()
     {
         Ok(())
     } else {
-        Err(InvalidSig)
+        Err(Error::new(Cclst19Sign, "verify", InvalidSignature))
     }
 }
@@ -25,26 +25,52 @@ use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::cryptographic_primitives::proofs::sigma_dlog::*;
 use curv::cryptographic_primitives::proofs::sigma_ec_ddh::*;
-use curv::cryptographic_primitives::proofs::ProofError;
 use curv::elliptic::curves::traits::*;
 use curv::BigInt;
 use curv::FE;
 use curv::GE;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroize;
 
 use super::party_one::EphKeyGenFirstMsg as Party1EphKeyGenFirstMsg;
 use super::party_one::KeyGenFirstMsg as Party1KeyGenFirstMessage;
 use super::party_one::KeyGenSecondMsg as Party1KeyGenSecondMessage;
 use super::SECURITY_BITS;
+use crate::utilities::secret::Redacted;
+use crate::Error;
+use crate::ErrorKind::{InvalidClDlProof, InvalidCommitment, InvalidDLogProof, PointAtInfinity};
+use crate::Protocol::{Cclst19Keygen, Cclst19Sign};
 
 //****************** Begin: Party Two structs ******************//
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyGenFirstMsg {
     pub d_log_proof: DLogProof,
@@ -69,7 +95,27 @@ pub struct PartialSig {
 pub struct Party2Private {
     x2: FE,
 }
-#[derive(Debug)]
+
+impl fmt::Debug for Party2Private {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Party2Private")
+            .field("x2", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for Party2Private {
+    fn zeroize(&mut self) {
+        self.x2.zeroize();
+    }
+}
+
+impl Drop for Party2Private {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 pub struct PDLchallenge {
     pub c_tag: BigInt,
     pub c_tag_tag: BigInt,
@@ -79,6 +125,33 @@ pub struct PDLchallenge {
     q_tag: GE,
 }
 
+impl fmt::Debug for PDLchallenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PDLchallenge")
+            .field("c_tag", &self.c_tag)
+            .field("c_tag_tag", &self.c_tag_tag)
+            .field("a", &Redacted)
+            .field("b", &Redacted)
+            .field("blindness", &Redacted)
+            .field("q_tag", &self.q_tag)
+            .finish()
+    }
+}
+
+impl Zeroize for PDLchallenge {
+    fn zeroize(&mut self) {
+        self.a.zeroize_bn();
+        self.b.zeroize_bn();
+        self.blindness.zeroize_bn();
+    }
+}
+
+impl Drop for PDLchallenge {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PDLFirstMessage {
     pub c_tag: BigInt,
@@ -96,12 +169,33 @@ pub struct PDLdecommit {
 pub struct PDLSecondMessage {
     pub decommit: PDLdecommit,
 }
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EphEcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EphEcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EphEcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EphEcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EphEcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EphCommWitness {
     pub pk_commitment_blind_factor: BigInt,
@@ -165,7 +259,7 @@ impl KeyGenSecondMsg {
     pub fn verify_commitments_and_dlog_proof(
         party_one_first_message: &Party1KeyGenFirstMessage,
         party_one_second_message: &Party1KeyGenSecondMessage,
-    ) -> Result<KeyGenSecondMsg, ProofError> {
+    ) -> Result<KeyGenSecondMsg, Error> {
         let party_one_pk_commitment = &party_one_first_message.pk_commitment;
         let party_one_zk_pok_commitment = &party_one_first_message.zk_pok_commitment;
         let party_one_zk_pok_blind_factor =
@@ -195,8 +289,15 @@ impl KeyGenSecondMsg {
             false => flag = false,
             true => flag = flag,
         };
-        assert!(flag);
-        DLogProof::verify(&party_one_d_log_proof)?;
+        if !flag {
+            return Err(
+                Error::new(Cclst19Keygen, "second message", InvalidCommitment)
+                    .with_parties(vec![1]),
+            );
+        }
+        DLogProof::verify(&party_one_d_log_proof).map_err(|_| {
+            Error::new(Cclst19Keygen, "second message", InvalidDLogProof).with_parties(vec![1])
+        })?;
         Ok(KeyGenSecondMsg {})
     }
 }
@@ -224,14 +325,17 @@ impl Party2Private {
 }
 
 impl HSMCLPublic {
-    pub fn verify_zkcldl_proof(proof: CLDLProofPublicSetup) -> Result<Self, ()> {
+    pub fn verify_zkcldl_proof(proof: CLDLProofPublicSetup) -> Result<Self, Error> {
         let res = proof.verify();
         match res {
             Ok(_) => Ok(HSMCLPublic {
                 ek: proof.pk.clone(),
                 encrypted_secret_share: proof.ciphertext.clone(),
             }),
-            Err(_) => Err(()),
+            Err(_) => {
+                Err(Error::new(Cclst19Keygen, "cl-dl proof", InvalidClDlProof)
+                    .with_parties(vec![1]))
+            }
         }
     }
 }
@@ -295,14 +399,19 @@ impl EphKeyGenSecondMsg {
     pub fn verify_and_decommit(
         comm_witness: EphCommWitness,
         party_one_first_message: &Party1EphKeyGenFirstMsg,
-    ) -> Result<EphKeyGenSecondMsg, ProofError> {
+    ) -> Result<EphKeyGenSecondMsg, Error> {
         let delta = ECDDHStatement {
             g1: GE::generator(),
             h1: party_one_first_message.public_share.clone(),
             g2: GE::base_point2(),
             h2: party_one_first_message.c.clone(),
         };
-        party_one_first_message.d_log_proof.verify(&delta)?;
+        party_one_first_message
+            .d_log_proof
+            .verify(&delta)
+            .map_err(|_| {
+                Error::new(Cclst19Sign, "ephemeral keygen", InvalidDLogProof).with_parties(vec![1])
+            })?;
         Ok(EphKeyGenSecondMsg { comm_witness })
     }
 }
@@ -314,13 +423,18 @@ impl PartialSig {
         ephemeral_local_share: &EphEcKeyPair,
         ephemeral_other_public_share: &GE,
         message: &BigInt,
-    ) -> PartialSig {
+    ) -> Result<PartialSig, Error> {
         let q = FE::q();
         //compute r = k2* R1
         let mut r: GE = ephemeral_other_public_share.clone();
         r = r.scalar_mul(&ephemeral_local_share.secret_share.get_element());
 
-        let rx = r.x_coor().unwrap().mod_floor(&q);
+        let rx = r
+            .x_coor()
+            .ok_or_else(|| {
+                Error::new(Cclst19Sign, "partial signature", PointAtInfinity).with_parties(vec![1])
+            })?
+            .mod_floor(&q);
         let k2_inv = &ephemeral_local_share
             .secret_share
             .to_big_int()
@@ -335,6 +449,6 @@ impl PartialSig {
         let c3 = HSMCL::eval_sum(&c1, &c2);
 
         //c3:
-        PartialSig { c3 }
+        Ok(PartialSig { c3 })
     }
 }
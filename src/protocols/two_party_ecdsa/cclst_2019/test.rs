@@ -29,7 +29,8 @@ fn test_full_key_gen() {
     let (party_one_first_message, comm_witness, ec_key_pair_party1) =
         party_one::KeyGenFirstMsg::create_commitments_with_fixed_secret_share(ECScalar::from(
             &BigInt::sample(253),
-        ));
+        ))
+        .expect("secret share out of range");
     let (party_two_first_message, _ec_key_pair_party2) =
         party_two::KeyGenFirstMsg::create_with_fixed_secret_share(ECScalar::from(&BigInt::from(
             10,
@@ -117,16 +118,23 @@ fn test_two_party_sign() {
         &eph_ec_key_pair_party2,
         &eph_party_one_first_message.public_share,
         &message,
-    );
+    )
+    .expect("bad R1");
 
-    let signature = party_one::Signature::compute(
+    let signature = party_one::Signature::compute_with_recid(
         &party1_private,
         partial_sig.c3,
         &eph_ec_key_pair_party1,
         &eph_party_two_second_message.comm_witness.public_share,
-    );
+    )
+    .expect("bad R2");
 
     let pubkey =
         party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
+    assert_eq!(signature.recover_public_key(&message).unwrap(), pubkey);
+    let signature = party_one::Signature {
+        r: signature.r,
+        s: signature.s,
+    };
     party_one::verify(&signature, &pubkey, &message).expect("Invalid signature")
 }
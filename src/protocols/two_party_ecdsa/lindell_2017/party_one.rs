@@ -14,6 +14,7 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 use std::cmp;
+use std::fmt;
 
 use centipede::juggling::proof_system::{Helgamalsegmented, Witness};
 use centipede::juggling::segmentation::Msegmentation;
@@ -24,14 +25,14 @@ use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::cryptographic_primitives::proofs::sigma_dlog::*;
 use curv::cryptographic_primitives::proofs::sigma_ec_ddh::*;
-use curv::cryptographic_primitives::proofs::ProofError;
 use curv::elliptic::curves::traits::*;
 use curv::BigInt;
 use curv::FE;
 use curv::GE;
 use paillier::Paillier;
-use paillier::{Decrypt, EncryptWithChosenRandomness, KeyGeneration};
+use paillier::{Decrypt, EncryptWithChosenRandomness};
 use paillier::{DecryptionKey, EncryptionKey, Randomness, RawCiphertext, RawPlaintext};
+use secp256k1::RecoveryId;
 use serde::{Deserialize, Serialize};
 use subtle::ConstantTimeEq;
 use zeroize::Zeroize;
@@ -39,10 +40,24 @@ use zk_paillier::zkproofs::{NICorrectKeyProof, RangeProofNi};
 
 use super::party_two::EphKeyGenFirstMsg as Party2EphKeyGenFirstMessage;
 use super::party_two::EphKeyGenSecondMsg as Party2EphKeyGenSecondMessage;
+use super::party_two::RingPedersenMsg as Party2RingPedersenMsg;
 use super::SECURITY_BITS;
 
+use crate::utilities::backup::EncryptedBackup;
+use crate::utilities::bip32::{derive_path, ChildKey};
+use crate::utilities::hedged_nonce::hedged_nonce;
 use crate::utilities::mta::MessageB;
+use crate::utilities::secret::{zeroize_decryption_key, Redacted};
+use crate::utilities::signature_encoding::{
+    self, COMPACT_SIGNATURE_SIZE, RECOVERABLE_SIGNATURE_SIZE,
+};
+use crate::utilities::zk_no_small_factor::NoSmallFactorProof;
+use crate::utilities::zk_paillier_blum_modulus::{generate_blum_keypair, PaillierBlumModulusProof};
 use crate::Error;
+use crate::ErrorKind::{
+    InvalidCommitment, InvalidDLogProof, InvalidSecretShare, InvalidSignature, PointAtInfinity,
+};
+use crate::Protocol::{Lindell17Keygen, Lindell17Sign};
 
 use crate::utilities::zk_pdl::PDLStatement;
 use crate::utilities::zk_pdl::PDLWitness;
@@ -50,12 +65,33 @@ use crate::utilities::zk_pdl::Prover as PDLProver;
 use crate::utilities::zk_pdl::*;
 
 //****************** Begin: Party One structs ******************//
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommWitness {
     pub pk_commitment_blind_factor: BigInt,
@@ -75,7 +111,7 @@ pub struct KeyGenSecondMsg {
     pub comm_witness: CommWitness,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct PaillierKeyPair {
     pub ek: EncryptionKey,
     dk: DecryptionKey,
@@ -83,6 +119,30 @@ pub struct PaillierKeyPair {
     randomness: BigInt,
 }
 
+impl fmt::Debug for PaillierKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PaillierKeyPair")
+            .field("ek", &self.ek)
+            .field("dk", &Redacted)
+            .field("encrypted_share", &self.encrypted_share)
+            .field("randomness", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for PaillierKeyPair {
+    fn zeroize(&mut self) {
+        zeroize_decryption_key(&mut self.dk);
+        self.randomness.zeroize_bn();
+    }
+}
+
+impl Drop for PaillierKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignatureRecid {
     pub s: BigInt,
@@ -103,6 +163,30 @@ pub struct Party1Private {
     c_key_randomness: BigInt,
 }
 
+impl fmt::Debug for Party1Private {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Party1Private")
+            .field("x1", &Redacted)
+            .field("paillier_priv", &Redacted)
+            .field("c_key_randomness", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for Party1Private {
+    fn zeroize(&mut self) {
+        self.x1.zeroize();
+        zeroize_decryption_key(&mut self.paillier_priv);
+        self.c_key_randomness.zeroize_bn();
+    }
+}
+
+impl Drop for Party1Private {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PDLFirstMessage {
     pub c_hat: BigInt,
@@ -119,12 +203,33 @@ pub struct PDLSecondMessage {
     pub decommit: PDLdecommit,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EphEcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EphEcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EphEcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EphEcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EphEcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EphKeyGenFirstMsg {
     pub d_log_proof: ECDDHProof,
@@ -185,11 +290,20 @@ impl KeyGenFirstMsg {
 
     pub fn create_commitments_with_fixed_secret_share(
         mut secret_share: FE,
-    ) -> (KeyGenFirstMsg, CommWitness, EcKeyPair) {
+    ) -> Result<(KeyGenFirstMsg, CommWitness, EcKeyPair), Error> {
         //in Lindell's protocol range proof works only for x1<q/3
-        let sk_bigint = secret_share.to_big_int();
+        let mut sk_bigint = secret_share.to_big_int();
         let q_third = FE::q();
-        assert!(sk_bigint < q_third.div_floor(&BigInt::from(3)));
+        let in_range = sk_bigint < q_third.div_floor(&BigInt::from(3));
+        sk_bigint.zeroize_bn();
+        if !in_range {
+            secret_share.zeroize();
+            return Err(Error::new(
+                Lindell17Keygen,
+                "first message",
+                InvalidSecretShare,
+            ));
+        }
         let base: GE = ECPoint::generator();
         let public_share = base.scalar_mul(&secret_share.get_element());
 
@@ -214,7 +328,7 @@ impl KeyGenFirstMsg {
             secret_share,
         };
         secret_share.zeroize();
-        (
+        Ok((
             KeyGenFirstMsg {
                 pk_commitment,
                 zk_pok_commitment,
@@ -226,7 +340,7 @@ impl KeyGenFirstMsg {
                 d_log_proof,
             },
             ec_key_pair,
-        )
+        ))
     }
 }
 
@@ -234,8 +348,10 @@ impl KeyGenSecondMsg {
     pub fn verify_and_decommit(
         comm_witness: CommWitness,
         proof: &DLogProof,
-    ) -> Result<KeyGenSecondMsg, ProofError> {
-        DLogProof::verify(proof)?;
+    ) -> Result<KeyGenSecondMsg, Error> {
+        DLogProof::verify(proof).map_err(|_| {
+            Error::new(Lindell17Keygen, "first message", InvalidDLogProof).with_parties(vec![2])
+        })?;
         Ok(KeyGenSecondMsg { comm_witness })
     }
 }
@@ -244,6 +360,21 @@ pub fn compute_pubkey(party_one_private: &Party1Private, other_share_public_shar
     other_share_public_share * &party_one_private.x1
 }
 
+impl EcKeyPair {
+    /// Restores the key pair of public_share from a backup made with Party1Private::backup.
+    pub fn restore(
+        backup: &EncryptedBackup,
+        public_share: &GE,
+        backup_private_key: &FE,
+    ) -> Result<EcKeyPair, Error> {
+        let secret_share = backup.restore(public_share, backup_private_key)?;
+        Ok(EcKeyPair {
+            public_share: *public_share,
+            secret_share,
+        })
+    }
+}
+
 impl Party1Private {
     pub fn set_private_key(ec_key: &EcKeyPair, paillier_key: &PaillierKeyPair) -> Party1Private {
         Party1Private {
@@ -252,17 +383,29 @@ impl Party1Private {
             c_key_randomness: paillier_key.randomness.clone(),
         }
     }
+
+    /// Multiplies x1 by factor and encrypts it under a new paillier key. The new key comes
+    /// with the keygen proofs, the no small factor proof is made against the ring-Pedersen
+    /// parameters of party two, which are verified first. Party two checks the proofs with
+    /// the PaillierPublic verify functions before using the new key.
     pub fn refresh_private_key(
         party_one_private: &Party1Private,
         factor: &BigInt,
-    ) -> (
-        EncryptionKey,
-        BigInt,
-        Party1Private,
-        NICorrectKeyProof,
-        RangeProofNi,
-    ) {
-        let (ek_new, dk_new) = Paillier::keypair().keys();
+        party_two_ring_pedersen: &Party2RingPedersenMsg,
+    ) -> Result<
+        (
+            EncryptionKey,
+            BigInt,
+            Party1Private,
+            NICorrectKeyProof,
+            PaillierBlumModulusProof,
+            NoSmallFactorProof,
+            RangeProofNi,
+        ),
+        Error,
+    > {
+        party_two_ring_pedersen.verify()?;
+        let (ek_new, dk_new) = generate_blum_keypair();
         let randomness = Randomness::sample(&ek_new);
         let factor_fe: FE = ECScalar::from(&factor);
         let x1_new = party_one_private.x1 * factor_fe;
@@ -275,6 +418,9 @@ impl Party1Private {
         .0
         .into_owned();
         let correct_key_proof_new = NICorrectKeyProof::proof(&dk_new);
+        let blum_modulus_proof_new = PaillierBlumModulusProof::prove(&dk_new);
+        let no_small_factor_proof_new =
+            NoSmallFactorProof::prove(&dk_new, &party_two_ring_pedersen.dlog_statement);
 
         let range_proof_new = RangeProofNi::prove(
             &ek_new,
@@ -290,13 +436,36 @@ impl Party1Private {
             c_key_randomness: randomness.0,
         };
 
-        (
+        Ok((
             ek_new,
             c_key_new,
             party_one_private_new,
             correct_key_proof_new,
+            blum_modulus_proof_new,
+            no_small_factor_proof_new,
             range_proof_new,
-        )
+        ))
+    }
+
+    /// Non-hardened BIP32 child of the joint public key at path. The child secret key is
+    /// x1 * x2 + tweak, party two applies the tweak to the encryption of x1 (see
+    /// Party2Private::derive_child) and keeps the new ciphertext to itself. Signature::compute
+    /// is unchanged, signatures made with the new ciphertext verify under the returned
+    /// public key. x1 itself stays the share of the parent key.
+    pub fn derive_child(
+        &self,
+        other_share_public_share: &GE,
+        chain_code: &[u8; 32],
+        path: &[u32],
+    ) -> Result<ChildKey, Error> {
+        let y = compute_pubkey(self, other_share_public_share);
+        derive_path(&y, chain_code, path)
+    }
+
+    /// Verifiable encrypted backup of x1 to backup_public_key. Anyone can check it with
+    /// EncryptedBackup::verify against the public share x1 * G, EcKeyPair::restore decrypts it.
+    pub fn backup(&self, backup_public_key: &GE) -> EncryptedBackup {
+        EncryptedBackup::create(&self.x1, backup_public_key)
     }
 
     // used for verifiable recovery
@@ -318,7 +487,7 @@ impl Party1Private {
 
 impl PaillierKeyPair {
     pub fn generate_keypair_and_encrypted_share(keygen: &EcKeyPair) -> PaillierKeyPair {
-        let (ek, dk) = Paillier::keypair().keys();
+        let (ek, dk) = generate_blum_keypair();
         let randomness = Randomness::sample(&ek);
 
         let encrypted_share = Paillier::encrypt_with_chosen_randomness(
@@ -377,6 +546,23 @@ impl PaillierKeyPair {
         NICorrectKeyProof::proof(&paillier_context.dk)
     }
 
+    pub fn generate_paillier_blum_modulus_proof(
+        paillier_context: &PaillierKeyPair,
+    ) -> PaillierBlumModulusProof {
+        PaillierBlumModulusProof::prove(&paillier_context.dk)
+    }
+
+    pub fn generate_no_small_factor_proof(
+        paillier_context: &PaillierKeyPair,
+        party_two_ring_pedersen: &Party2RingPedersenMsg,
+    ) -> Result<NoSmallFactorProof, Error> {
+        party_two_ring_pedersen.verify()?;
+        Ok(NoSmallFactorProof::prove(
+            &paillier_context.dk,
+            &party_two_ring_pedersen.dlog_statement,
+        ))
+    }
+
     pub fn pdl_first_message(
         party_one_private: &Party1Private,
         verifier_first_messsage: &PDLVerifierFirstMessage,
@@ -408,7 +594,7 @@ impl PaillierKeyPair {
         verifier_second_message: &PDLVerifierSecondMessage,
         wintess: &PDLWitness,
         prover_state: &PDLProverState,
-    ) -> Result<PDLProverSecondMessage, ()> {
+    ) -> Result<PDLProverSecondMessage, Error> {
         PDLProver::message2(
             verifier_first_message,
             verifier_second_message,
@@ -420,8 +606,21 @@ impl PaillierKeyPair {
 
 impl EphKeyGenFirstMsg {
     pub fn create() -> (EphKeyGenFirstMsg, EphEcKeyPair) {
+        Self::create_with_secret_share(ECScalar::new_random())
+    }
+
+    /// As create, but the ephemeral secret is a hedged nonce derived from fresh randomness,
+    /// x1, the message digest and session_id, which must be unique per signing session.
+    pub fn create_hedged(
+        private: &Party1Private,
+        message: &BigInt,
+        session_id: &[u8],
+    ) -> (EphKeyGenFirstMsg, EphEcKeyPair) {
+        Self::create_with_secret_share(hedged_nonce(&private.x1, message, session_id, b"k1"))
+    }
+
+    fn create_with_secret_share(mut secret_share: FE) -> (EphKeyGenFirstMsg, EphEcKeyPair) {
         let base: GE = ECPoint::generator();
-        let mut secret_share: FE = ECScalar::new_random();
         let public_share = &base * &secret_share;
         let h: GE = GE::base_point2();
 
@@ -456,7 +655,7 @@ impl EphKeyGenSecondMsg {
     pub fn verify_commitments_and_dlog_proof(
         party_two_first_message: &Party2EphKeyGenFirstMessage,
         party_two_second_message: &Party2EphKeyGenSecondMessage,
-    ) -> Result<EphKeyGenSecondMsg, ProofError> {
+    ) -> Result<EphKeyGenSecondMsg, Error> {
         let party_two_pk_commitment = &party_two_first_message.pk_commitment;
         let party_two_zk_pok_commitment = &party_two_first_message.zk_pok_commitment;
         let party_two_zk_pok_blind_factor =
@@ -491,14 +690,21 @@ impl EphKeyGenSecondMsg {
         } else {
             flag = false
         };
-        assert!(flag);
+        if !flag {
+            return Err(
+                Error::new(Lindell17Sign, "ephemeral keygen", InvalidCommitment)
+                    .with_parties(vec![2]),
+            );
+        }
         let delta = ECDDHStatement {
             g1: GE::generator(),
             h1: *party_two_public_share,
             g2: GE::base_point2(),
             h2: party_two_second_message.comm_witness.c,
         };
-        party_two_d_log_proof.verify(&delta)?;
+        party_two_d_log_proof.verify(&delta).map_err(|_| {
+            Error::new(Lindell17Sign, "ephemeral keygen", InvalidDLogProof).with_parties(vec![2])
+        })?;
         Ok(EphKeyGenSecondMsg {})
     }
 }
@@ -509,12 +715,15 @@ impl Signature {
         partial_sig_c3: &BigInt,
         ephemeral_local_share: &EphEcKeyPair,
         ephemeral_other_public_share: &GE,
-    ) -> Signature {
+    ) -> Result<Signature, Error> {
         //compute r = k2* R1
         let r = ephemeral_other_public_share
             .scalar_mul(&ephemeral_local_share.secret_share.get_element());
 
-        let rx = r.x_coor().unwrap().mod_floor(&FE::q());
+        let rx = r
+            .x_coor()
+            .ok_or_else(point_at_infinity)?
+            .mod_floor(&FE::q());
 
         let mut k1_inv = ephemeral_local_share.secret_share.invert();
 
@@ -531,7 +740,7 @@ impl Signature {
 
         let s = cmp::min(s_tag_tag_bn.clone(), FE::q().clone() - s_tag_tag_bn.clone());
 
-        Signature { s, r: rx }
+        Ok(Signature { s, r: rx })
     }
 
     pub fn compute_with_recid(
@@ -539,13 +748,19 @@ impl Signature {
         partial_sig_c3: &BigInt,
         ephemeral_local_share: &EphEcKeyPair,
         ephemeral_other_public_share: &GE,
-    ) -> SignatureRecid {
+    ) -> Result<SignatureRecid, Error> {
         //compute r = k2* R1
         let r = ephemeral_other_public_share
             .scalar_mul(&ephemeral_local_share.secret_share.get_element());
 
-        let rx = r.x_coor().unwrap().mod_floor(&FE::q());
-        let ry = r.y_coor().unwrap().mod_floor(&FE::q());
+        let rx = r
+            .x_coor()
+            .ok_or_else(point_at_infinity)?
+            .mod_floor(&FE::q());
+        let ry = r
+            .y_coor()
+            .ok_or_else(point_at_infinity)?
+            .mod_floor(&FE::q());
         let mut k1_inv = ephemeral_local_share.secret_share.invert();
 
         let s_tag = Paillier::decrypt(
@@ -572,11 +787,106 @@ impl Signature {
             recid = recid ^ 1;
         }
 
-        SignatureRecid { s, r: rx, recid }
+        Ok(SignatureRecid { s, r: rx, recid })
+    }
+}
+
+// r = k1 * R2 can only be the point at infinity if party two sent a bad R2
+fn point_at_infinity() -> Error {
+    Error::new(Lindell17Sign, "signature", PointAtInfinity).with_parties(vec![2])
+}
+
+impl Signature {
+    /// DER encoding, as bitcoin transactions and most HSMs expect it.
+    pub fn to_der(&self) -> Vec<u8> {
+        signature_encoding::to_der(&self.r, &self.s)
+    }
+
+    pub fn from_der(bytes: &[u8]) -> Result<Signature, Error> {
+        let (r, s) = signature_encoding::from_der(bytes)?;
+        Ok(Signature { s, r })
+    }
+
+    /// 64 byte r || s.
+    pub fn to_compact(&self) -> [u8; COMPACT_SIGNATURE_SIZE] {
+        signature_encoding::to_compact(&self.r, &self.s)
+    }
+
+    pub fn from_compact(bytes: &[u8]) -> Result<Signature, Error> {
+        let (r, s) = signature_encoding::from_compact(bytes)?;
+        Ok(Signature { s, r })
+    }
+
+    pub fn to_libsecp256k1(&self) -> secp256k1::Signature {
+        signature_encoding::to_libsecp256k1(&self.r, &self.s)
+    }
+
+    pub fn from_libsecp256k1(signature: &secp256k1::Signature) -> Result<Signature, Error> {
+        let (r, s) = signature_encoding::from_libsecp256k1(signature)?;
+        Ok(Signature { s, r })
+    }
+}
+
+impl SignatureRecid {
+    /// DER encoding of r and s, the recovery id is not part of it.
+    pub fn to_der(&self) -> Vec<u8> {
+        signature_encoding::to_der(&self.r, &self.s)
+    }
+
+    pub fn from_der(bytes: &[u8], recid: u8) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_der(bytes)?;
+        Ok(SignatureRecid { s, r, recid })
+    }
+
+    /// 64 byte r || s, the recovery id is not part of it.
+    pub fn to_compact(&self) -> [u8; COMPACT_SIGNATURE_SIZE] {
+        signature_encoding::to_compact(&self.r, &self.s)
+    }
+
+    pub fn from_compact(bytes: &[u8], recid: u8) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_compact(bytes)?;
+        Ok(SignatureRecid { s, r, recid })
+    }
+
+    /// 65 byte r || s || recid.
+    pub fn to_recoverable(&self) -> [u8; RECOVERABLE_SIGNATURE_SIZE] {
+        signature_encoding::to_recoverable(&self.r, &self.s, self.recid)
+    }
+
+    pub fn from_recoverable(bytes: &[u8]) -> Result<SignatureRecid, Error> {
+        let (r, s, recid) = signature_encoding::from_recoverable(bytes)?;
+        Ok(SignatureRecid { s, r, recid })
+    }
+
+    pub fn to_libsecp256k1(&self) -> Result<(secp256k1::Signature, RecoveryId), Error> {
+        Ok((
+            signature_encoding::to_libsecp256k1(&self.r, &self.s),
+            signature_encoding::to_recovery_id(self.recid)?,
+        ))
+    }
+
+    pub fn from_libsecp256k1(
+        signature: &secp256k1::Signature,
+        recid: &RecoveryId,
+    ) -> Result<SignatureRecid, Error> {
+        let (r, s) = signature_encoding::from_libsecp256k1(signature)?;
+        Ok(SignatureRecid {
+            s,
+            r,
+            recid: recid.serialize(),
+        })
+    }
+
+    /// The public key the signature recovers to, the signing key when recid is correct.
+    pub fn recover_public_key(&self, message: &BigInt) -> Result<GE, Error> {
+        signature_encoding::recover_public_key(&self.r, &self.s, self.recid, message)
     }
 }
 
 pub fn verify(signature: &Signature, pubkey: &GE, message: &BigInt) -> Result<(), Error> {
+    if signature.s == BigInt::zero() || signature.r == BigInt::zero() {
+        return Err(Error::new(Lindell17Sign, "verify", InvalidSignature));
+    }
     let s_fe: FE = ECScalar::from(&signature.s);
     let rx_fe: FE = ECScalar::from(&signature.r);
 
@@ -587,13 +897,16 @@ pub fn verify(signature: &Signature, pubkey: &GE, message: &BigInt) -> Result<()
 
     // second condition is against malleability
     let rx_bytes = &BigInt::to_vec(&signature.r)[..];
-    let u1_plus_u2_bytes = &BigInt::to_vec(&(u1 + u2).x_coor().unwrap())[..];
+    let u1_plus_u2_x = (u1 + u2)
+        .x_coor()
+        .ok_or_else(|| Error::new(Lindell17Sign, "verify", InvalidSignature))?;
+    let u1_plus_u2_bytes = &BigInt::to_vec(&u1_plus_u2_x)[..];
 
     if rx_bytes.ct_eq(&u1_plus_u2_bytes).unwrap_u8() == 1
         && signature.s < FE::q() - signature.s.clone()
     {
         Ok(())
     } else {
-        Err(Error::InvalidSig)
+        Err(Error::new(Lindell17Sign, "verify", InvalidSignature))
     }
 }
@@ -23,7 +23,6 @@ use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::cryptographic_primitives::proofs::sigma_dlog::*;
 use curv::cryptographic_primitives::proofs::sigma_ec_ddh::*;
-use curv::cryptographic_primitives::proofs::ProofError;
 use curv::elliptic::curves::traits::*;
 use curv::BigInt;
 use curv::FE;
@@ -32,27 +31,64 @@ use paillier::Paillier;
 use paillier::{Add, Encrypt, Mul};
 use paillier::{EncryptionKey, RawCiphertext, RawPlaintext};
 use serde::{Deserialize, Serialize};
-use zk_paillier::zkproofs::{CorrectKeyProofError, NICorrectKeyProof};
+use std::fmt;
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NICorrectKeyProof};
 
 use super::party_one::EphKeyGenFirstMsg as Party1EphKeyGenFirstMsg;
 use super::party_one::KeyGenFirstMsg as Party1KeyGenFirstMessage;
 use super::party_one::KeyGenSecondMsg as Party1KeyGenSecondMessage;
 use super::SECURITY_BITS;
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::generate_ring_pedersen_parameters;
+use crate::utilities::backup::EncryptedBackup;
+use crate::utilities::bip32::{derive_path, ChildKey};
+use crate::utilities::hedged_nonce::hedged_nonce;
 use crate::utilities::mta::{MessageA, MessageB};
+use crate::utilities::ring_pedersen;
+use crate::utilities::secret::Redacted;
+use crate::utilities::zk_no_small_factor::NoSmallFactorProof;
+use crate::utilities::zk_paillier_blum_modulus::PaillierBlumModulusProof;
 use crate::utilities::zk_pdl::PDLStatement;
 use crate::utilities::zk_pdl::Verifier as PDLVerifier;
 use crate::utilities::zk_pdl::*;
+use crate::Error;
+use crate::ErrorKind::{
+    InvalidCommitment, InvalidCompositeDLogProof, InvalidCorrectKeyProof, InvalidDLogProof,
+    InvalidNoSmallFactorProof, InvalidPaillierBlumModulusProof, PaillierModulusTooSmall,
+    PointAtInfinity,
+};
+use crate::Protocol::{Lindell17Keygen, Lindell17Sign};
 
 use zeroize::Zeroize;
 const PAILLIER_KEY_SIZE: usize = 2048;
 //****************** Begin: Party Two structs ******************//
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyGenFirstMsg {
     pub d_log_proof: DLogProof,
@@ -68,6 +104,15 @@ pub struct PaillierPublic {
     pub encrypted_secret_share: BigInt,
 }
 
+/// Ring-Pedersen parameters of party two, for the no small factor proof of party one's
+/// paillier key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RingPedersenMsg {
+    pub dlog_statement: DLogStatement,
+    pub composite_dlog_proof_base_h1: CompositeDLogProof,
+    pub composite_dlog_proof_base_h2: CompositeDLogProof,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PartialSig {
     pub c3: BigInt,
@@ -77,7 +122,27 @@ pub struct PartialSig {
 pub struct Party2Private {
     x2: FE,
 }
-#[derive(Debug)]
+
+impl fmt::Debug for Party2Private {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Party2Private")
+            .field("x2", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for Party2Private {
+    fn zeroize(&mut self) {
+        self.x2.zeroize();
+    }
+}
+
+impl Drop for Party2Private {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 pub struct PDLchallenge {
     pub c_tag: BigInt,
     pub c_tag_tag: BigInt,
@@ -87,6 +152,33 @@ pub struct PDLchallenge {
     q_tag: GE,
 }
 
+impl fmt::Debug for PDLchallenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PDLchallenge")
+            .field("c_tag", &self.c_tag)
+            .field("c_tag_tag", &self.c_tag_tag)
+            .field("a", &Redacted)
+            .field("b", &Redacted)
+            .field("blindness", &Redacted)
+            .field("q_tag", &self.q_tag)
+            .finish()
+    }
+}
+
+impl Zeroize for PDLchallenge {
+    fn zeroize(&mut self) {
+        self.a.zeroize_bn();
+        self.b.zeroize_bn();
+        self.blindness.zeroize_bn();
+    }
+}
+
+impl Drop for PDLchallenge {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PDLFirstMessage {
     pub c_tag: BigInt,
@@ -104,12 +196,33 @@ pub struct PDLdecommit {
 pub struct PDLSecondMessage {
     pub decommit: PDLdecommit,
 }
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EphEcKeyPair {
     pub public_share: GE,
     secret_share: FE,
 }
 
+impl fmt::Debug for EphEcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EphEcKeyPair")
+            .field("public_share", &self.public_share)
+            .field("secret_share", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for EphEcKeyPair {
+    fn zeroize(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl Drop for EphEcKeyPair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EphCommWitness {
     pub pk_commitment_blind_factor: BigInt,
@@ -175,7 +288,7 @@ impl KeyGenSecondMsg {
     pub fn verify_commitments_and_dlog_proof(
         party_one_first_message: &Party1KeyGenFirstMessage,
         party_one_second_message: &Party1KeyGenSecondMessage,
-    ) -> Result<KeyGenSecondMsg, ProofError> {
+    ) -> Result<KeyGenSecondMsg, Error> {
         let party_one_pk_commitment = &party_one_first_message.pk_commitment;
         let party_one_zk_pok_commitment = &party_one_first_message.zk_pok_commitment;
         let party_one_zk_pok_blind_factor =
@@ -209,8 +322,15 @@ impl KeyGenSecondMsg {
         } else {
             flag = false
         };
-        assert!(flag);
-        DLogProof::verify(&party_one_d_log_proof)?;
+        if !flag {
+            return Err(
+                Error::new(Lindell17Keygen, "second message", InvalidCommitment)
+                    .with_parties(vec![1]),
+            );
+        }
+        DLogProof::verify(&party_one_d_log_proof).map_err(|_| {
+            Error::new(Lindell17Keygen, "second message", InvalidDLogProof).with_parties(vec![1])
+        })?;
         Ok(KeyGenSecondMsg {})
     }
 }
@@ -220,6 +340,21 @@ pub fn compute_pubkey(local_share: &EcKeyPair, other_share_public_share: &GE) ->
     pubkey.scalar_mul(&local_share.secret_share.get_element())
 }
 
+impl EcKeyPair {
+    /// Restores the key pair of public_share from a backup made with Party2Private::backup.
+    pub fn restore(
+        backup: &EncryptedBackup,
+        public_share: &GE,
+        backup_private_key: &FE,
+    ) -> Result<EcKeyPair, Error> {
+        let secret_share = backup.restore(public_share, backup_private_key)?;
+        Ok(EcKeyPair {
+            public_share: *public_share,
+            secret_share,
+        })
+    }
+}
+
 impl Party2Private {
     pub fn set_private_key(ec_key: &EcKeyPair) -> Party2Private {
         Party2Private {
@@ -234,6 +369,46 @@ impl Party2Private {
         }
     }
 
+    /// Non-hardened BIP32 child of the joint public key at path. x2 is kept and the tweak
+    /// divided by x2 is added homomorphically to party one's encrypted share, so the returned
+    /// encryption is of a share x1' with x1' * x2 = x1 * x2 + tweak. PartialSig::compute with it
+    /// gives signatures under the child public key. The new ciphertext must not be sent to
+    /// party one: decrypting it would reveal tweak / x2 and hence x2.
+    pub fn derive_child(
+        &self,
+        paillier_public: &PaillierPublic,
+        other_share_public_share: &GE,
+        chain_code: &[u8; 32],
+        path: &[u32],
+    ) -> Result<(PaillierPublic, ChildKey), Error> {
+        let y = other_share_public_share * &self.x2;
+        let child = derive_path(&y, chain_code, path)?;
+        let offset = BigInt::mod_mul(
+            &child.tweak.to_big_int(),
+            &self.x2.invert().to_big_int(),
+            &FE::q(),
+        );
+        let c_offset = Paillier::encrypt(&paillier_public.ek, RawPlaintext::from(offset));
+        let encrypted_secret_share = Paillier::add(
+            &paillier_public.ek,
+            RawCiphertext::from(paillier_public.encrypted_secret_share.clone()),
+            c_offset,
+        )
+        .0
+        .into_owned();
+        let paillier_public = PaillierPublic {
+            ek: paillier_public.ek.clone(),
+            encrypted_secret_share,
+        };
+        Ok((paillier_public, child))
+    }
+
+    /// Verifiable encrypted backup of x2 to backup_public_key. Anyone can check it with
+    /// EncryptedBackup::verify against the public share x2 * G, EcKeyPair::restore decrypts it.
+    pub fn backup(&self, backup_public_key: &GE) -> EncryptedBackup {
+        EncryptedBackup::create(&self.x2, backup_public_key)
+    }
+
     // used for verifiable recovery
     pub fn to_encrypted_segment(
         &self,
@@ -274,7 +449,7 @@ impl PaillierPublic {
         prover_first_message: &PDLProverFirstMessage,
         statement: &PDLStatement,
         state: &mut PDLVerifierState,
-    ) -> Result<PDLVerifierSecondMessage, ()> {
+    ) -> Result<PDLVerifierSecondMessage, Error> {
         PDLVerifier::message2(prover_first_message, statement, state)
     }
 
@@ -282,27 +457,111 @@ impl PaillierPublic {
         prover_first_message: &PDLProverFirstMessage,
         prover_second_messasge: &PDLProverSecondMessage,
         state: &PDLVerifierState,
-    ) -> Result<(), ()> {
+    ) -> Result<(), Error> {
         PDLVerifier::finalize(prover_first_message, prover_second_messasge, state)
     }
 
     pub fn verify_ni_proof_correct_key(
         proof: NICorrectKeyProof,
         ek: &EncryptionKey,
-    ) -> Result<(), CorrectKeyProofError> {
-        //
+    ) -> Result<(), Error> {
+        let err = Error::new(Lindell17Keygen, "paillier key", InvalidCorrectKeyProof)
+            .with_parties(vec![1]);
+        if ek.n.bit_length() < PAILLIER_KEY_SIZE - 1 {
+            return Err(err);
+        };
+        proof.verify(&ek).map_err(|_| err)
+    }
+
+    pub fn verify_paillier_blum_modulus_proof(
+        proof: &PaillierBlumModulusProof,
+        ek: &EncryptionKey,
+    ) -> Result<(), Error> {
         if ek.n.bit_length() < PAILLIER_KEY_SIZE - 1 {
-            return Err(CorrectKeyProofError);
+            return Err(
+                Error::new(Lindell17Keygen, "paillier key", PaillierModulusTooSmall)
+                    .with_parties(vec![1]),
+            );
         };
-        proof.verify(&ek)
+        proof.verify(ek).map_err(|_| {
+            Error::new(
+                Lindell17Keygen,
+                "paillier key",
+                InvalidPaillierBlumModulusProof,
+            )
+            .with_parties(vec![1])
+        })
+    }
+
+    pub fn verify_no_small_factor_proof(
+        proof: &NoSmallFactorProof,
+        ek: &EncryptionKey,
+        ring_pedersen: &RingPedersenMsg,
+    ) -> Result<(), Error> {
+        proof
+            .verify(ek, &ring_pedersen.dlog_statement)
+            .map_err(|_| {
+                Error::new(Lindell17Keygen, "paillier key", InvalidNoSmallFactorProof)
+                    .with_parties(vec![1])
+            })
+    }
+}
+
+impl RingPedersenMsg {
+    pub fn create() -> RingPedersenMsg {
+        let (n_tilde, h1, h2, xhi, xhi_inv) = generate_ring_pedersen_parameters();
+        let dlog_statement = DLogStatement {
+            N: n_tilde,
+            g: h1,
+            ni: h2,
+        };
+        let (composite_dlog_proof_base_h1, composite_dlog_proof_base_h2) =
+            ring_pedersen::prove(&dlog_statement, &xhi, &xhi_inv);
+        RingPedersenMsg {
+            dlog_statement,
+            composite_dlog_proof_base_h1,
+            composite_dlog_proof_base_h2,
+        }
+    }
+
+    /// Checked by party one before proving anything against these parameters.
+    pub fn verify(&self) -> Result<(), Error> {
+        if ring_pedersen::verify(
+            &self.dlog_statement,
+            &self.composite_dlog_proof_base_h1,
+            &self.composite_dlog_proof_base_h2,
+        ) {
+            Ok(())
+        } else {
+            Err(
+                Error::new(Lindell17Keygen, "ring pedersen", InvalidCompositeDLogProof)
+                    .with_parties(vec![2]),
+            )
+        }
     }
 }
 
 impl EphKeyGenFirstMsg {
     pub fn create_commitments() -> (EphKeyGenFirstMsg, EphCommWitness, EphEcKeyPair) {
-        let base: GE = ECPoint::generator();
+        Self::create_commitments_with_secret_share(ECScalar::new_random())
+    }
 
-        let mut secret_share: FE = ECScalar::new_random();
+    /// As create_commitments, but the ephemeral secret is a hedged nonce derived from fresh
+    /// randomness, x2, the message digest and session_id, which must be unique per signing
+    /// session.
+    pub fn create_commitments_hedged(
+        private: &Party2Private,
+        message: &BigInt,
+        session_id: &[u8],
+    ) -> (EphKeyGenFirstMsg, EphCommWitness, EphEcKeyPair) {
+        let secret_share = hedged_nonce(&private.x2, message, session_id, b"k2");
+        Self::create_commitments_with_secret_share(secret_share)
+    }
+
+    fn create_commitments_with_secret_share(
+        mut secret_share: FE,
+    ) -> (EphKeyGenFirstMsg, EphCommWitness, EphEcKeyPair) {
+        let base: GE = ECPoint::generator();
 
         let public_share = base.scalar_mul(&secret_share.get_element());
 
@@ -359,14 +618,20 @@ impl EphKeyGenSecondMsg {
     pub fn verify_and_decommit(
         comm_witness: EphCommWitness,
         party_one_first_message: &Party1EphKeyGenFirstMsg,
-    ) -> Result<EphKeyGenSecondMsg, ProofError> {
+    ) -> Result<EphKeyGenSecondMsg, Error> {
         let delta = ECDDHStatement {
             g1: GE::generator(),
             h1: party_one_first_message.public_share,
             g2: GE::base_point2(),
             h2: party_one_first_message.c,
         };
-        party_one_first_message.d_log_proof.verify(&delta)?;
+        party_one_first_message
+            .d_log_proof
+            .verify(&delta)
+            .map_err(|_| {
+                Error::new(Lindell17Sign, "ephemeral keygen", InvalidDLogProof)
+                    .with_parties(vec![1])
+            })?;
         Ok(EphKeyGenSecondMsg { comm_witness })
     }
 }
@@ -379,13 +644,19 @@ impl PartialSig {
         ephemeral_local_share: &EphEcKeyPair,
         ephemeral_other_public_share: &GE,
         message: &BigInt,
-    ) -> PartialSig {
+    ) -> Result<PartialSig, Error> {
         let q = FE::q();
         //compute r = k2* R1
         let r = ephemeral_other_public_share
             .scalar_mul(&ephemeral_local_share.secret_share.get_element());
 
-        let rx = r.x_coor().unwrap().mod_floor(&q);
+        let rx = r
+            .x_coor()
+            .ok_or_else(|| {
+                Error::new(Lindell17Sign, "partial signature", PointAtInfinity)
+                    .with_parties(vec![1])
+            })?
+            .mod_floor(&q);
         let rho = BigInt::sample_below(&q.pow(2));
         let mut k2_inv = ephemeral_local_share
             .secret_share
@@ -407,8 +678,8 @@ impl PartialSig {
             RawPlaintext::from(v),
         );
         //c3:
-        PartialSig {
+        Ok(PartialSig {
             c3: Paillier::add(ek, c2, c1).0.into_owned(),
-        }
+        })
     }
 }
@@ -29,7 +29,8 @@ fn test_full_key_gen() {
     let (party_one_first_message, comm_witness, ec_key_pair_party1) =
         party_one::KeyGenFirstMsg::create_commitments_with_fixed_secret_share(ECScalar::from(
             &BigInt::sample(253),
-        ));
+        ))
+        .expect("secret share out of range");
     let (party_two_first_message, _ec_key_pair_party2) =
         party_two::KeyGenFirstMsg::create_with_fixed_secret_share(ECScalar::from(&BigInt::from(
             10,
@@ -67,6 +68,27 @@ fn test_full_key_gen() {
     )
     .expect("bad paillier key");
 
+    // zk proofs that the paillier modulus is a Blum integer without small factors
+    let blum_modulus_proof =
+        party_one::PaillierKeyPair::generate_paillier_blum_modulus_proof(&paillier_key_pair);
+    party_two::PaillierPublic::verify_paillier_blum_modulus_proof(
+        &blum_modulus_proof,
+        &party_two_paillier.ek,
+    )
+    .expect("bad paillier key");
+    let party_two_ring_pedersen = party_two::RingPedersenMsg::create();
+    let no_small_factor_proof = party_one::PaillierKeyPair::generate_no_small_factor_proof(
+        &paillier_key_pair,
+        &party_two_ring_pedersen,
+    )
+    .expect("bad ring pedersen parameters");
+    party_two::PaillierPublic::verify_no_small_factor_proof(
+        &no_small_factor_proof,
+        &party_two_paillier.ek,
+        &party_two_ring_pedersen,
+    )
+    .expect("bad paillier key");
+
     //zk_pdl
 
     let (party_two_pdl_first_message, mut party_two_pdl_state, party_two_pdl_statement) =
@@ -145,7 +167,8 @@ fn test_two_party_sign() {
         &eph_ec_key_pair_party2,
         &eph_party_one_first_message.public_share,
         &message,
-    );
+    )
+    .expect("bad R1");
 
     let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
 
@@ -154,9 +177,315 @@ fn test_two_party_sign() {
         &partial_sig.c3,
         &eph_ec_key_pair_party1,
         &eph_party_two_second_message.comm_witness.public_share,
-    );
+    )
+    .expect("bad R2");
 
     let pubkey =
         party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
     party_one::verify(&signature, &pubkey, &message).expect("Invalid signature")
 }
+
+#[test]
+fn test_two_party_sign_with_bip32_child_key() {
+    let (_party_one_private_share_gen, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (party_two_private_share_gen, ec_key_pair_party2) = party_two::KeyGenFirstMsg::create();
+    let keypair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
+    let party2_private = party_two::Party2Private::set_private_key(&ec_key_pair_party2);
+    let paillier_public = party_two::PaillierPublic {
+        ek: keypair.ek.clone(),
+        encrypted_secret_share: keypair.encrypted_share.clone(),
+    };
+
+    let chain_code = [7u8; 32];
+    let path = [0, 1];
+    let child_one = party1_private
+        .derive_child(
+            &party_two_private_share_gen.public_share,
+            &chain_code,
+            &path,
+        )
+        .unwrap();
+    let (child_paillier_public, child_two) = party2_private
+        .derive_child(
+            &paillier_public,
+            &ec_key_pair_party1.public_share,
+            &chain_code,
+            &path,
+        )
+        .unwrap();
+    assert_eq!(child_one.public_key, child_two.public_key);
+    assert_eq!(child_one.chain_code, child_two.chain_code);
+
+    let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
+        party_two::EphKeyGenFirstMsg::create_commitments();
+    let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+        party_one::EphKeyGenFirstMsg::create();
+    let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+        eph_comm_witness,
+        &eph_party_one_first_message,
+    )
+    .expect("party1 DLog proof failed");
+    let _eph_party_one_second_message =
+        party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &eph_party_two_first_message,
+            &eph_party_two_second_message,
+        )
+        .expect("failed to verify commitments and DLog proof");
+
+    let message = BigInt::from(1234);
+    let partial_sig = party_two::PartialSig::compute(
+        &child_paillier_public.ek,
+        &child_paillier_public.encrypted_secret_share,
+        &party2_private,
+        &eph_ec_key_pair_party2,
+        &eph_party_one_first_message.public_share,
+        &message,
+    )
+    .expect("bad R1");
+    let signature = party_one::Signature::compute(
+        &party1_private,
+        &partial_sig.c3,
+        &eph_ec_key_pair_party1,
+        &eph_party_two_second_message.comm_witness.public_share,
+    )
+    .expect("bad R2");
+
+    party_one::verify(&signature, &child_one.public_key, &message).expect("Invalid signature");
+    let pubkey =
+        party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
+    assert!(party_one::verify(&signature, &pubkey, &message).is_err());
+}
+
+#[test]
+fn test_two_party_sign_with_hedged_nonces() {
+    let (_party_one_private_share_gen, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (party_two_private_share_gen, ec_key_pair_party2) = party_two::KeyGenFirstMsg::create();
+    let keypair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
+    let party2_private = party_two::Party2Private::set_private_key(&ec_key_pair_party2);
+
+    let message = BigInt::from(1234);
+    let session_id = b"session 1";
+    let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
+        party_two::EphKeyGenFirstMsg::create_commitments_hedged(
+            &party2_private,
+            &message,
+            session_id,
+        );
+    let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+        party_one::EphKeyGenFirstMsg::create_hedged(&party1_private, &message, session_id);
+    let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+        eph_comm_witness,
+        &eph_party_one_first_message,
+    )
+    .expect("party1 DLog proof failed");
+    let _eph_party_one_second_message =
+        party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &eph_party_two_first_message,
+            &eph_party_two_second_message,
+        )
+        .expect("failed to verify commitments and DLog proof");
+
+    let partial_sig = party_two::PartialSig::compute(
+        &keypair.ek,
+        &keypair.encrypted_share,
+        &party2_private,
+        &eph_ec_key_pair_party2,
+        &eph_party_one_first_message.public_share,
+        &message,
+    )
+    .expect("bad R1");
+    let signature = party_one::Signature::compute(
+        &party1_private,
+        &partial_sig.c3,
+        &eph_ec_key_pair_party1,
+        &eph_party_two_second_message.comm_witness.public_share,
+    )
+    .expect("bad R2");
+
+    let pubkey =
+        party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
+    party_one::verify(&signature, &pubkey, &message).expect("Invalid signature");
+
+    // the same message and session still give fresh nonces
+    let (eph_party_one_first_message_again, _) =
+        party_one::EphKeyGenFirstMsg::create_hedged(&party1_private, &message, session_id);
+    assert_ne!(
+        eph_party_one_first_message.public_share,
+        eph_party_one_first_message_again.public_share
+    );
+}
+
+#[test]
+fn test_backup_and_restore_key_shares() {
+    use curv::{FE, GE};
+
+    let (_party_one_private_share_gen, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (_party_two_private_share_gen, ec_key_pair_party2) = party_two::KeyGenFirstMsg::create();
+    let keypair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
+    let party2_private = party_two::Party2Private::set_private_key(&ec_key_pair_party2);
+
+    let backup_private_key: FE = ECScalar::new_random();
+    let backup_public_key = GE::generator() * backup_private_key;
+    let backup_one = party1_private.backup(&backup_public_key);
+    let backup_two = party2_private.backup(&backup_public_key);
+    backup_one
+        .verify(&ec_key_pair_party1.public_share, &backup_public_key)
+        .unwrap();
+    backup_two
+        .verify(&ec_key_pair_party2.public_share, &backup_public_key)
+        .unwrap();
+    assert!(backup_one
+        .verify(&ec_key_pair_party2.public_share, &backup_public_key)
+        .is_err());
+
+    let restored_one = party_one::EcKeyPair::restore(
+        &backup_one,
+        &ec_key_pair_party1.public_share,
+        &backup_private_key,
+    )
+    .unwrap();
+    let restored_two = party_two::EcKeyPair::restore(
+        &backup_two,
+        &ec_key_pair_party2.public_share,
+        &backup_private_key,
+    )
+    .unwrap();
+    let restored_private = party_one::Party1Private::set_private_key(&restored_one, &keypair);
+    assert_eq!(
+        party_one::compute_pubkey(&restored_private, &restored_two.public_share),
+        party_one::compute_pubkey(&party1_private, &ec_key_pair_party2.public_share)
+    );
+}
+
+#[test]
+fn test_fixed_secret_share_out_of_range() {
+    use crate::ErrorKind;
+    use curv::FE;
+
+    // the range proof of party one only covers x1 < q/3
+    let secret_share: FE = ECScalar::from(&(FE::q() - BigInt::from(1)));
+    let err = party_one::KeyGenFirstMsg::create_commitments_with_fixed_secret_share(secret_share)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidSecretShare);
+}
+
+#[test]
+fn test_refresh_private_key_proves_new_paillier_key() {
+    let (_party_one_first_message, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let paillier_key_pair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party_one_private =
+        party_one::Party1Private::set_private_key(&ec_key_pair_party1, &paillier_key_pair);
+    let party_two_ring_pedersen = party_two::RingPedersenMsg::create();
+
+    let (
+        ek_new,
+        _c_key_new,
+        _party_one_private_new,
+        correct_key_proof,
+        blum_modulus_proof,
+        no_small_factor_proof,
+        _range_proof,
+    ) = party_one::Party1Private::refresh_private_key(
+        &party_one_private,
+        &BigInt::from(3),
+        &party_two_ring_pedersen,
+    )
+    .expect("bad ring pedersen parameters");
+    party_two::PaillierPublic::verify_ni_proof_correct_key(correct_key_proof, &ek_new)
+        .expect("bad paillier key");
+    party_two::PaillierPublic::verify_paillier_blum_modulus_proof(&blum_modulus_proof, &ek_new)
+        .expect("bad paillier key");
+    party_two::PaillierPublic::verify_no_small_factor_proof(
+        &no_small_factor_proof,
+        &ek_new,
+        &party_two_ring_pedersen,
+    )
+    .expect("bad paillier key");
+}
+
+#[test]
+fn test_debug_output_redacts_secrets() {
+    use curv::FE;
+
+    // below q/3, as the range proof of party one requires
+    let secret_share: FE = ECScalar::from(&BigInt::sample(253));
+    let (_party_one_first_message, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments_with_fixed_secret_share(secret_share)
+            .expect("secret share out of range");
+    let keypair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
+
+    let secret = format!("{:?}", secret_share);
+    for debug in &[
+        format!("{:?}", ec_key_pair_party1),
+        format!("{:?}", keypair),
+        format!("{:?}", party1_private),
+    ] {
+        assert!(debug.contains("[REDACTED]"));
+        assert!(!debug.contains(&secret));
+    }
+}
+
+#[test]
+fn test_signature_encodings() {
+    let (_party_one_private_share_gen, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (party_two_private_share_gen, ec_key_pair_party2) = party_two::KeyGenFirstMsg::create();
+    let keypair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+
+    let (_eph_party_two_first_message, _eph_comm_witness, eph_ec_key_pair_party2) =
+        party_two::EphKeyGenFirstMsg::create_commitments();
+    let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+        party_one::EphKeyGenFirstMsg::create();
+
+    let party2_private = party_two::Party2Private::set_private_key(&ec_key_pair_party2);
+    let message = BigInt::from(1234);
+    let partial_sig = party_two::PartialSig::compute(
+        &keypair.ek,
+        &keypair.encrypted_share,
+        &party2_private,
+        &eph_ec_key_pair_party2,
+        &eph_party_one_first_message.public_share,
+        &message,
+    )
+    .expect("bad R1");
+    let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
+    let signature = party_one::Signature::compute_with_recid(
+        &party1_private,
+        &partial_sig.c3,
+        &eph_ec_key_pair_party1,
+        &eph_ec_key_pair_party2.public_share,
+    )
+    .expect("bad R2");
+    let pubkey =
+        party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
+
+    let (secp_sig, secp_recid) = signature.to_libsecp256k1().unwrap();
+    let decoded = vec![
+        party_one::SignatureRecid::from_der(&signature.to_der(), signature.recid).unwrap(),
+        party_one::SignatureRecid::from_compact(&signature.to_compact(), signature.recid).unwrap(),
+        party_one::SignatureRecid::from_recoverable(&signature.to_recoverable()).unwrap(),
+        party_one::SignatureRecid::from_libsecp256k1(&secp_sig, &secp_recid).unwrap(),
+    ];
+    for decoded_sig in decoded {
+        assert_eq!(decoded_sig.recid, signature.recid);
+        assert_eq!(decoded_sig.recover_public_key(&message).unwrap(), pubkey);
+        let sig = party_one::Signature::from_der(&decoded_sig.to_der()).unwrap();
+        party_one::verify(&sig, &pubkey, &message).expect("Invalid signature");
+    }
+
+    assert!(party_one::Signature::from_compact(&signature.to_recoverable()).is_err());
+}
@@ -0,0 +1,89 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Verifiable encrypted backup of a key share, built on centipede
+//! (https://github.com/KZen-networks/centipede). The secret x is split into segments, each
+//! segment is ElGamal encrypted to the backup public key, and a proof shows that the segments
+//! are in range and that together they encrypt the discrete log of the public share x * G.
+//! Anyone can verify a backup against the public share, only the holder of the backup private
+//! key can restore it.
+
+use centipede::juggling::proof_system::{Helgamalsegmented, Proof};
+use centipede::juggling::segmentation::Msegmentation;
+use curv::elliptic::curves::traits::*;
+use curv::{FE, GE};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::ErrorKind::{InvalidBackupProof, InvalidSecretShare};
+use crate::Protocol::Backup;
+
+/// Segments of SEGMENT_SIZE bits, restoring a segment takes up to 2^SEGMENT_SIZE point
+/// additions.
+pub const SEGMENT_SIZE: usize = 8;
+pub const NUM_OF_SEGMENTS: usize = 32;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedBackup {
+    pub encryption: Helgamalsegmented,
+    pub proof: Proof,
+}
+
+impl EncryptedBackup {
+    /// Encrypts secret to backup_public_key.
+    pub fn create(secret: &FE, backup_public_key: &GE) -> EncryptedBackup {
+        let g: GE = ECPoint::generator();
+        let (witness, encryption) = Msegmentation::to_encrypted_segments(
+            secret,
+            &SEGMENT_SIZE,
+            NUM_OF_SEGMENTS,
+            backup_public_key,
+            &g,
+        );
+        let proof = Proof::prove(&witness, &encryption, &g, backup_public_key, &SEGMENT_SIZE);
+        EncryptedBackup { encryption, proof }
+    }
+
+    /// Checks that the backup encrypts the discrete log of public_share to backup_public_key.
+    pub fn verify(&self, public_share: &GE, backup_public_key: &GE) -> Result<(), Error> {
+        let g: GE = ECPoint::generator();
+        self.proof
+            .verify(
+                &self.encryption,
+                &g,
+                backup_public_key,
+                public_share,
+                &SEGMENT_SIZE,
+            )
+            .map_err(|_| Error::new(Backup, "verify", InvalidBackupProof))
+    }
+
+    /// Verifies the backup and decrypts the secret of public_share.
+    pub fn restore(&self, public_share: &GE, backup_private_key: &FE) -> Result<FE, Error> {
+        let g: GE = ECPoint::generator();
+        self.verify(public_share, &(&g * backup_private_key))?;
+        let secret =
+            Msegmentation::decrypt(&self.encryption, &g, backup_private_key, &SEGMENT_SIZE)
+                .map_err(|_| Error::new(Backup, "restore", InvalidSecretShare))?;
+        if g * secret != *public_share {
+            return Err(Error::new(Backup, "restore", InvalidSecretShare));
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,31 @@
+use crate::utilities::backup::*;
+use crate::ErrorKind;
+use curv::elliptic::curves::traits::*;
+use curv::{FE, GE};
+
+#[test]
+fn test_backup_verify_restore() {
+    let g: GE = ECPoint::generator();
+    let secret: FE = ECScalar::new_random();
+    let backup_private_key: FE = ECScalar::new_random();
+    let backup_public_key = g * backup_private_key;
+
+    let backup = EncryptedBackup::create(&secret, &backup_public_key);
+    backup.verify(&(g * secret), &backup_public_key).unwrap();
+    let restored = backup.restore(&(g * secret), &backup_private_key).unwrap();
+    assert_eq!(restored, secret);
+}
+
+#[test]
+fn test_backup_rejects_wrong_share_and_key() {
+    let g: GE = ECPoint::generator();
+    let secret: FE = ECScalar::new_random();
+    let backup_private_key: FE = ECScalar::new_random();
+    let backup_public_key = g * backup_private_key;
+    let backup = EncryptedBackup::create(&secret, &backup_public_key);
+
+    let other: FE = ECScalar::new_random();
+    let err = backup.verify(&(g * other), &backup_public_key).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidBackupProof);
+    assert!(backup.restore(&(g * secret), &other).is_err());
+}
@@ -0,0 +1,88 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Public (non-hardened) BIP32 child key derivation,
+//! https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+//! The derivation only needs the joint public key and a chain code, the child secret key is
+//! x + tweak, where x is the shared secret key. Each protocol applies the tweak to its shares.
+
+use curv::arithmetic::traits::Converter;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE, GE};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+
+use crate::Error;
+use crate::ErrorKind::{HardenedDerivation, InvalidChildKey};
+use crate::Protocol::Bip32;
+
+/// Indices from HARDENED on are hardened and cannot be derived from the public key.
+pub const HARDENED: u32 = 1 << 31;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChildKey {
+    /// sum of the tweaks along the path, the child secret key is x + tweak
+    pub tweak: FE,
+    pub public_key: GE,
+    pub chain_code: [u8; 32],
+}
+
+/// Derives the child of y at index. Fails for hardened indices and, with negligible
+/// probability, for an invalid child, in which case BIP32 proceeds with the next index.
+pub fn derive_child(y: &GE, chain_code: &[u8; 32], index: u32) -> Result<ChildKey, Error> {
+    if index >= HARDENED {
+        return Err(Error::new(Bip32, "derive", HardenedDerivation));
+    }
+    let mut mac = Hmac::<Sha512>::new_varkey(chain_code).expect("any key length is valid");
+    mac.input(&BigInt::to_vec(&y.bytes_compressed_to_big_int()));
+    mac.input(&index.to_be_bytes());
+    let result = mac.result().code();
+    let (il, ir) = result.split_at(32);
+
+    let il = BigInt::from(il);
+    if il >= FE::q() || il == BigInt::zero() {
+        return Err(Error::new(Bip32, "derive", InvalidChildKey));
+    }
+    let tweak: FE = ECScalar::from(&il);
+    let mut child_chain_code = [0u8; 32];
+    child_chain_code.copy_from_slice(ir);
+    Ok(ChildKey {
+        tweak,
+        public_key: *y + GE::generator() * tweak,
+        chain_code: child_chain_code,
+    })
+}
+
+/// Derives along path, starting from y. The tweak of the result is the sum of the tweaks of
+/// every step.
+pub fn derive_path(y: &GE, chain_code: &[u8; 32], path: &[u32]) -> Result<ChildKey, Error> {
+    let root = ChildKey {
+        tweak: FE::zero(),
+        public_key: *y,
+        chain_code: *chain_code,
+    };
+    path.iter().try_fold(root, |parent, &index| {
+        let child = derive_child(&parent.public_key, &parent.chain_code, index)?;
+        Ok(ChildKey {
+            tweak: parent.tweak + child.tweak,
+            ..child
+        })
+    })
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,52 @@
+use crate::utilities::bip32::*;
+use crate::ErrorKind;
+use curv::arithmetic::traits::Converter;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE, GE};
+
+// test vector 1 of BIP32, from m/0H/1/2H
+fn parent() -> (FE, [u8; 32]) {
+    let x: FE = ECScalar::from(&BigInt::from_hex(
+        "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+    ));
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(
+        &hex::decode("04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f").unwrap(),
+    );
+    (x, chain_code)
+}
+
+#[test]
+fn test_derive_path_vector_1() {
+    let (x, chain_code) = parent();
+    let y = GE::generator() * x;
+
+    let child = derive_child(&y, &chain_code, 2).unwrap();
+    assert_eq!(
+        child.public_key.bytes_compressed_to_big_int(),
+        BigInt::from_hex("02e8445082a72f29b75ca48748a914df60622a609cacfce8ed0e35804560741d29")
+    );
+    assert_eq!(
+        child.chain_code.to_vec(),
+        hex::decode("cfb71883f01676f587d023cc53a35bc7f88f724b1f8c2892ac1275ac822a3edd").unwrap()
+    );
+
+    // m/0H/1/2H/2/1000000000
+    let child = derive_path(&y, &chain_code, &[2, 1_000_000_000]).unwrap();
+    let expected: FE = ECScalar::from(&BigInt::from_hex(
+        "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+    ));
+    assert_eq!(x + child.tweak, expected);
+    assert_eq!(
+        child.public_key.bytes_compressed_to_big_int(),
+        BigInt::from_hex("022a471424da5e657499d1ff51cb43c47481a03b1e77f951fe64cec9f5a48f7011")
+    );
+}
+
+#[test]
+fn test_hardened_index_is_rejected() {
+    let (x, chain_code) = parent();
+    let y = GE::generator() * x;
+    let err = derive_path(&y, &chain_code, &[1, HARDENED]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::HardenedDerivation);
+}
@@ -0,0 +1,86 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Hedged nonce generation, the HMAC-DRBG of RFC 6979 section 3.2 with the additional data of
+//! section 3.6 set to fresh randomness, a session id and a label. The nonce stays unpredictable
+//! if either the RNG or the secret is sound, and a repeated or biased RNG output no longer
+//! gives the same nonce for different messages or sessions.
+
+use curv::arithmetic::traits::{Converter, Modulo, Samplable};
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+/// Nonce for the holder of secret signing message (the message digest). session_id must be
+/// fresh for every signing session, label separates the nonces drawn in one session.
+pub fn hedged_nonce(secret: &FE, message: &BigInt, session_id: &[u8], label: &[u8]) -> FE {
+    let mut extra = to_bytes32(&BigInt::sample(256)).to_vec();
+    extra.extend_from_slice(&(session_id.len() as u64).to_be_bytes());
+    extra.extend_from_slice(session_id);
+    extra.extend_from_slice(label);
+    let k = rfc6979_nonce(secret, message, &extra);
+    extra.zeroize();
+    k
+}
+
+/// RFC 6979 nonce with additional data, for SHA-256 and secp256k1.
+fn rfc6979_nonce(secret: &FE, message: &BigInt, extra: &[u8]) -> FE {
+    let q = FE::q();
+    let mut x = to_bytes32(&secret.to_big_int());
+    let h = to_bytes32(&message.modulus(&q));
+
+    let mut v = [1u8; 32];
+    let mut k = [0u8; 32];
+    for &round in &[0u8, 1u8] {
+        k = hmac_sha256(&k, &[&v, &[round], &x, &h, extra]);
+        v = hmac_sha256(&k, &[&v]);
+    }
+    x.zeroize();
+
+    loop {
+        v = hmac_sha256(&k, &[&v]);
+        let candidate = BigInt::from(&v[..]);
+        if candidate < q && candidate != BigInt::zero() {
+            k.zeroize();
+            v.zeroize();
+            return ECScalar::from(&candidate);
+        }
+        k = hmac_sha256(&k, &[&v, &[0u8]]);
+        v = hmac_sha256(&k, &[&v]);
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("any key length is valid");
+    for d in data {
+        mac.input(d);
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&mac.result().code());
+    out
+}
+
+fn to_bytes32(n: &BigInt) -> [u8; 32] {
+    let v = BigInt::to_vec(n);
+    let mut out = [0u8; 32];
+    out[32 - v.len()..].copy_from_slice(&v);
+    out
+}
+
+#[cfg(test)]
+mod test;
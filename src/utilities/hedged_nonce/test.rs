@@ -0,0 +1,27 @@
+use crate::utilities::hedged_nonce::*;
+use curv::arithmetic::traits::Converter;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn test_rfc6979_nonce_without_extra_data() {
+    // the well known secp256k1 vector: private key 1, message "Satoshi Nakamoto"
+    let x: FE = ECScalar::from(&BigInt::one());
+    let message = BigInt::from(&Sha256::digest(b"Satoshi Nakamoto")[..]);
+    assert_eq!(
+        rfc6979_nonce(&x, &message, &[]).to_big_int(),
+        BigInt::from_hex("8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15")
+    );
+}
+
+#[test]
+fn test_hedged_nonce_is_fresh() {
+    let x: FE = ECScalar::new_random();
+    let message = BigInt::from(1234);
+    let k1 = hedged_nonce(&x, &message, b"session", b"k_i");
+    let k2 = hedged_nonce(&x, &message, b"session", b"k_i");
+    let k3 = hedged_nonce(&x, &message, b"session", b"gamma_i");
+    assert_ne!(k1, k2);
+    assert_ne!(k1, k3);
+}
@@ -0,0 +1,194 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Password sealed storage of secret key material, e.g. PartyPrivate, Party1Private,
+//! Party2Private or a LocalKeyShare. The password is stretched with scrypt and the serialized
+//! secret is encrypted with AES-256-GCM. The header (version, KDF parameters, nonce and the
+//! caller's metadata) is the associated data of the cipher, so a file whose header or
+//! ciphertext was changed does not open.
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::Aes256Gcm;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::Error;
+use crate::ErrorKind::{AuthenticationFailed, InvalidEncoding, UnsupportedVersion};
+use crate::Protocol::KeyStore;
+
+pub const KEY_STORE_VERSION: u16 = 1;
+pub const CIPHER: &str = "aes-256-gcm";
+pub const KDF: &str = "scrypt";
+/// largest scrypt memory cost (128 * r * 2^log_n bytes) and parallelism a file may ask for
+pub const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+pub const MAX_SCRYPT_P: u32 = 16;
+pub const SALT_LEN: usize = 32;
+
+/// scrypt cost parameters, the defaults use 64 MiB of memory.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl ScryptParams {
+    /// Bounds the cost before any work is done, as the parameters of a file are untrusted.
+    fn check(&self, round: &'static str) -> Result<(), Error> {
+        let memory = 1u64
+            .checked_shl(u32::from(self.log_n))
+            .and_then(|n| n.checked_mul(128 * u64::from(self.r)));
+        match memory {
+            Some(memory) if memory <= MAX_SCRYPT_MEMORY && self.p <= MAX_SCRYPT_P => Ok(()),
+            _ => Err(Error::new(KeyStore, round, InvalidEncoding)),
+        }
+    }
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        ScryptParams {
+            log_n: 16,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyStoreHeader {
+    pub version: u16,
+    pub kdf: String,
+    pub kdf_params: ScryptParams,
+    pub salt: Vec<u8>,
+    pub cipher: String,
+    pub nonce: Vec<u8>,
+    /// not encrypted, but authenticated
+    pub metadata: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SealedKeyStore {
+    pub header: KeyStoreHeader,
+    pub ciphertext: Vec<u8>,
+}
+
+impl SealedKeyStore {
+    pub fn seal<T: Serialize>(secret: &T, password: &[u8], metadata: &str) -> Result<Self, Error> {
+        SealedKeyStore::seal_with_params(secret, password, metadata, ScryptParams::default())
+    }
+
+    pub fn seal_with_params<T: Serialize>(
+        secret: &T,
+        password: &[u8],
+        metadata: &str,
+        kdf_params: ScryptParams,
+    ) -> Result<Self, Error> {
+        kdf_params.check("seal")?;
+        let mut salt = vec![0u8; SALT_LEN];
+        let mut nonce = vec![0u8; 12];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let header = KeyStoreHeader {
+            version: KEY_STORE_VERSION,
+            kdf: KDF.to_string(),
+            kdf_params,
+            salt,
+            cipher: CIPHER.to_string(),
+            nonce,
+            metadata: metadata.to_string(),
+        };
+
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(secret)
+                .map_err(|_| Error::new(KeyStore, "seal", InvalidEncoding))?,
+        );
+        let aad = serde_json::to_vec(&header).expect("header serializes to JSON");
+        let cipher = header.derive_cipher("seal", password)?;
+        let payload = Payload {
+            msg: &plaintext,
+            aad: &aad,
+        };
+        let ciphertext = cipher
+            .encrypt(GenericArray::from_slice(&header.nonce), payload)
+            .map_err(|_| Error::new(KeyStore, "seal", InvalidEncoding))?;
+        Ok(SealedKeyStore { header, ciphertext })
+    }
+
+    /// Decrypts the secret, fails on a wrong password or any change to the stored data.
+    pub fn open<T: DeserializeOwned>(&self, password: &[u8]) -> Result<T, Error> {
+        let plaintext = self.open_bytes(password)?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|_| Error::new(KeyStore, "open", InvalidEncoding))
+    }
+
+    /// Decrypts the serialized secret, for formats with their own decoding such as
+    /// LocalKeyShare::from_json.
+    pub fn open_bytes(&self, password: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let header = &self.header;
+        if header.version != KEY_STORE_VERSION {
+            return Err(Error::new(KeyStore, "open", UnsupportedVersion));
+        }
+        if header.kdf != KDF
+            || header.cipher != CIPHER
+            || header.nonce.len() != 12
+            || header.salt.len() != SALT_LEN
+        {
+            return Err(Error::new(KeyStore, "open", InvalidEncoding));
+        }
+        header.kdf_params.check("open")?;
+        let aad = serde_json::to_vec(header).expect("header serializes to JSON");
+        let cipher = header.derive_cipher("open", password)?;
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &aad,
+        };
+        let plaintext = cipher
+            .decrypt(GenericArray::from_slice(&header.nonce), payload)
+            .map_err(|_| Error::new(KeyStore, "open", AuthenticationFailed))?;
+        Ok(Zeroizing::new(plaintext))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("key store serializes to JSON")
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, Error> {
+        serde_json::from_str(data).map_err(|_| Error::new(KeyStore, "open", InvalidEncoding))
+    }
+}
+
+impl KeyStoreHeader {
+    /// AES-256-GCM keyed with scrypt(password, salt).
+    fn derive_cipher(&self, round: &'static str, password: &[u8]) -> Result<Aes256Gcm, Error> {
+        let params = &self.kdf_params;
+        let scrypt_params = scrypt::ScryptParams::new(params.log_n, params.r, params.p)
+            .map_err(|_| Error::new(KeyStore, round, InvalidEncoding))?;
+        let mut key = [0u8; 32];
+        scrypt::scrypt(password, &self.salt, &scrypt_params, &mut key)
+            .expect("32 bytes is a valid scrypt output length");
+        let cipher = Aes256Gcm::new(*GenericArray::from_slice(&key));
+        key.zeroize();
+        Ok(cipher)
+    }
+}
+
+#[cfg(test)]
+mod test;
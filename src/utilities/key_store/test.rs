@@ -0,0 +1,81 @@
+use crate::protocols::two_party_ecdsa::lindell_2017::party_two;
+use crate::utilities::key_store::*;
+use crate::ErrorKind;
+
+// cheap parameters, the defaults take a noticeable time per test
+fn params() -> ScryptParams {
+    ScryptParams {
+        log_n: 10,
+        r: 8,
+        p: 1,
+    }
+}
+
+#[test]
+fn test_seal_and_open() {
+    let (_, ec_key_pair) = party_two::KeyGenFirstMsg::create();
+    let party2_private = party_two::Party2Private::set_private_key(&ec_key_pair);
+    let store = SealedKeyStore::seal_with_params(&party2_private, b"password", "party 2", params())
+        .unwrap();
+    let store = SealedKeyStore::from_json(&store.to_json()).unwrap();
+    assert_eq!(store.header.metadata, "party 2");
+    let opened: party_two::Party2Private = store.open(b"password").unwrap();
+    assert_eq!(
+        serde_json::to_string(&opened).unwrap(),
+        serde_json::to_string(&party2_private).unwrap()
+    );
+}
+
+#[test]
+fn test_open_rejects_wrong_password_and_tampering() {
+    let store =
+        SealedKeyStore::seal_with_params(&42u64, b"password", "metadata", params()).unwrap();
+
+    let err = store.open::<u64>(b"wrong password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::AuthenticationFailed);
+
+    let mut tampered = store.clone();
+    tampered.header.metadata = "other metadata".to_string();
+    let err = tampered.open::<u64>(b"password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::AuthenticationFailed);
+
+    let mut tampered = store.clone();
+    tampered.ciphertext[0] ^= 1;
+    let err = tampered.open::<u64>(b"password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::AuthenticationFailed);
+
+    let mut tampered = store;
+    tampered.header.version += 1;
+    let err = tampered.open::<u64>(b"password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnsupportedVersion);
+}
+
+#[test]
+fn test_open_bounds_kdf_params() {
+    let store =
+        SealedKeyStore::seal_with_params(&42u64, b"password", "metadata", params()).unwrap();
+
+    // rejected before scrypt runs, which would need 2^40 * 1 KiB of memory
+    let mut tampered = store.clone();
+    tampered.header.kdf_params.log_n = 40;
+    let err = tampered.open::<u64>(b"password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+
+    let mut tampered = store.clone();
+    tampered.header.kdf_params.p = MAX_SCRYPT_P + 1;
+    let err = tampered.open::<u64>(b"password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+
+    let mut tampered = store;
+    tampered.header.salt = vec![0u8; 1 << 20];
+    let err = tampered.open::<u64>(b"password").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+
+    let huge = ScryptParams {
+        log_n: 24,
+        r: 8,
+        p: 1,
+    };
+    let err = SealedKeyStore::seal_with_params(&42u64, b"password", "metadata", huge).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidEncoding);
+}
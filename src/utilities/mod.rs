@@ -1,3 +1,12 @@
+pub mod backup;
+pub mod bip32;
+pub mod hedged_nonce;
+pub mod key_store;
 pub mod mta;
+pub mod ring_pedersen;
+pub mod secret;
+pub mod signature_encoding;
+pub mod zk_no_small_factor;
+pub mod zk_paillier_blum_modulus;
 pub mod zk_pdl;
 pub mod zk_pdl_with_slack;
@@ -14,8 +14,9 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 
-/// MtA is descrbied in https://eprint.iacr.org/2019/114.pdf section 3
-use curv::arithmetic::traits::Samplable;
+//! MtA is described in https://eprint.iacr.org/2019/114.pdf section 3
+
+use curv::arithmetic::traits::{Samplable, ZeroizeBN};
 use curv::cryptographic_primitives::proofs::sigma_dlog::{DLogProof, ProveDLog};
 use curv::elliptic::curves::traits::*;
 use curv::BigInt;
@@ -25,9 +26,17 @@ use paillier::traits::EncryptWithChosenRandomness;
 use paillier::{Add, Decrypt, Mul};
 use paillier::{DecryptionKey, EncryptionKey, Paillier, Randomness, RawCiphertext, RawPlaintext};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use zk_paillier::zkproofs::DLogStatement;
 
 use crate::protocols::multi_party_ecdsa::gg_2018::party_i::PartyPrivate;
-use crate::Error::{self, InvalidKey};
+use crate::Error;
+use crate::ErrorKind::{InvalidMtaProof, InvalidRangeProof};
+use crate::Protocol::MtA;
+
+pub mod range_proofs;
+use self::range_proofs::{AliceProof, BobProof, BobProofExt};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MessageA {
@@ -41,6 +50,29 @@ pub struct MessageB {
     pub beta_tag_proof: DLogProof,
 }
 
+/// MtA with the range proofs of https://eprint.iacr.org/2019/114.pdf appendix A. Each proof
+/// is made against the ring-Pedersen parameters (N_tilde, h1, h2) of its verifier, so Alice
+/// sends a separate message to every Bob.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MessageAWithRangeProof {
+    pub c: BigInt, // paillier encryption
+    pub range_proof: AliceProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MessageBWithRangeProof {
+    pub c: BigInt, // paillier encryption
+    pub b_proof: DLogProof,
+    pub range_proof: BobRangeProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum BobRangeProof {
+    WithoutCheck(BobProof),
+    /// MtAwc: b is also checked against a public point g^b
+    WithCheck(BobProofExt),
+}
+
 impl MessageA {
     pub fn a(a: &FE, alice_ek: &EncryptionKey) -> (Self, BigInt) {
         let randomness = BigInt::sample_below(&alice_ek.n);
@@ -59,15 +91,18 @@ impl MessageA {
 }
 
 impl MessageB {
+    /// Returns the message, Bob's secret share beta and the randomness of Enc(beta_tag).
+    /// beta should be zeroized once it has been added up.
     pub fn b(b: &FE, alice_ek: &EncryptionKey, c_a: MessageA) -> (Self, FE, BigInt) {
-        let beta_tag = BigInt::sample_below(&alice_ek.n);
-        let beta_tag_fe: FE = ECScalar::from(&beta_tag);
+        let mut beta_tag = BigInt::sample_below(&alice_ek.n);
+        let mut beta_tag_fe: FE = ECScalar::from(&beta_tag);
         let randomness = BigInt::sample_below(&alice_ek.n);
         let c_beta_tag = Paillier::encrypt_with_chosen_randomness(
             alice_ek,
-            RawPlaintext::from(beta_tag),
+            RawPlaintext::from(beta_tag.clone()),
             &Randomness::from(randomness.clone()),
         );
+        beta_tag.zeroize_bn();
 
         let b_bn = b.to_big_int();
         let b_c_a = Paillier::mul(
@@ -79,6 +114,7 @@ impl MessageB {
         let beta = FE::zero().sub(&beta_tag_fe.get_element());
         let dlog_proof_b = DLogProof::prove(b);
         let dlog_proof_beta_tag = DLogProof::prove(&beta_tag_fe);
+        beta_tag_fe.zeroize();
 
         (
             Self {
@@ -104,7 +140,7 @@ impl MessageB {
         {
             Ok(alpha)
         } else {
-            Err(InvalidKey)
+            Err(Error::new(MtA, "message b", InvalidMtaProof))
         }
     }
 
@@ -127,7 +163,7 @@ impl MessageB {
         {
             Ok(alpha)
         } else {
-            Err(InvalidKey)
+            Err(Error::new(MtA, "message b", InvalidMtaProof))
         }
     }
 
@@ -136,5 +172,172 @@ impl MessageB {
     }
 }
 
+impl MessageAWithRangeProof {
+    /// bob_statement holds the ring-Pedersen parameters of the receiver.
+    pub fn a(a: &FE, alice_ek: &EncryptionKey, bob_statement: &DLogStatement) -> (Self, BigInt) {
+        let (m_a, randomness) = MessageA::a(a, alice_ek);
+        let range_proof = AliceProof::generate(
+            &a.to_big_int(),
+            &m_a.c,
+            alice_ek,
+            bob_statement,
+            &randomness,
+        );
+        (
+            Self {
+                c: m_a.c,
+                range_proof,
+            },
+            randomness,
+        )
+    }
+
+    pub fn verify(
+        &self,
+        alice_ek: &EncryptionKey,
+        bob_statement: &DLogStatement,
+    ) -> Result<(), Error> {
+        if self.range_proof.verify(&self.c, alice_ek, bob_statement) {
+            Ok(())
+        } else {
+            Err(Error::new(MtA, "message a", InvalidRangeProof))
+        }
+    }
+}
+
+impl MessageBWithRangeProof {
+    /// Checks Alice's range proof against bob_statement and answers with a proof against
+    /// alice_statement. As in MessageB::b, the returned beta should be zeroized after use.
+    pub fn b(
+        b: &FE,
+        alice_ek: &EncryptionKey,
+        m_a: &MessageAWithRangeProof,
+        alice_statement: &DLogStatement,
+        bob_statement: &DLogStatement,
+    ) -> Result<(Self, FE, BigInt), Error> {
+        Self::b_with_optional_check(b, alice_ek, m_a, alice_statement, bob_statement, false)
+    }
+
+    /// MtAwc version of b, Alice checks the result against g^b.
+    pub fn b_with_check(
+        b: &FE,
+        alice_ek: &EncryptionKey,
+        m_a: &MessageAWithRangeProof,
+        alice_statement: &DLogStatement,
+        bob_statement: &DLogStatement,
+    ) -> Result<(Self, FE, BigInt), Error> {
+        Self::b_with_optional_check(b, alice_ek, m_a, alice_statement, bob_statement, true)
+    }
+
+    fn b_with_optional_check(
+        b: &FE,
+        alice_ek: &EncryptionKey,
+        m_a: &MessageAWithRangeProof,
+        alice_statement: &DLogStatement,
+        bob_statement: &DLogStatement,
+        check: bool,
+    ) -> Result<(Self, FE, BigInt), Error> {
+        m_a.verify(alice_ek, bob_statement)?;
+
+        let mut beta_tag = BigInt::sample_below(&alice_ek.n);
+        let mut beta_tag_fe: FE = ECScalar::from(&beta_tag);
+        let randomness = BigInt::sample_below(&alice_ek.n);
+        let c_beta_tag = Paillier::encrypt_with_chosen_randomness(
+            alice_ek,
+            RawPlaintext::from(beta_tag.clone()),
+            &Randomness::from(randomness.clone()),
+        );
+        let b_c_a = Paillier::mul(
+            alice_ek,
+            RawCiphertext::from(m_a.c.clone()),
+            RawPlaintext::from(b.to_big_int()),
+        );
+        let c_b = Paillier::add(alice_ek, b_c_a, c_beta_tag).0.into_owned();
+        let beta = FE::zero().sub(&beta_tag_fe.get_element());
+        beta_tag_fe.zeroize();
+
+        let b_proof = DLogProof::prove(b);
+        let range_proof = if check {
+            BobRangeProof::WithCheck(BobProofExt::generate(
+                &m_a.c,
+                &c_b,
+                b,
+                &beta_tag,
+                alice_ek,
+                alice_statement,
+                &randomness,
+                &b_proof.pk,
+            ))
+        } else {
+            BobRangeProof::WithoutCheck(BobProof::generate(
+                &m_a.c,
+                &c_b,
+                b,
+                &beta_tag,
+                alice_ek,
+                alice_statement,
+                &randomness,
+            ))
+        };
+        beta_tag.zeroize_bn();
+
+        Ok((
+            Self {
+                c: c_b,
+                b_proof,
+                range_proof,
+            },
+            beta,
+            randomness,
+        ))
+    }
+
+    /// c_a is the ciphertext Alice sent to Bob.
+    pub fn verify_proofs_get_alpha(
+        &self,
+        dk: &DecryptionKey,
+        alice_ek: &EncryptionKey,
+        c_a: &BigInt,
+        alice_statement: &DLogStatement,
+    ) -> Result<FE, Error> {
+        match &self.range_proof {
+            BobRangeProof::WithoutCheck(proof)
+                if DLogProof::verify(&self.b_proof).is_ok()
+                    && proof.verify(c_a, &self.c, alice_ek, alice_statement) =>
+            {
+                Ok(self.decrypt_alpha(dk))
+            }
+            _ => Err(Error::new(MtA, "message b", InvalidRangeProof)),
+        }
+    }
+
+    /// MtAwc version of verify_proofs_get_alpha, g_b is the public point Bob's input is
+    /// checked against. Passing `self.b_proof.pk` binds b to the point Bob revealed.
+    pub fn verify_proofs_get_alpha_with_check(
+        &self,
+        dk: &DecryptionKey,
+        alice_ek: &EncryptionKey,
+        c_a: &BigInt,
+        alice_statement: &DLogStatement,
+        g_b: &GE,
+    ) -> Result<FE, Error> {
+        match &self.range_proof {
+            BobRangeProof::WithCheck(proof)
+                if self.b_proof.pk.get_element() == g_b.get_element()
+                    && DLogProof::verify(&self.b_proof).is_ok()
+                    && proof.verify(c_a, &self.c, alice_ek, alice_statement, g_b) =>
+            {
+                Ok(self.decrypt_alpha(dk))
+            }
+            _ => Err(Error::new(MtA, "message b", InvalidRangeProof)),
+        }
+    }
+
+    fn decrypt_alpha(&self, dk: &DecryptionKey) -> FE {
+        let alice_share = Paillier::decrypt(dk, &RawCiphertext::from(self.c.clone()));
+        ECScalar::from(&alice_share.0)
+    }
+}
+
 #[cfg(test)]
 mod test;
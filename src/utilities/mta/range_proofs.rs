@@ -0,0 +1,408 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Range proofs for MtA as given in appendix A of https://eprint.iacr.org/2019/114.pdf:
+//! AliceProof is the range proof of the initiator (A.1), BobProof the respondent proof (A.2)
+//! and BobProofExt the respondent proof with check used in MtAwc (A.3).
+//!
+//! All proofs commit with the verifier's ring-Pedersen parameters (N_tilde, h1, h2), given as
+//! a DLogStatement { N: N_tilde, g: h1, ni: h2 }.
+
+use curv::arithmetic::traits::{Modulo, Samplable};
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE, GE};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::utilities::ring_pedersen::is_valid_statement;
+use crate::utilities::zk_pdl_with_slack::commitment_unknown_order;
+
+/// Proof that the plaintext of Alice's ciphertext c is in the range [0, q^3].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AliceProof {
+    z: BigInt,
+    u: BigInt,
+    w: BigInt,
+    s: BigInt,
+    s1: BigInt,
+    s2: BigInt,
+}
+
+impl AliceProof {
+    /// a is the plaintext of cipher and r the randomness used to encrypt it.
+    pub fn generate(
+        a: &BigInt,
+        cipher: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &BigInt,
+    ) -> Self {
+        let (N_tilde, h1, h2) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let q = FE::q();
+        let q3 = q.pow(3);
+        let one = BigInt::one();
+
+        let alpha = BigInt::sample_below(&q3);
+        let beta = BigInt::sample_range(&one, &(&alice_ek.n - &one));
+        let gamma = BigInt::sample_below(&(&q3 * N_tilde));
+        let rho = BigInt::sample_below(&(&q * N_tilde));
+
+        let z = commitment_unknown_order(h1, h2, N_tilde, a, &rho);
+        let u = commitment_unknown_order(
+            &(&alice_ek.n + &one),
+            &beta,
+            &alice_ek.nn,
+            &alpha,
+            &alice_ek.n,
+        );
+        let w = commitment_unknown_order(h1, h2, N_tilde, &alpha, &gamma);
+
+        let e = Self::challenge(alice_ek, dlog_statement, cipher, &z, &u, &w);
+
+        let s = BigInt::mod_mul(&BigInt::mod_pow(r, &e, &alice_ek.n), &beta, &alice_ek.n);
+        let s1 = &e * a + alpha;
+        let s2 = &e * rho + gamma;
+
+        AliceProof { z, u, w, s, s1, s2 }
+    }
+
+    pub fn verify(
+        &self,
+        cipher: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+    ) -> bool {
+        let (N_tilde, h1, h2) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let N = &alice_ek.n;
+        let NN = &alice_ek.nn;
+
+        if !is_valid_statement(dlog_statement)
+            || !is_in_range(&self.z, N_tilde)
+            || !is_in_range(&self.w, N_tilde)
+            || !is_in_range(&self.u, NN)
+            || !is_in_range(&self.s, N)
+            || !is_in_range(cipher, NN)
+            || self.s1 < BigInt::zero()
+            || self.s1 > FE::q().pow(3)
+            || self.s2 < BigInt::zero()
+        {
+            return false;
+        }
+
+        let e = Self::challenge(alice_ek, dlog_statement, cipher, &self.z, &self.u, &self.w);
+
+        // Gamma^s1 * s^N == u * c^e mod N^2
+        let u_test = commitment_unknown_order(&(N + BigInt::one()), &self.s, NN, &self.s1, N);
+        let u_expected = BigInt::mod_mul(&self.u, &BigInt::mod_pow(cipher, &e, NN), NN);
+
+        // h1^s1 * h2^s2 == w * z^e mod N_tilde
+        let w_test = commitment_unknown_order(h1, h2, N_tilde, &self.s1, &self.s2);
+        let w_expected = BigInt::mod_mul(&self.w, &BigInt::mod_pow(&self.z, &e, N_tilde), N_tilde);
+
+        u_test == u_expected && w_test == w_expected
+    }
+
+    fn challenge(
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        cipher: &BigInt,
+        z: &BigInt,
+        u: &BigInt,
+        w: &BigInt,
+    ) -> BigInt {
+        HSha256::create_hash(&[
+            &alice_ek.n,
+            &dlog_statement.N,
+            &dlog_statement.g,
+            &dlog_statement.ni,
+            cipher,
+            z,
+            u,
+            w,
+        ])
+        .mod_floor(&FE::q())
+    }
+}
+
+/// Proof that Bob's ciphertext c2 = c1^b * Enc(beta_tag) with b in the range [0, q^3].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BobProof {
+    t: BigInt,
+    z: BigInt,
+    z_prim: BigInt,
+    v: BigInt,
+    w: BigInt,
+    s: BigInt,
+    s1: BigInt,
+    s2: BigInt,
+    t1: BigInt,
+    t2: BigInt,
+}
+
+/// BobProof together with the check that b is the discrete log of a public point X,
+/// used in MtAwc.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BobProofExt {
+    proof: BobProof,
+    u: GE,
+}
+
+impl BobProof {
+    /// b and beta_tag are the plaintexts Bob combined with c1 into c2, r the randomness used
+    /// to encrypt beta_tag.
+    pub fn generate(
+        c1: &BigInt,
+        c2: &BigInt,
+        b: &FE,
+        beta_tag: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &BigInt,
+    ) -> Self {
+        let (proof, _) =
+            Self::generate_with_check(c1, c2, b, beta_tag, alice_ek, dlog_statement, r, None);
+        proof
+    }
+
+    pub fn verify(
+        &self,
+        c1: &BigInt,
+        c2: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+    ) -> bool {
+        self.verify_with_check(c1, c2, alice_ek, dlog_statement, None)
+    }
+
+    // with X = g^b the proof also commits to u = g^alpha, which is returned
+    #[allow(clippy::too_many_arguments)]
+    fn generate_with_check(
+        c1: &BigInt,
+        c2: &BigInt,
+        b: &FE,
+        beta_tag: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &BigInt,
+        X: Option<&GE>,
+    ) -> (Self, Option<GE>) {
+        let (N_tilde, h1, h2) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let q = FE::q();
+        let q3 = q.pow(3);
+        let q_N_tilde = &q * N_tilde;
+        let one = BigInt::one();
+        let N = &alice_ek.n;
+        let NN = &alice_ek.nn;
+        let b_bn = b.to_big_int();
+
+        let alpha = BigInt::sample_below(&q3);
+        let rho = BigInt::sample_below(&q_N_tilde);
+        let rho_prim = BigInt::sample_below(&(&q3 * N_tilde));
+        let sigma = BigInt::sample_below(&q_N_tilde);
+        let beta = BigInt::sample_range(&one, &(N - &one));
+        let gamma = BigInt::sample_range(&one, &(N - &one));
+        let tau = BigInt::sample_below(&q_N_tilde);
+
+        let z = commitment_unknown_order(h1, h2, N_tilde, &b_bn, &rho);
+        let z_prim = commitment_unknown_order(h1, h2, N_tilde, &alpha, &rho_prim);
+        let t = commitment_unknown_order(h1, h2, N_tilde, beta_tag, &sigma);
+        let v = BigInt::mod_mul(
+            &BigInt::mod_pow(c1, &alpha, NN),
+            &commitment_unknown_order(&(N + &one), &beta, NN, &gamma, N),
+            NN,
+        );
+        let w = commitment_unknown_order(h1, h2, N_tilde, &gamma, &tau);
+
+        let alpha_fe: FE = ECScalar::from(&alpha);
+        let u = X.map(|_| GE::generator() * alpha_fe);
+        let check = X.and_then(|X| u.as_ref().map(|u| (X, u)));
+        let e = Self::challenge(
+            alice_ek,
+            dlog_statement,
+            c1,
+            c2,
+            &[&z, &z_prim, &t, &v, &w],
+            check,
+        );
+
+        let s = BigInt::mod_mul(&BigInt::mod_pow(r, &e, N), &beta, N);
+        let s1 = &e * &b_bn + alpha;
+        let s2 = &e * rho + rho_prim;
+        let t1 = &e * beta_tag + gamma;
+        let t2 = &e * sigma + tau;
+
+        (
+            BobProof {
+                t,
+                z,
+                z_prim,
+                v,
+                w,
+                s,
+                s1,
+                s2,
+                t1,
+                t2,
+            },
+            u,
+        )
+    }
+
+    fn verify_with_check(
+        &self,
+        c1: &BigInt,
+        c2: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        check: Option<(&GE, &GE)>,
+    ) -> bool {
+        let (N_tilde, h1, h2) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let N = &alice_ek.n;
+        let NN = &alice_ek.nn;
+
+        if !is_valid_statement(dlog_statement)
+            || ![&self.t, &self.z, &self.z_prim, &self.w]
+                .iter()
+                .all(|x| is_in_range(x, N_tilde))
+            || !is_in_range(&self.v, NN)
+            || !is_in_range(&self.s, N)
+            || !is_in_range(c1, NN)
+            || !is_in_range(c2, NN)
+            || self.s1 < BigInt::zero()
+            || self.s1 > FE::q().pow(3)
+            || [&self.s2, &self.t1, &self.t2]
+                .iter()
+                .any(|x| *x < &BigInt::zero())
+        {
+            return false;
+        }
+
+        let e = Self::challenge(
+            alice_ek,
+            dlog_statement,
+            c1,
+            c2,
+            &[&self.z, &self.z_prim, &self.t, &self.v, &self.w],
+            check,
+        );
+
+        // g^s1 == X^e * u
+        if let Some((X, u)) = check {
+            let e_fe: FE = ECScalar::from(&e);
+            let s1_fe: FE = ECScalar::from(&self.s1);
+            let g_s1 = GE::generator() * s1_fe;
+            if g_s1 != *X * e_fe + *u {
+                return false;
+            }
+        }
+
+        // h1^s1 * h2^s2 == z^e * z_prim mod N_tilde
+        let z_test = commitment_unknown_order(h1, h2, N_tilde, &self.s1, &self.s2);
+        let z_expected = BigInt::mod_mul(
+            &BigInt::mod_pow(&self.z, &e, N_tilde),
+            &self.z_prim,
+            N_tilde,
+        );
+
+        // h1^t1 * h2^t2 == t^e * w mod N_tilde
+        let t_test = commitment_unknown_order(h1, h2, N_tilde, &self.t1, &self.t2);
+        let t_expected = BigInt::mod_mul(&BigInt::mod_pow(&self.t, &e, N_tilde), &self.w, N_tilde);
+
+        // c1^s1 * s^N * Gamma^t1 == c2^e * v mod N^2
+        let v_test = BigInt::mod_mul(
+            &BigInt::mod_pow(c1, &self.s1, NN),
+            &commitment_unknown_order(&(N + BigInt::one()), &self.s, NN, &self.t1, N),
+            NN,
+        );
+        let v_expected = BigInt::mod_mul(&BigInt::mod_pow(c2, &e, NN), &self.v, NN);
+
+        z_test == z_expected && t_test == t_expected && v_test == v_expected
+    }
+
+    fn challenge(
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        c1: &BigInt,
+        c2: &BigInt,
+        commitments: &[&BigInt],
+        check: Option<(&GE, &GE)>,
+    ) -> BigInt {
+        let mut values = vec![
+            alice_ek.n.clone(),
+            dlog_statement.N.clone(),
+            dlog_statement.g.clone(),
+            dlog_statement.ni.clone(),
+            c1.clone(),
+            c2.clone(),
+        ];
+        values.extend(commitments.iter().map(|x| (*x).clone()));
+        if let Some((X, u)) = check {
+            values.push(X.bytes_compressed_to_big_int());
+            values.push(u.bytes_compressed_to_big_int());
+        }
+        HSha256::create_hash(&values.iter().collect::<Vec<&BigInt>>()).mod_floor(&FE::q())
+    }
+}
+
+impl BobProofExt {
+    /// Same as BobProof::generate, X = g^b is the public point b is checked against.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate(
+        c1: &BigInt,
+        c2: &BigInt,
+        b: &FE,
+        beta_tag: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &BigInt,
+        X: &GE,
+    ) -> Self {
+        let (proof, u) = BobProof::generate_with_check(
+            c1,
+            c2,
+            b,
+            beta_tag,
+            alice_ek,
+            dlog_statement,
+            r,
+            Some(X),
+        );
+        BobProofExt {
+            proof,
+            u: u.expect("u is computed when X is given"),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        c1: &BigInt,
+        c2: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        X: &GE,
+    ) -> bool {
+        self.proof
+            .verify_with_check(c1, c2, alice_ek, dlog_statement, Some((X, &self.u)))
+    }
+}
+
+fn is_in_range(x: &BigInt, modulus: &BigInt) -> bool {
+    x > &BigInt::zero() && x < modulus
+}
@@ -1,7 +1,10 @@
 use crate::utilities::mta::*;
-use curv::elliptic::curves::traits::ECScalar;
-use curv::FE;
+use curv::arithmetic::traits::{Modulo, Samplable};
+use curv::cryptographic_primitives::proofs::sigma_dlog::{DLogProof, ProveDLog};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
 use paillier::traits::KeyGeneration;
+use zk_paillier::zkproofs::DLogStatement;
 
 #[test]
 fn test_mta() {
@@ -18,3 +21,111 @@ fn test_mta() {
     let right = alice_input * bob_input;
     assert_eq!(left.get_element(), right.get_element());
 }
+
+fn generate_dlog_statement() -> DLogStatement {
+    let (ek_tilde, dk_tilde) = Paillier::keypair().keys();
+    let one = BigInt::one();
+    let phi = (&dk_tilde.p - &one) * (&dk_tilde.q - &one);
+    let h1 = BigInt::sample_below(&phi);
+    let xhi = BigInt::sample_below(&phi);
+    let h2 = BigInt::mod_pow(&h1, &xhi, &ek_tilde.n);
+    DLogStatement {
+        N: ek_tilde.n,
+        g: h1,
+        ni: h2,
+    }
+}
+
+#[test]
+fn test_mta_with_range_proofs() {
+    let alice_input: FE = ECScalar::new_random();
+    let (ek_alice, dk_alice) = Paillier::keypair().keys();
+    let alice_statement = generate_dlog_statement();
+    let bob_input: FE = ECScalar::new_random();
+    let bob_statement = generate_dlog_statement();
+
+    let (m_a, _r) = MessageAWithRangeProof::a(&alice_input, &ek_alice, &bob_statement);
+    let (m_b, beta, _) = MessageBWithRangeProof::b(
+        &bob_input,
+        &ek_alice,
+        &m_a,
+        &alice_statement,
+        &bob_statement,
+    )
+    .expect("bad range proof of alice");
+    let alpha = m_b
+        .verify_proofs_get_alpha(&dk_alice, &ek_alice, &m_a.c, &alice_statement)
+        .expect("bad range proof of bob");
+    assert_eq!(
+        (alpha + beta).get_element(),
+        (alice_input * bob_input).get_element()
+    );
+
+    // a proof without check is not accepted where MtAwc is expected
+    let g_b = GE::generator() * bob_input;
+    assert!(m_b
+        .verify_proofs_get_alpha_with_check(&dk_alice, &ek_alice, &m_a.c, &alice_statement, &g_b)
+        .is_err());
+
+    let (m_b, beta, _) = MessageBWithRangeProof::b_with_check(
+        &bob_input,
+        &ek_alice,
+        &m_a,
+        &alice_statement,
+        &bob_statement,
+    )
+    .expect("bad range proof of alice");
+    let alpha = m_b
+        .verify_proofs_get_alpha_with_check(&dk_alice, &ek_alice, &m_a.c, &alice_statement, &g_b)
+        .expect("bad range proof of bob");
+    assert_eq!(
+        (alpha + beta).get_element(),
+        (alice_input * bob_input).get_element()
+    );
+
+    let g_other = GE::generator() * alice_input;
+    assert!(m_b
+        .verify_proofs_get_alpha_with_check(
+            &dk_alice,
+            &ek_alice,
+            &m_a.c,
+            &alice_statement,
+            &g_other
+        )
+        .is_err());
+
+    // b_proof must reveal the same b the range proof is about
+    let mut m_b_other = m_b.clone();
+    m_b_other.b_proof = DLogProof::prove(&alice_input);
+    assert!(m_b_other
+        .verify_proofs_get_alpha_with_check(
+            &dk_alice,
+            &ek_alice,
+            &m_a.c,
+            &alice_statement,
+            &m_b_other.b_proof.pk
+        )
+        .is_err());
+}
+
+#[test]
+fn test_mta_range_proof_rejects_wrong_statement() {
+    let alice_input: FE = ECScalar::new_random();
+    let (ek_alice, _) = Paillier::keypair().keys();
+    let bob_statement = generate_dlog_statement();
+    let other_statement = generate_dlog_statement();
+
+    let (m_a, _r) = MessageAWithRangeProof::a(&alice_input, &ek_alice, &other_statement);
+    assert!(m_a.verify(&ek_alice, &bob_statement).is_err());
+
+    let bob_input: FE = ECScalar::new_random();
+    let err = MessageBWithRangeProof::b(
+        &bob_input,
+        &ek_alice,
+        &m_a,
+        &other_statement,
+        &bob_statement,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind, crate::ErrorKind::InvalidRangeProof);
+}
@@ -0,0 +1,69 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Ring-Pedersen parameters (N_tilde, h1, h2), given as a DLogStatement
+//! { N: N_tilde, g: h1, ni: h2 }. The commitments of the range and no small factor proofs
+//! are binding only if h1 and h2 generate the same group, which is proven in both directions
+//! with composite dlog proofs.
+
+use curv::BigInt;
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement};
+
+/// Proves h2 = h1^xhi and h1 = h2^xhi_inv mod N_tilde.
+pub fn prove(
+    statement: &DLogStatement,
+    xhi: &BigInt,
+    xhi_inv: &BigInt,
+) -> (CompositeDLogProof, CompositeDLogProof) {
+    let proof_base_h1 = CompositeDLogProof::prove(statement, xhi);
+    let proof_base_h2 = CompositeDLogProof::prove(&swap_bases(statement), xhi_inv);
+    (proof_base_h1, proof_base_h2)
+}
+
+/// Checks the proofs made by prove, after rejecting degenerate parameters.
+pub fn verify(
+    statement: &DLogStatement,
+    proof_base_h1: &CompositeDLogProof,
+    proof_base_h2: &CompositeDLogProof,
+) -> bool {
+    is_valid_statement(statement)
+        && proof_base_h1.verify(statement).is_ok()
+        && proof_base_h2.verify(&swap_bases(statement)).is_ok()
+}
+
+/// Rejects degenerate parameters, for which the commitments are not binding.
+pub fn is_valid_statement(statement: &DLogStatement) -> bool {
+    let one = BigInt::one();
+    let N_tilde = &statement.N;
+    N_tilde > &one
+        && statement.g > one
+        && &statement.g < N_tilde
+        && statement.ni > one
+        && &statement.ni < N_tilde
+        && statement.g != statement.ni
+}
+
+fn swap_bases(statement: &DLogStatement) -> DLogStatement {
+    DLogStatement {
+        N: statement.N.clone(),
+        g: statement.ni.clone(),
+        ni: statement.g.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,32 @@
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::generate_h1_h2_N_tilde;
+use crate::utilities::ring_pedersen::*;
+use curv::BigInt;
+use paillier::traits::KeyGeneration;
+use paillier::Paillier;
+use zk_paillier::zkproofs::DLogStatement;
+
+#[test]
+fn test_ring_pedersen_proofs() {
+    let (n_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_N_tilde(Paillier::keypair().keys());
+    let statement = DLogStatement {
+        N: n_tilde,
+        g: h1,
+        ni: h2,
+    };
+    let (proof_base_h1, proof_base_h2) = prove(&statement, &xhi, &xhi_inv);
+    assert!(verify(&statement, &proof_base_h1, &proof_base_h2));
+    assert!(!verify(&statement, &proof_base_h2, &proof_base_h1));
+
+    let degenerate = DLogStatement {
+        N: statement.N.clone(),
+        g: statement.g.clone(),
+        ni: statement.g.clone(),
+    };
+    assert!(!is_valid_statement(&degenerate));
+    let degenerate = DLogStatement {
+        N: statement.N.clone(),
+        g: BigInt::one(),
+        ni: statement.ni.clone(),
+    };
+    assert!(!is_valid_statement(&degenerate));
+}
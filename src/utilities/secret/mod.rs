@@ -0,0 +1,38 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Helpers for the types that hold secrets. Such types implement Zeroize, zeroize themselves
+//! on drop, and print Redacted in place of their secret fields.
+
+use std::fmt;
+
+use curv::arithmetic::traits::ZeroizeBN;
+use paillier::DecryptionKey;
+
+/// Debug output of a secret field.
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+/// Overwrites the factors of a Paillier decryption key.
+pub fn zeroize_decryption_key(dk: &mut DecryptionKey) {
+    dk.p.zeroize_bn();
+    dk.q.zeroize_bn();
+}
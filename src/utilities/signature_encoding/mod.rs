@@ -0,0 +1,133 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Standard encodings of secp256k1 ECDSA signatures: DER, the 64 byte compact r || s, the 65 byte
+//! recoverable r || s || recid, and the signature and recovery id types of libsecp256k1. The
+//! encoders expect r and s below the group order, as every signing protocol of the crate outputs
+//! them. The parsers reject r or s that are zero or not below the group order, and recovery ids
+//! above 3. Public key recovery from (r, s, recid) lives here as well.
+
+use curv::arithmetic::traits::{Converter, Modulo};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use secp256k1::{Message, RecoveryId, Signature};
+
+use crate::Error;
+use crate::ErrorKind::{InvalidEncoding, InvalidSignature};
+use crate::Protocol::SignatureEncoding;
+
+pub const COMPACT_SIGNATURE_SIZE: usize = 64;
+pub const RECOVERABLE_SIGNATURE_SIZE: usize = 65;
+
+/// DER encoding of (r, s), with the minimal integer lengths of BIP66.
+pub fn to_der(r: &BigInt, s: &BigInt) -> Vec<u8> {
+    to_libsecp256k1(r, s).serialize_der().as_ref().to_vec()
+}
+
+pub fn from_der(bytes: &[u8]) -> Result<(BigInt, BigInt), Error> {
+    let signature = Signature::parse_der(bytes).map_err(|_| invalid("der"))?;
+    parse_compact(&signature.serialize(), "der")
+}
+
+/// r || s, each as 32 big endian bytes.
+pub fn to_compact(r: &BigInt, s: &BigInt) -> [u8; COMPACT_SIGNATURE_SIZE] {
+    let mut out = [0u8; COMPACT_SIGNATURE_SIZE];
+    out[..32].copy_from_slice(&to_bytes32(r));
+    out[32..].copy_from_slice(&to_bytes32(s));
+    out
+}
+
+pub fn from_compact(bytes: &[u8]) -> Result<(BigInt, BigInt), Error> {
+    if bytes.len() != COMPACT_SIGNATURE_SIZE {
+        return Err(invalid("compact"));
+    }
+    parse_compact(bytes, "compact")
+}
+
+/// r || s || recid, with the recovery id in 0..=3 (add 27 for the ethereum `v`).
+pub fn to_recoverable(r: &BigInt, s: &BigInt, recid: u8) -> [u8; RECOVERABLE_SIGNATURE_SIZE] {
+    let mut out = [0u8; RECOVERABLE_SIGNATURE_SIZE];
+    out[..COMPACT_SIGNATURE_SIZE].copy_from_slice(&to_compact(r, s));
+    out[COMPACT_SIGNATURE_SIZE] = recid;
+    out
+}
+
+pub fn from_recoverable(bytes: &[u8]) -> Result<(BigInt, BigInt, u8), Error> {
+    if bytes.len() != RECOVERABLE_SIGNATURE_SIZE {
+        return Err(invalid("recoverable"));
+    }
+    let recid = bytes[COMPACT_SIGNATURE_SIZE];
+    if recid > 3 {
+        return Err(invalid("recoverable"));
+    }
+    let (r, s) = parse_compact(&bytes[..COMPACT_SIGNATURE_SIZE], "recoverable")?;
+    Ok((r, s, recid))
+}
+
+pub fn to_libsecp256k1(r: &BigInt, s: &BigInt) -> Signature {
+    Signature::parse(&to_compact(r, s))
+}
+
+pub fn from_libsecp256k1(signature: &Signature) -> Result<(BigInt, BigInt), Error> {
+    parse_compact(&signature.serialize(), "libsecp256k1")
+}
+
+pub fn to_recovery_id(recid: u8) -> Result<RecoveryId, Error> {
+    RecoveryId::parse(recid).map_err(|_| invalid("libsecp256k1"))
+}
+
+/// The public key for which (r, s) is a signature of message, picked among the up to four
+/// candidates by recid. The message is reduced mod q, as in the verify functions of the crate.
+pub fn recover_public_key(
+    r: &BigInt,
+    s: &BigInt,
+    recid: u8,
+    message: &BigInt,
+) -> Result<GE, Error> {
+    let signature = to_libsecp256k1(r, s);
+    let recid = to_recovery_id(recid)?;
+    let message = Message::parse(&to_bytes32(&message.modulus(&FE::q())));
+    let public_key = secp256k1::recover(&message, &signature, &recid)
+        .map_err(|_| Error::new(SignatureEncoding, "recover", InvalidSignature))?;
+    let bytes = public_key.serialize();
+    let x = BigInt::from(&bytes[1..33]);
+    let y = BigInt::from(&bytes[33..65]);
+    Ok(GE::from_coor(&x, &y))
+}
+
+fn parse_compact(bytes: &[u8], round: &'static str) -> Result<(BigInt, BigInt), Error> {
+    let r = BigInt::from(&bytes[..32]);
+    let s = BigInt::from(&bytes[32..COMPACT_SIGNATURE_SIZE]);
+    let q = FE::q();
+    if r == BigInt::zero() || s == BigInt::zero() || r >= q || s >= q {
+        return Err(invalid(round));
+    }
+    Ok((r, s))
+}
+
+fn to_bytes32(n: &BigInt) -> [u8; 32] {
+    let v = BigInt::to_vec(n);
+    let mut out = [0u8; 32];
+    out[32 - v.len()..].copy_from_slice(&v);
+    out
+}
+
+fn invalid(round: &'static str) -> Error {
+    Error::new(SignatureEncoding, round, InvalidEncoding)
+}
+
+#[cfg(test)]
+mod test;
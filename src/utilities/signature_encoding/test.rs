@@ -0,0 +1,86 @@
+use crate::utilities::signature_encoding::*;
+use curv::arithmetic::traits::Samplable;
+use curv::elliptic::curves::traits::*;
+use curv::{BigInt, FE};
+
+fn random_signature() -> (BigInt, BigInt) {
+    let r: FE = ECScalar::new_random();
+    let s: FE = ECScalar::new_random();
+    (r.to_big_int(), s.to_big_int())
+}
+
+#[test]
+fn test_encodings_round_trip() {
+    let (r, s) = random_signature();
+
+    assert_eq!(from_der(&to_der(&r, &s)).unwrap(), (r.clone(), s.clone()));
+    assert_eq!(
+        from_compact(&to_compact(&r, &s)).unwrap(),
+        (r.clone(), s.clone())
+    );
+    assert_eq!(
+        from_recoverable(&to_recoverable(&r, &s, 3)).unwrap(),
+        (r.clone(), s.clone(), 3)
+    );
+    assert_eq!(
+        from_libsecp256k1(&to_libsecp256k1(&r, &s)).unwrap(),
+        (r.clone(), s.clone())
+    );
+    assert_eq!(to_recovery_id(2).unwrap().serialize(), 2);
+}
+
+#[test]
+fn test_der_integers_are_minimal() {
+    let der = to_der(&BigInt::one(), &BigInt::from(0x80));
+    assert_eq!(
+        der,
+        vec![0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80]
+    );
+
+    let (r, s) = random_signature();
+    let mut der = to_der(&r, &s);
+    der.push(0);
+    assert!(from_der(&der).is_err());
+}
+
+#[test]
+fn test_out_of_range_values_are_rejected() {
+    let (r, s) = random_signature();
+    let q = FE::q();
+
+    assert!(from_compact(&to_compact(&r, &BigInt::zero())).is_err());
+    assert!(from_compact(&to_compact(&q, &s)).is_err());
+    assert!(from_der(&to_der(&BigInt::zero(), &s)).is_err());
+    assert!(from_compact(&to_recoverable(&r, &s, 0)).is_err());
+    assert!(from_recoverable(&to_recoverable(&r, &s, 4)).is_err());
+    assert!(to_recovery_id(4).is_err());
+
+    let overflowing = BigInt::sample_range(&q, &(BigInt::one() << 256));
+    assert!(from_compact(&to_compact(&r, &overflowing)).is_err());
+}
+
+#[test]
+fn test_recover_public_key() {
+    use curv::arithmetic::traits::Modulo;
+    use curv::GE;
+
+    let x: FE = ECScalar::new_random();
+    let k: FE = ECScalar::new_random();
+    let message = BigInt::from(1234);
+    let q = FE::q();
+
+    let big_r = GE::generator() * k;
+    let r = big_r.x_coor().unwrap().mod_floor(&q);
+    let k_inv = k.to_big_int().invert(&q).unwrap();
+    let mut s = BigInt::mod_mul(&k_inv, &(message.clone() + &r * &x.to_big_int()), &q);
+    let mut recid = big_r.y_coor().unwrap().tstbit(0) as u8;
+    if s > &q - &s {
+        s = &q - &s;
+        recid ^= 1;
+    }
+
+    let y = GE::generator() * x;
+    assert_eq!(recover_public_key(&r, &s, recid, &message).unwrap(), y);
+    assert_ne!(recover_public_key(&r, &s, recid ^ 1, &message).unwrap(), y);
+    assert!(recover_public_key(&r, &s, 4, &message).is_err());
+}
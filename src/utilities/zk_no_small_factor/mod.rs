@@ -0,0 +1,196 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! No small factor proof, as given in figure 28 of https://eprint.iacr.org/2021/060.pdf
+//! Statement: (N0, N_tilde, h1, h2)
+//! witness (p, q) such that N0 = pq and p, q > sqrt(N0) / 2^(L + EPSILON).
+//! The proof commits with the ring-Pedersen parameters (N_tilde, h1, h2) of the verifier,
+//! given as a DLogStatement { N: N_tilde, g: h1, ni: h2 }. The verifier's parameters must be
+//! checked (e.g. by composite dlog proofs) before proving, otherwise the proof may leak p.
+//! note that the masks are sampled from non negative ranges, so the challenge is taken in [0, q).
+
+use curv::arithmetic::traits::{Modulo, Samplable};
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::elliptic::curves::traits::ECScalar;
+use curv::{BigInt, FE};
+use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::Error;
+use crate::ErrorKind::InvalidNoSmallFactorProof;
+use crate::Protocol::NoSmallFactor;
+
+const L: u32 = 256;
+const EPSILON: u32 = 512;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoSmallFactorProof {
+    P: BigInt,
+    Q: BigInt,
+    A: BigInt,
+    B: BigInt,
+    T: BigInt,
+    sigma: BigInt,
+    z1: BigInt,
+    z2: BigInt,
+    w1: BigInt,
+    w2: BigInt,
+    v: BigInt,
+}
+
+impl NoSmallFactorProof {
+    pub fn prove(dk: &DecryptionKey, dlog_statement: &DLogStatement) -> Self {
+        let (N_tilde, s, t) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let (p, q) = (&dk.p, &dk.q);
+        let N0 = p * q;
+        let two_l = BigInt::from(2).pow(L);
+        let two_l_e = BigInt::from(2).pow(L + EPSILON);
+        let sqrt_N0 = N0.sqrt();
+
+        let alpha = BigInt::sample_below(&(&two_l_e * &sqrt_N0));
+        let beta = BigInt::sample_below(&(&two_l_e * &sqrt_N0));
+        let mu = BigInt::sample_below(&(&two_l * N_tilde));
+        let nu = BigInt::sample_below(&(&two_l * N_tilde));
+        let sigma = BigInt::sample_below(&(&two_l * &N0 * N_tilde));
+        let r = BigInt::sample_below(&(&two_l_e * &N0 * N_tilde));
+        let x = BigInt::sample_below(&(&two_l_e * N_tilde));
+        let y = BigInt::sample_below(&(&two_l_e * N_tilde));
+
+        let P = commit(s, t, N_tilde, p, &mu);
+        let Q = commit(s, t, N_tilde, q, &nu);
+        let A = commit(s, t, N_tilde, &alpha, &x);
+        let B = commit(s, t, N_tilde, &beta, &y);
+        let T = commit(&Q, t, N_tilde, &alpha, &r);
+
+        let e = challenge(&N0, dlog_statement, &[&P, &Q, &A, &B, &T, &sigma]);
+
+        let sigma_hat = &sigma - &nu * p;
+        let z1 = alpha + &e * p;
+        let z2 = beta + &e * q;
+        let w1 = x + &e * mu;
+        let w2 = y + &e * nu;
+        let v = r + &e * sigma_hat;
+
+        NoSmallFactorProof {
+            P,
+            Q,
+            A,
+            B,
+            T,
+            sigma,
+            z1,
+            z2,
+            w1,
+            w2,
+            v,
+        }
+    }
+
+    pub fn verify(&self, ek: &EncryptionKey, dlog_statement: &DLogStatement) -> Result<(), Error> {
+        let (N_tilde, s, t) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let N0 = &ek.n;
+        let err = Error::new(NoSmallFactor, "verify", InvalidNoSmallFactorProof);
+
+        // the honest z1, z2 are below 2^(L + EPSILON) * sqrt(N0) + 2^L * p
+        let bound = BigInt::from(2).pow(L + EPSILON + 1) * N0.sqrt();
+        let one = BigInt::one();
+        if N_tilde <= &one
+            || ![&self.P, &self.Q, &self.A, &self.B, &self.T, s, t]
+                .iter()
+                .all(|x| x > &&one && x < &N_tilde)
+            || self.z1 < BigInt::zero()
+            || self.z1 > bound
+            || self.z2 < BigInt::zero()
+            || self.z2 > bound
+        {
+            return Err(err);
+        }
+
+        let e = challenge(
+            N0,
+            dlog_statement,
+            &[&self.P, &self.Q, &self.A, &self.B, &self.T, &self.sigma],
+        );
+
+        if self.check_equations(N0, dlog_statement, &e) == Some(true) {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    // s^z1 t^w1 == A P^e, s^z2 t^w2 == B Q^e and Q^z1 t^v == T R^e with R = s^N0 t^sigma,
+    // None if an exponent is negative and its base is not invertible
+    fn check_equations(
+        &self,
+        N0: &BigInt,
+        dlog_statement: &DLogStatement,
+        e: &BigInt,
+    ) -> Option<bool> {
+        let (N_tilde, s, t) = (&dlog_statement.N, &dlog_statement.g, &dlog_statement.ni);
+        let R = commit_signed(s, t, N_tilde, N0, &self.sigma)?;
+        let checks = [
+            (
+                commit_signed(s, t, N_tilde, &self.z1, &self.w1)?,
+                BigInt::mod_mul(&self.A, &BigInt::mod_pow(&self.P, e, N_tilde), N_tilde),
+            ),
+            (
+                commit_signed(s, t, N_tilde, &self.z2, &self.w2)?,
+                BigInt::mod_mul(&self.B, &BigInt::mod_pow(&self.Q, e, N_tilde), N_tilde),
+            ),
+            (
+                commit_signed(&self.Q, t, N_tilde, &self.z1, &self.v)?,
+                BigInt::mod_mul(&self.T, &BigInt::mod_pow(&R, e, N_tilde), N_tilde),
+            ),
+        ];
+        Some(checks.iter().all(|(left, right)| left == right))
+    }
+}
+
+fn challenge(N0: &BigInt, dlog_statement: &DLogStatement, values: &[&BigInt]) -> BigInt {
+    let mut input = vec![N0, &dlog_statement.N, &dlog_statement.g, &dlog_statement.ni];
+    input.extend_from_slice(values);
+    HSha256::create_hash(&input).mod_floor(&FE::q())
+}
+
+// g^x h^r mod N for non negative exponents
+fn commit(g: &BigInt, h: &BigInt, N: &BigInt, x: &BigInt, r: &BigInt) -> BigInt {
+    BigInt::mod_mul(&BigInt::mod_pow(g, x, N), &BigInt::mod_pow(h, r, N), N)
+}
+
+// commit for exponents of any sign, None if a base is not invertible
+fn commit_signed(g: &BigInt, h: &BigInt, N: &BigInt, x: &BigInt, r: &BigInt) -> Option<BigInt> {
+    Some(BigInt::mod_mul(
+        &mod_pow_signed(g, x, N)?,
+        &mod_pow_signed(h, r, N)?,
+        N,
+    ))
+}
+
+fn mod_pow_signed(base: &BigInt, exp: &BigInt, N: &BigInt) -> Option<BigInt> {
+    if exp < &BigInt::zero() {
+        let base_inv = base.invert(N)?;
+        Some(BigInt::mod_pow(&base_inv, &(-exp), N))
+    } else {
+        Some(BigInt::mod_pow(base, exp, N))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,41 @@
+#![allow(non_snake_case)]
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::generate_h1_h2_N_tilde;
+use crate::utilities::zk_no_small_factor::*;
+use curv::BigInt;
+use paillier::traits::KeyGeneration;
+use paillier::{Keypair, Paillier};
+use zk_paillier::zkproofs::DLogStatement;
+
+fn generate_dlog_statement() -> DLogStatement {
+    let (N_tilde, h1, h2, _, _) = generate_h1_h2_N_tilde(Paillier::keypair().keys());
+    DLogStatement {
+        N: N_tilde,
+        g: h1,
+        ni: h2,
+    }
+}
+
+#[test]
+fn test_no_small_factor_proof() {
+    let (ek, dk) = Paillier::keypair().keys();
+    let dlog_statement = generate_dlog_statement();
+    let proof = NoSmallFactorProof::prove(&dk, &dlog_statement);
+    assert!(proof.verify(&ek, &dlog_statement).is_ok());
+
+    let other_statement = generate_dlog_statement();
+    assert!(proof.verify(&ek, &other_statement).is_err());
+}
+
+#[test]
+fn test_no_small_factor_proof_rejects_small_factor() {
+    // N = 3 * p for a 2045 bit prime p
+    let p = (BigInt::from(2).pow(2044) + BigInt::from(3)).nextprime();
+    let keypair = Keypair {
+        p: BigInt::from(3),
+        q: p,
+    };
+    let (ek, dk) = keypair.keys();
+    let dlog_statement = generate_dlog_statement();
+    let proof = NoSmallFactorProof::prove(&dk, &dlog_statement);
+    assert!(proof.verify(&ek, &dlog_statement).is_err());
+}
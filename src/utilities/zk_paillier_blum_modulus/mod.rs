@@ -0,0 +1,215 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Paillier-Blum modulus proof, as given in figure 16 of https://eprint.iacr.org/2021/060.pdf
+//! Statement: N
+//! witness (p, q) such that N = pq and p = q = 3 mod 4.
+//! note that the z_i of the paper, which show gcd(N, phi(N)) = 1, are left out: this is
+//! exactly what NICorrectKeyProof proves, so the two proofs are meant to be sent together.
+
+use curv::arithmetic::traits::{Modulo, Samplable};
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::BigInt;
+use paillier::{DecryptionKey, EncryptionKey, KeyGeneration, Paillier};
+use serde::{Deserialize, Serialize};
+use std::ops::Shl;
+
+use crate::Error;
+use crate::ErrorKind::InvalidPaillierBlumModulusProof;
+use crate::Protocol::PaillierBlumModulus;
+
+const ITERATIONS: usize = 80;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierBlumModulusProof {
+    w: BigInt,
+    x_vec: Vec<BigInt>,
+    a_vec: Vec<bool>,
+    b_vec: Vec<bool>,
+}
+
+impl PaillierBlumModulusProof {
+    /// dk must hold primes that are 3 mod 4, see generate_blum_keypair.
+    pub fn prove(dk: &DecryptionKey) -> Self {
+        let (p, q) = (&dk.p, &dk.q);
+        let N = p * q;
+        let w = loop {
+            let w = BigInt::sample_below(&N);
+            if jacobi(&w, &N) == -1 {
+                break w;
+            }
+        };
+
+        let p_exp = fourth_root_exponent(p);
+        let q_exp = fourth_root_exponent(q);
+        let q_inv_p = q.invert(p).expect("p and q are coprime");
+        let p_inv_q = p.invert(q).expect("p and q are coprime");
+
+        let mut x_vec = Vec::with_capacity(ITERATIONS);
+        let mut a_vec = Vec::with_capacity(ITERATIONS);
+        let mut b_vec = Vec::with_capacity(ITERATIONS);
+        for i in 0..ITERATIONS {
+            let y = challenge(&N, &w, i);
+            // exactly one of the four candidates is a square modulo both primes
+            let (a, b, y_tag) = [(false, false), (true, false), (false, true), (true, true)]
+                .iter()
+                .map(|&(a, b)| (a, b, twist(&y, &w, &N, a, b)))
+                .find(|(_, _, y_tag)| {
+                    is_square_mod_prime(y_tag, p) && is_square_mod_prime(y_tag, q)
+                })
+                .unwrap_or_else(|| (false, false, y.clone()));
+
+            let x_p = BigInt::mod_pow(&y_tag, &p_exp, p);
+            let x_q = BigInt::mod_pow(&y_tag, &q_exp, q);
+            let x = (x_p * q * &q_inv_p + x_q * p * &p_inv_q).mod_floor(&N);
+
+            x_vec.push(x);
+            a_vec.push(a);
+            b_vec.push(b);
+        }
+
+        PaillierBlumModulusProof {
+            w,
+            x_vec,
+            a_vec,
+            b_vec,
+        }
+    }
+
+    pub fn verify(&self, ek: &EncryptionKey) -> Result<(), Error> {
+        let N = &ek.n;
+        let one = BigInt::one();
+        let two = BigInt::from(2);
+        let four = BigInt::from(4);
+        let err = Error::new(
+            PaillierBlumModulus,
+            "verify",
+            InvalidPaillierBlumModulusProof,
+        );
+
+        // N must be odd and composite
+        if !N.tstbit(0)
+            || N <= &one
+            || BigInt::mod_pow(&two, &(N - &one), N) == one
+            || self.w <= BigInt::zero()
+            || &self.w >= N
+            || jacobi(&self.w, N) != -1
+            || self.x_vec.len() != ITERATIONS
+            || self.a_vec.len() != ITERATIONS
+            || self.b_vec.len() != ITERATIONS
+        {
+            return Err(err);
+        }
+
+        let valid = (0..ITERATIONS).all(|i| {
+            let y = challenge(N, &self.w, i);
+            let y_tag = twist(&y, &self.w, N, self.a_vec[i], self.b_vec[i]);
+            let x = &self.x_vec[i];
+            x > &BigInt::zero() && x < N && BigInt::mod_pow(x, &four, N) == y_tag
+        });
+        if valid {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+}
+
+/// Paillier key pair whose primes are both 3 mod 4, as the proof requires. Safe primes
+/// always are, see Paillier::keypair_safe_primes.
+pub fn generate_blum_keypair() -> (EncryptionKey, DecryptionKey) {
+    let three = BigInt::from(3);
+    let four = BigInt::from(4);
+    loop {
+        let (ek, dk) = Paillier::keypair().keys();
+        if dk.p.mod_floor(&four) == three && dk.q.mod_floor(&four) == three {
+            return (ek, dk);
+        }
+    }
+}
+
+// y_i are derived from N and w by hashing, with enough output to be close to uniform mod N
+fn challenge(N: &BigInt, w: &BigInt, i: usize) -> BigInt {
+    let blocks = N.bit_length() / 256 + 2;
+    (0..blocks)
+        .fold(BigInt::zero(), |acc, j| {
+            let h = HSha256::create_hash(&[N, w, &BigInt::from(i as u64), &BigInt::from(j as u64)]);
+            acc.shl(256) + h
+        })
+        .mod_floor(N)
+}
+
+// (-1)^a * w^b * y mod N
+fn twist(y: &BigInt, w: &BigInt, N: &BigInt, a: bool, b: bool) -> BigInt {
+    let y = if b {
+        BigInt::mod_mul(y, w, N)
+    } else {
+        y.clone()
+    };
+    if a {
+        (N - y).mod_floor(N)
+    } else {
+        y
+    }
+}
+
+// for p = 3 mod 4, y^((p + 1) / 4) is the square root of y that is itself a square
+fn fourth_root_exponent(p: &BigInt) -> BigInt {
+    let e = (p + BigInt::one()) / BigInt::from(4);
+    (&e * &e).mod_floor(&(p - BigInt::one()))
+}
+
+fn is_square_mod_prime(y: &BigInt, p: &BigInt) -> bool {
+    let e = (p - BigInt::one()) / BigInt::from(2);
+    BigInt::mod_pow(y, &e, p) == BigInt::one()
+}
+
+// Jacobi symbol (a / n) for odd n
+fn jacobi(a: &BigInt, n: &BigInt) -> i8 {
+    let (three, four, five, eight) = (
+        BigInt::from(3),
+        BigInt::from(4),
+        BigInt::from(5),
+        BigInt::from(8),
+    );
+    let mut a = a.mod_floor(n);
+    let mut n = n.clone();
+    let mut t = 1;
+    while a != BigInt::zero() {
+        while !a.tstbit(0) {
+            a = a / BigInt::from(2);
+            let r = n.mod_floor(&eight);
+            if r == three || r == five {
+                t = -t;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a.mod_floor(&four) == three && n.mod_floor(&four) == three {
+            t = -t;
+        }
+        a = a.mod_floor(&n);
+    }
+    if n == BigInt::one() {
+        t
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,26 @@
+use crate::utilities::zk_paillier_blum_modulus::*;
+use curv::BigInt;
+use paillier::traits::KeyGeneration;
+use paillier::{Keypair, Paillier};
+
+#[test]
+fn test_paillier_blum_modulus_proof() {
+    let (ek, dk) = generate_blum_keypair();
+    let proof = PaillierBlumModulusProof::prove(&dk);
+    assert!(proof.verify(&ek).is_ok());
+
+    let (other_ek, _) = Paillier::keypair().keys();
+    assert!(proof.verify(&other_ek).is_err());
+}
+
+#[test]
+fn test_paillier_blum_modulus_proof_rejects_non_blum_modulus() {
+    // 7 = 3 mod 4 but 13 = 1 mod 4
+    let keypair = Keypair {
+        p: BigInt::from(7),
+        q: BigInt::from(13),
+    };
+    let (ek, dk) = keypair.keys();
+    let proof = PaillierBlumModulusProof::prove(&dk);
+    assert!(proof.verify(&ek).is_err());
+}
@@ -15,11 +15,12 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 
-/// We use the proof as given in protocol 6.1 in https://eprint.iacr.org/2017/552.pdf
-/// Statement: (c, pk, Q, G)
-/// witness (x, r, sk) such that Q = xG, c = Enc(pk, x, r) and Dec(sk, c) = x.
-/// note that because of the range proof, the proof is sound only for x < q/3
-use curv::arithmetic::traits::Samplable;
+//! We use the proof as given in protocol 6.1 in https://eprint.iacr.org/2017/552.pdf
+//! Statement: (c, pk, Q, G)
+//! witness (x, r, sk) such that Q = xG, c = Enc(pk, x, r) and Dec(sk, c) = x.
+//! note that because of the range proof, the proof is sound only for x < q/3
+
+use curv::arithmetic::traits::{Samplable, ZeroizeBN};
 use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
 use curv::cryptographic_primitives::commitments::traits::Commitment;
 use curv::elliptic::curves::traits::ECPoint;
@@ -29,8 +30,15 @@ use paillier::Paillier;
 use paillier::{Add, Decrypt, Encrypt, Mul};
 use paillier::{DecryptionKey, EncryptionKey, RawCiphertext, RawPlaintext};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::ops::Shl;
+use zeroize::Zeroize;
 use zk_paillier::zkproofs::RangeProofError;
+
+use crate::utilities::secret::{zeroize_decryption_key, Redacted};
+use crate::Error;
+use crate::ErrorKind::{InvalidCommitment, InvalidPdlProof, InvalidRangeProof};
+use crate::Protocol::Pdl;
 use zk_paillier::zkproofs::RangeProofNi;
 
 #[derive(Clone)]
@@ -47,7 +55,31 @@ pub struct PDLWitness {
     pub dk: DecryptionKey,
 }
 
-#[derive(Debug, Clone)]
+impl fmt::Debug for PDLWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PDLWitness")
+            .field("x", &Redacted)
+            .field("r", &Redacted)
+            .field("dk", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for PDLWitness {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+        self.r.zeroize_bn();
+        zeroize_decryption_key(&mut self.dk);
+    }
+}
+
+impl Drop for PDLWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone)]
 pub struct PDLVerifierState {
     pub c_tag: BigInt,
     pub c_tag_tag: BigInt,
@@ -58,12 +90,61 @@ pub struct PDLVerifierState {
     c_hat: BigInt,
 }
 
-#[derive(Debug, Clone)]
+impl fmt::Debug for PDLVerifierState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PDLVerifierState")
+            .field("c_tag", &self.c_tag)
+            .field("c_tag_tag", &self.c_tag_tag)
+            .field("a", &Redacted)
+            .field("b", &Redacted)
+            .field("blindness", &Redacted)
+            .field("q_tag", &self.q_tag)
+            .field("c_hat", &self.c_hat)
+            .finish()
+    }
+}
+
+impl Zeroize for PDLVerifierState {
+    fn zeroize(&mut self) {
+        self.a.zeroize_bn();
+        self.b.zeroize_bn();
+        self.blindness.zeroize_bn();
+    }
+}
+
+impl Drop for PDLVerifierState {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone)]
 pub struct PDLProverState {
     pub decommit: PDLProverDecommit,
     pub alpha: BigInt,
 }
 
+impl fmt::Debug for PDLProverState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PDLProverState")
+            .field("decommit", &self.decommit)
+            .field("alpha", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for PDLProverState {
+    fn zeroize(&mut self) {
+        self.alpha.zeroize_bn();
+    }
+}
+
+impl Drop for PDLProverState {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PDLVerifierFirstMessage {
     pub c_tag: BigInt,
@@ -139,7 +220,7 @@ impl Verifier {
         prover_first_messasge: &PDLProverFirstMessage,
         statement: &PDLStatement,
         state: &mut PDLVerifierState,
-    ) -> Result<PDLVerifierSecondMessage, ()> {
+    ) -> Result<PDLVerifierSecondMessage, Error> {
         let decommit_message = PDLVerifierSecondMessage {
             a: state.a.clone(),
             b: state.b.clone(),
@@ -151,7 +232,7 @@ impl Verifier {
         if range_proof_is_ok {
             Ok(decommit_message)
         } else {
-            Err(())
+            Err(Error::new(Pdl, "verifier message 2", InvalidRangeProof))
         }
     }
 
@@ -159,7 +240,7 @@ impl Verifier {
         prover_first_message: &PDLProverFirstMessage,
         prover_second_message: &PDLProverSecondMessage,
         state: &PDLVerifierState,
-    ) -> Result<(), ()> {
+    ) -> Result<(), Error> {
         let c_hat_test = HashCommitment::create_commitment_with_user_defined_randomness(
             &prover_second_message
                 .decommit
@@ -173,7 +254,7 @@ impl Verifier {
         {
             Ok(())
         } else {
-            Err(())
+            Err(Error::new(Pdl, "finalize", InvalidPdlProof))
         }
     }
 }
@@ -209,7 +290,7 @@ impl Prover {
         verifier_second_message: &PDLVerifierSecondMessage,
         witness: &PDLWitness,
         state: &PDLProverState,
-    ) -> Result<PDLProverSecondMessage, ()> {
+    ) -> Result<PDLProverSecondMessage, Error> {
         let ab_concat = &verifier_second_message.a
             + verifier_second_message
                 .b
@@ -226,7 +307,7 @@ impl Prover {
                 decommit: state.decommit.clone(),
             })
         } else {
-            Err(())
+            Err(Error::new(Pdl, "prover message 2", InvalidCommitment))
         }
     }
 }
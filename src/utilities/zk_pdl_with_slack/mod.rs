@@ -15,20 +15,29 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 
-use curv::arithmetic::traits::Modulo;
-/// We use the proof as given in proof PIi in https://eprint.iacr.org/2016/013.pdf.
-/// This proof ws taken from the proof 6.3 (left side ) in https://www.cs.unc.edu/~reiter/papers/2004/IJIS.pdf
+//! We use the proof as given in proof PIi in https://eprint.iacr.org/2016/013.pdf.
+//! This proof ws taken from the proof 6.3 (left side ) in https://www.cs.unc.edu/~reiter/papers/2004/IJIS.pdf
+//!
+//! Statement: (c, pk, Q, G)
+//! witness (x, r, sk) such that Q = xG, c = Enc(pk, x, r) and Dec(sk, c) = x.
+//! note that because of the range proof, the proof has a slack in the range: x in [-q^3, q^3]
 
-/// Statement: (c, pk, Q, G)
-/// witness (x, r, sk) such that Q = xG, c = Enc(pk, x, r) and Dec(sk, c) = x.
-/// note that because of the range proof, the proof has a slack in the range: x in [-q^3, q^3]
-use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::{Samplable, ZeroizeBN};
 use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::elliptic::curves::traits::ECPoint;
 use curv::elliptic::curves::traits::ECScalar;
 use curv::{BigInt, FE, GE};
 use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroize;
+
+use crate::utilities::secret::{zeroize_decryption_key, Redacted};
+use crate::Error;
+use crate::ErrorKind::InvalidPdlProof;
+use crate::Protocol::PdlWithSlack;
 
 #[derive(Clone)]
 pub struct PDLwSlackStatement {
@@ -47,7 +56,31 @@ pub struct PDLwSlackWitness {
     pub dk: DecryptionKey,
 }
 
-#[derive(Debug, Clone)]
+impl fmt::Debug for PDLwSlackWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PDLwSlackWitness")
+            .field("x", &Redacted)
+            .field("r", &Redacted)
+            .field("dk", &Redacted)
+            .finish()
+    }
+}
+
+impl Zeroize for PDLwSlackWitness {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+        self.r.zeroize_bn();
+        zeroize_decryption_key(&mut self.dk);
+    }
+}
+
+impl Drop for PDLwSlackWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PDLwSlackProof {
     z: BigInt,
     u1: GE,
@@ -118,7 +151,7 @@ impl PDLwSlackProof {
         }
     }
 
-    pub fn verify(&self, statement: &PDLwSlackStatement) -> Result<(), ()> {
+    pub fn verify(&self, statement: &PDLwSlackStatement) -> Result<(), Error> {
         let e = HSha256::create_hash(&[
             &statement.G.bytes_compressed_to_big_int(),
             &statement.Q.bytes_compressed_to_big_int(),
@@ -166,7 +199,7 @@ impl PDLwSlackProof {
         if &self.u1 == &u1_test && &self.u2 == &u2_test && &self.u3 == &u3_test {
             Ok(())
         } else {
-            Err(())
+            Err(Error::new(PdlWithSlack, "verify", InvalidPdlProof))
         }
     }
 }